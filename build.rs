@@ -3,48 +3,58 @@ extern crate cbindgen;
 use std::fs;
 use std::env;
 use std::path::Path;
-use cbindgen::DocumentationStyle;
 
 fn main() -> Result<(), Box<dyn std::error::Error>> {
     let crate_dir = env::var::<String>("CARGO_MANIFEST_DIR".into()).unwrap();
 
-    // Configure cbindgen
-    let config = cbindgen::Config {
-        language: cbindgen::Language::Cxx,
-        cpp_compat: true,
-        pragma_once: true,
-        tab_width: 4,
-        namespace: Some(String::from("swc")),
-        documentation_style: DocumentationStyle::Doxy,
-        documentation: true,
-        ..Default::default()
+    // Configure cbindgen; cbindgen.toml describes the C++ header in the `swc` namespace
+    let config = cbindgen::Config::from_file(Path::new(&crate_dir).join("cbindgen.toml"))?;
+
+    // The C header has no namespace to hide behind: it only declares the `swc_`-prefixed
+    // API, which covers every legacy `char*` function, and C enumerators are not scoped, so
+    // `swc_status::Ok` becomes `swc_status_Ok`
+    let c_config = cbindgen::Config {
+        language: cbindgen::Language::C,
+        namespace: None,
+        enumeration: cbindgen::EnumConfig {
+            prefix_with_name: true,
+            ..config.enumeration.clone()
+        },
+        ..config.clone()
     };
 
     // Generate bindings
-    match cbindgen::generate_with_config(crate_dir.clone(), config) {
-        Ok(bindings) => {
-            bindings.write_to_file("swc.h".to_string());
-            println!("cargo:rerun-if-changed=src/lib.rs");
-        },
-        Err(e) => {
-            eprintln!("cbindgen error(swc.h): {}", e);
-            std::process::exit(1);
+    for (config, header) in [(config, "swc.h"), (c_config, "swc_c.h")] {
+        let c = config.language == cbindgen::Language::C;
+        match cbindgen::generate_with_config(crate_dir.clone(), config) {
+            Ok(mut bindings) => {
+                if c {
+                    bindings.functions.retain(|f| f.path().name().starts_with("swc_"));
+                }
+                bindings.write_to_file(header);
+            },
+            Err(e) => {
+                eprintln!("cbindgen error({header}): {e}");
+                std::process::exit(1);
+            }
         }
     }
+    println!("cargo:rerun-if-changed=src");
+    println!("cargo:rerun-if-changed=cbindgen.toml");
 
     // Copy the built library to a convenient location
     let profile = build_target::Profile::current();
 
-    let target_dir = Path::new(&crate_dir).join("target".to_string());
+    let target_dir = Path::new(&crate_dir).join("target");
     let (src_lib, dest_lib) = if profile == build_target::Profile::Release {
         (
-            target_dir.join("release".to_string()).join("libswc.so".to_string()),
-            Path::new(&crate_dir).join("libswc.so".to_string())
+            target_dir.join("release").join("libswc.so"),
+            Path::new(&crate_dir).join("libswc.so")
         )
     } else {
         (
-            target_dir.join("debug".to_string()).join("libswc.so".to_string()),
-            Path::new(&crate_dir).join("libswc-devel.so".to_string())
+            target_dir.join("debug").join("libswc.so"),
+            Path::new(&crate_dir).join("libswc-devel.so")
         )
     };
 
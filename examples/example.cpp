@@ -1,4 +1,4 @@
-#include "../swc.h"
+#include "../swc.hpp"
 #include <iostream>
 
 int main(int argc, char const *argv[]) {
@@ -29,23 +29,18 @@ const world: User = {
 console.log(greet(world));
     )";
 
-    const char* file_name = "test.ts";
-    char *error;
-    auto code = swc::transpile(file_name, ts_code);
-    auto js_code = swc::minify_js(swc::compile_file(argv[1], error), error);
+    try {
+        auto code = swc::cxx::transpile("test.ts", ts_code);
 
-    std::cout << "\n" << YELLOW << UNDERLINE << "Input:\n" << RESET << ts_code << std::endl;
-    std::cout << "\n" << YELLOW << UNDERLINE << "Output:\n\n"<< RESET << code << std::endl;
+        std::cout << "\n" << YELLOW << UNDERLINE << "Input:\n" << RESET << ts_code << std::endl;
+        std::cout << "\n" << YELLOW << UNDERLINE << "Output:\n\n"<< RESET << code.code << std::endl;
 
-    if (error != nullptr) {
-        std::cerr << "\n" << RED << UNDERLINE << "Error:\n\n" << RESET << error << std::endl;
-        swc::free_string(error);
-    } else {
-        std::cout << "\n" << YELLOW << UNDERLINE << "Compiled from File and Minified:\n\n" << RESET << js_code << std::endl;
+        auto js_code = swc::cxx::minify(swc::cxx::compile_file(argv[1]).code);
+        std::cout << "\n" << YELLOW << UNDERLINE << "Compiled from File and Minified:\n\n" << RESET << js_code.code << std::endl;
+    } catch (const swc::cxx::Error& e) {
+        std::cerr << "\n" << RED << UNDERLINE << "Error:\n\n" << RESET << e.what() << std::endl;
+        return 1;
     }
 
-    swc::free_string(code);
-    swc::free_string(js_code);
-
     return 0;
-}
\ No newline at end of file
+}
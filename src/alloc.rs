@@ -0,0 +1,146 @@
+use crate::status::{Status, StatusExt};
+use anyhow::{anyhow, Error};
+use std::alloc::Layout;
+use std::ffi::c_void;
+use std::ptr;
+use std::sync::{Mutex, OnceLock, PoisonError};
+
+/// Allocates `size` bytes on behalf of the library, returning null on failure
+///
+/// `size` is never 0. Only strings and byte buffers are allocated through it, so the memory
+/// needs no particular alignment. A call that cannot allocate its output fails with
+/// `swc_status::Internal`.
+#[allow(non_camel_case_types)]
+pub type swc_alloc_fn =
+    Option<unsafe extern "C" fn(size: usize, user_data: *mut c_void) -> *mut c_void>;
+
+/// Releases memory returned by the matching `swc_alloc_fn`
+#[allow(non_camel_case_types)]
+pub type swc_free_fn = Option<unsafe extern "C" fn(ptr: *mut c_void, user_data: *mut c_void)>;
+
+/// Allocation functions installed by the host
+#[derive(Clone, Copy)]
+struct Allocator {
+    alloc: unsafe extern "C" fn(usize, *mut c_void) -> *mut c_void,
+    free: unsafe extern "C" fn(*mut c_void, *mut c_void),
+    user_data: *mut c_void,
+}
+
+// The host promises that its functions and `user_data` may be used from any thread.
+unsafe impl Send for Allocator {}
+unsafe impl Sync for Allocator {}
+
+/// The allocator installed with `swc_set_allocator`, read once by the first allocation
+static INSTALLED: Mutex<Option<Allocator>> = Mutex::new(None);
+
+/// The allocator every string and buffer handed out is allocated with; `None` is Rust's
+/// global allocator. Fixed by the first allocation so nothing is freed by the wrong one.
+static ACTIVE: OnceLock<Option<Allocator>> = OnceLock::new();
+
+fn active() -> Option<Allocator> {
+    *ACTIVE.get_or_init(|| *INSTALLED.lock().unwrap_or_else(PoisonError::into_inner))
+}
+
+fn layout(size: usize) -> Layout {
+    Layout::from_size_align(size, 1).expect("allocation size overflows")
+}
+
+/// Copies `bytes` into memory owned by the active allocator.
+///
+/// Never returns a null pointer, also not for empty input. The memory must be released with
+/// [`deallocate`] and the same length.
+///
+/// # Errors
+///
+/// Fails with [`Status::Internal`] if the allocator returns null. A host allocator, such as an
+/// arena with a budget, may refuse any request, so the caller reports this like any other
+/// failure.
+pub(crate) fn allocate(bytes: &[u8]) -> Result<*mut u8, Error> {
+    let size = bytes.len().max(1);
+    let ptr = match active() {
+        Some(allocator) => unsafe { (allocator.alloc)(size, allocator.user_data) as *mut u8 },
+        None => unsafe { std::alloc::alloc(layout(size)) },
+    };
+    if ptr.is_null() {
+        return Err(anyhow!("failed to allocate {size} bytes for the output"))
+            .status(Status::Internal);
+    }
+
+    unsafe { ptr::copy_nonoverlapping(bytes.as_ptr(), ptr, bytes.len()) };
+    Ok(ptr)
+}
+
+/// Releases memory returned by [`allocate`] for `len` bytes. A null pointer is ignored.
+pub(crate) fn deallocate(ptr: *mut u8, len: usize) {
+    if ptr.is_null() {
+        return;
+    }
+
+    match active() {
+        Some(allocator) => unsafe { (allocator.free)(ptr as *mut c_void, allocator.user_data) },
+        None => unsafe { std::alloc::dealloc(ptr, layout(len.max(1))) },
+    }
+}
+
+/// Makes the library allocate every string and buffer it returns with the host's functions.
+///
+/// This covers the `char*` results and error messages of the legacy functions, the strings
+/// inside `swc_result` and `swc_diagnostic`, and the contents of `swc_buffer`. The host may
+/// release such memory with its own `free` counterpart instead of `free_string` or
+/// `swc_buffer_free`; to keep a string of an `swc_result`, set the field to null before calling
+/// `swc_result_free()`. Result structs, diagnostic arrays and handles are still allocated by
+/// the library.
+///
+/// The allocator can only be installed before the library allocates anything, typically at
+/// startup. Passing null for both functions restores the default allocator. If `alloc`
+/// returns null, the call fails with `swc_status::Internal` instead of returning its output.
+///
+/// # Parameters
+///
+/// * `alloc` - Allocates memory, see `swc_alloc_fn`
+/// * `free` - Releases memory returned by `alloc`
+/// * `user_data` - Passed to every call of `alloc` and `free`; may be null
+///
+/// # Returns
+///
+/// `true` if the allocator was installed, `false` if the library already allocated memory
+/// or only one of `alloc` and `free` is null
+///
+/// # Safety
+///
+/// Both functions must be callable from any thread with `user_data`, and `free` must accept
+/// every pointer returned by `alloc`. They must stay valid until the process exits.
+///
+/// # Examples
+///
+/// ```c
+/// static void* arena_alloc(size_t size, void* arena) { return arena_push((Arena*)arena, size); }
+/// static void arena_free(void* ptr, void* arena) {} // released with the arena
+///
+/// swc_set_allocator(arena_alloc, arena_free, &arena);
+/// char* js = transpile("a.ts", "const a: number = 1;"); // lives in the arena
+/// ```
+#[no_mangle]
+pub extern "C" fn swc_set_allocator(
+    alloc: swc_alloc_fn,
+    free: swc_free_fn,
+    user_data: *mut c_void,
+) -> bool {
+    let allocator = match (alloc, free) {
+        (Some(alloc), Some(free)) => Some(Allocator {
+            alloc,
+            free,
+            user_data,
+        }),
+        (None, None) => None,
+        _ => return false,
+    };
+
+    // Holding the lock keeps the first allocation from reading it before it is replaced
+    let mut installed = INSTALLED.lock().unwrap_or_else(PoisonError::into_inner);
+    if ACTIVE.get().is_some() {
+        return false;
+    }
+    *installed = allocator;
+    true
+}
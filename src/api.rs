@@ -0,0 +1,205 @@
+//! Rust API of the library
+//!
+//! The same pipelines as the exported C functions, with owned inputs, typed options and a
+//! [`Result`] whose error carries the [`Status`] and every [`Diagnostic`] of the failed call.
+//!
+//! ```rust
+//! use swc::api::{self, File, TranspileOptions};
+//!
+//! let file = File::source("greet.ts", "const greet = (name: string) => `hi ${name}`;");
+//! let output = api::transpile(file, &TranspileOptions::default()).unwrap();
+//! assert!(output.code.contains("const greet = (name)=>`hi ${name}`;"));
+//!
+//! let error = api::transpile(File::path("missing.ts"), &TranspileOptions::default())
+//!     .unwrap_err();
+//! assert_eq!(error.status(), api::Status::FileNotFound);
+//! ```
+
+use crate::diagnostics::{with_diagnostics, Outcome};
+use crate::{compile_with_handler, minify_with, requested_source_maps};
+use std::fmt;
+use swc_common::GLOBALS;
+
+pub use crate::comments::Comments;
+pub use crate::diagnostics::{Diagnostic, Severity};
+pub use crate::env::{EnvOptions, PolyfillMode};
+pub use crate::mangle_cache::MangleCache;
+pub use crate::mangle_props::KeepQuoted;
+pub use crate::module::{ModuleFormat, ModuleInterop, ModuleOptions};
+pub use crate::options::{JsxRuntime, TranspileOptions};
+pub use crate::session::Session;
+pub use crate::source_map::SourceMapMode;
+pub use crate::stats::MinifyStats;
+pub use crate::status::Status;
+pub use crate::syntax::InputSyntax;
+pub use crate::{File, Output};
+pub use swc_common::FileName;
+/// The configuration types of swc, used by [`CompileOptions`]
+pub use swc_compiler::config;
+/// Options of swc's `minify()`, the typed form of its JSON options
+pub use swc_compiler::config::JsMinifyOptions as MinifyOptions;
+/// swc options, the typed form of `.swcrc`
+pub use swc_compiler::config::Options as CompileOptions;
+/// `true`, `false` or detailed options, e.g. for [`MinifyOptions::compress`]
+pub use swc_compiler::BoolOrDataConfig;
+pub use swc_ecma_ast::EsVersion;
+
+/// Why a call failed
+///
+/// Displays as the messages of all error diagnostics, one per line, with their locations.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Error {
+    status: Status,
+    message: String,
+    diagnostics: Vec<Diagnostic>,
+}
+
+impl Error {
+    /// Why the call failed; never [`Status::Ok`]
+    pub fn status(&self) -> Status {
+        self.status
+    }
+
+    /// The messages of all error diagnostics, one per line
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+
+    /// Every error and warning reported by the failed call, at least one of them an error
+    pub fn diagnostics(&self) -> &[Diagnostic] {
+        &self.diagnostics
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+impl std::error::Error for Error {}
+
+/// Turns an outcome into a `Result`, keeping the diagnostics of a failure.
+///
+/// Warnings of a successful call are dropped.
+pub(crate) fn into_result<T>(outcome: Outcome<T>) -> Result<T, Error> {
+    let message = outcome.error_message();
+    outcome.value.ok_or(Error {
+        status: outcome.status,
+        message,
+        diagnostics: outcome.diagnostics,
+    })
+}
+
+/// The error of a call that failed before swc reported anything, e.g. on invalid input.
+pub(crate) fn failure(error: anyhow::Error) -> Error {
+    into_result(Outcome::<()>::failure(error)).unwrap_err()
+}
+
+/// Transpiles TypeScript/TSX to JavaScript.
+///
+/// The Rust counterpart of `swc_transpile_with_options`. Use a [`Session`] to process many
+/// files with one source map.
+pub fn transpile(file: File, options: &TranspileOptions) -> Result<Output, Error> {
+    into_result(crate::transpile_outcome(Default::default(), file, options))
+}
+
+/// Compiles TypeScript/JavaScript with swc options.
+///
+/// The Rust counterpart of `swc_compile_js_with_config`. A `.swcrc` next to (or above) a file
+/// on disk is only merged in if `swcrc` is set, which [`CompileOptions::default`] leaves off
+/// unlike a JSON config. Options can also be deserialized from `.swcrc` JSON with `serde_json`.
+pub fn compile(file: File, options: &CompileOptions) -> Result<Output, Error> {
+    let compiler = swc_compiler::Compiler::new(Default::default());
+    GLOBALS.set(&Default::default(), || {
+        into_result(compile_in(&compiler, file, options))
+    })
+}
+
+/// Minifies JavaScript.
+///
+/// The Rust counterpart of `swc_minify_js_with_source_map`.
+pub fn minify(file: File, source_maps: SourceMapMode) -> Result<Output, Error> {
+    into_result(crate::minify(file, source_maps, None))
+}
+
+/// Minifies JavaScript with every minifier option given by the caller.
+///
+/// The Rust counterpart of `swc_minify_js_with_options`. Unlike in JSON,
+/// [`MinifyOptions::default`] turns off `inline_sources_content`; a map enabled with
+/// `source_map` is returned in [`Output::map`]. swc's options have no field for the
+/// `keep_quoted` setting of property mangling, so it is passed as `keep_quoted`.
+pub fn minify_with_options(
+    file: File,
+    options: &MinifyOptions,
+    keep_quoted: KeepQuoted,
+) -> Result<Output, Error> {
+    into_result(minify_with(
+        file,
+        options,
+        keep_quoted,
+        requested_source_maps(options),
+        None,
+        false,
+    ))
+}
+
+/// Minifies JavaScript with names shared through `cache`.
+///
+/// The Rust counterpart of `swc_minify_js_with_cache`, see [`minify_with_options`].
+pub fn minify_with_cache(
+    file: File,
+    options: &MinifyOptions,
+    keep_quoted: KeepQuoted,
+    cache: &MangleCache,
+) -> Result<Output, Error> {
+    into_result(minify_with(
+        file,
+        options,
+        keep_quoted,
+        requested_source_maps(options),
+        Some(cache),
+        false,
+    ))
+}
+
+/// Minifies JavaScript, keeping the comments `comments` selects.
+///
+/// The Rust counterpart of `swc_minify_js_with_comments`. The license comments left in the
+/// code are also listed in [`Output::licenses`].
+pub fn minify_with_comments(file: File, comments: &Comments) -> Result<Output, Error> {
+    crate::comments::minify_with_comments(file, comments)
+        .map_err(failure)
+        .and_then(into_result)
+}
+
+/// Minifies JavaScript and measures what the minification achieved.
+///
+/// The Rust counterpart of `swc_minify_js_with_stats`, see [`minify_with_options`]. The
+/// stats are in [`Output::stats`]; estimating the compressed sizes takes about as long as
+/// minifying.
+pub fn minify_with_stats(
+    file: File,
+    options: &MinifyOptions,
+    keep_quoted: KeepQuoted,
+) -> Result<Output, Error> {
+    into_result(minify_with(
+        file,
+        options,
+        keep_quoted,
+        requested_source_maps(options),
+        None,
+        true,
+    ))
+}
+
+/// Like [`compile`], but uses `compiler` and runs within the `GLOBALS` set by the caller.
+pub(crate) fn compile_in(
+    compiler: &swc_compiler::Compiler,
+    file: File,
+    options: &CompileOptions,
+) -> Outcome<Output> {
+    with_diagnostics(compiler.cm.clone(), |handler| {
+        compile_with_handler(compiler, handler, file, options, None)
+    })
+}
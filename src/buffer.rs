@@ -0,0 +1,99 @@
+use crate::alloc;
+use crate::diagnostics::Outcome;
+use crate::ffi::{catch_panic, guard};
+use crate::last_error;
+use crate::reporter;
+use crate::Output;
+use anyhow::Error;
+use std::ptr;
+
+/// A length-delimited byte buffer owned by the library
+///
+/// Unlike the C strings returned elsewhere, the contents are not NUL-terminated and may
+/// contain NUL bytes. A failed operation returns a buffer whose `ptr` is null; a successful
+/// one always has a non-null `ptr`, even if `len` is 0.
+///
+/// Buffers must be released with `swc_buffer_free`.
+#[allow(non_camel_case_types)]
+#[repr(C)]
+pub struct swc_buffer {
+    pub ptr: *mut u8,
+    pub len: usize,
+}
+
+impl swc_buffer {
+    /// The buffer returned on failure.
+    pub(crate) fn null() -> Self {
+        swc_buffer {
+            ptr: ptr::null_mut(),
+            len: 0,
+        }
+    }
+
+    /// Copies `bytes` into memory from the host's allocator if one is installed.
+    pub(crate) fn new(bytes: &[u8]) -> Result<Self, Error> {
+        Ok(swc_buffer {
+            ptr: alloc::allocate(bytes)?,
+            len: bytes.len(),
+        })
+    }
+}
+
+/// Hands the outcome of an operation to a caller of the buffer API.
+///
+/// On failure, including a failure to allocate the output, every error message is written to
+/// `error` (if it is not null and the message can be allocated) and a null buffer is returned.
+pub(crate) fn outcome_to_buffer(error: *mut swc_buffer, outcome: Outcome<Output>) -> swc_buffer {
+    let outcome = guard(|| Ok(outcome.try_map(|output| swc_buffer::new(output.code.as_bytes()))));
+    reporter::report_rest(&outcome);
+    last_error::record(&outcome);
+    let message = outcome.error_message();
+    match outcome.value {
+        Some(buffer) => buffer,
+        None => {
+            if let Some(error) = unsafe { error.as_mut() } {
+                *error = swc_buffer::new(message.as_bytes()).unwrap_or_else(|_| swc_buffer::null());
+            }
+            swc_buffer::null()
+        }
+    }
+}
+
+/// Frees a buffer returned by any `*_buf` entry point.
+///
+/// Freeing a buffer whose `ptr` is null is a no-op.
+///
+/// # Parameters
+///
+/// * `buffer` - Buffer previously returned by this library
+///
+/// # Safety
+///
+/// This function is unsafe because:
+/// * The buffer must have been returned by this library and not freed already
+/// * `ptr` and `len` must not have been modified
+/// * The memory must not be used after this call
+///
+/// # Examples
+///
+/// ```c
+/// swc_buffer error = {0};
+/// swc_buffer js = swc_compile_js_buf((const uint8_t*)src.data(), src.size(), &error);
+/// if (js.ptr) {
+///     std::string_view code((const char*)js.ptr, js.len);
+///     swc_buffer_free(js);
+/// } else {
+///     swc_buffer_free(error);
+/// }
+/// ```
+#[no_mangle]
+pub extern "C" fn swc_buffer_free(buffer: swc_buffer) {
+    if buffer.ptr.is_null() {
+        return;
+    }
+
+    let _ = catch_panic(|| {
+        alloc::deallocate(buffer.ptr, buffer.len);
+        Ok(())
+    });
+}
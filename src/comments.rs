@@ -0,0 +1,177 @@
+use crate::diagnostics::Outcome;
+use crate::ffi::{guard, input_str};
+use crate::mangle_props::KeepQuoted;
+use crate::options::optional_str;
+use crate::result::{into_result_ptr, swc_result};
+use crate::source_map::SourceMapMode;
+use crate::status::{Status, StatusExt};
+use crate::{input_name, minify_options, minify_with, File, Output};
+use anyhow::{anyhow, Context, Error};
+use std::os::raw::c_char;
+use swc_common::comments::{Comment, CommentKind, SingleThreadedComments};
+use swc_compiler::config::JsMinifyCommentOption;
+use swc_compiler::BoolOrDataConfig;
+use swc_config::regex_js::CachedJsRegex;
+
+/// Which comments minification keeps
+///
+/// License comments are `/*! ... */` blocks and comments containing `@license`, `@preserve`
+/// or `@copyright`, the ones terser keeps by default.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub enum Comments {
+    /// Every comment is removed
+    #[default]
+    None,
+    /// License comments are kept
+    Licenses,
+    /// Every comment is kept
+    All,
+    /// Comments whose text matches the regular expression, in JavaScript syntax, are kept
+    Regex(String),
+}
+
+impl Comments {
+    /// The `format.comments` option of swc for this policy
+    pub(crate) fn to_option(&self) -> Result<BoolOrDataConfig<JsMinifyCommentOption>, Error> {
+        let option = match self {
+            Comments::None => return Ok(BoolOrDataConfig::from_bool(false)),
+            Comments::Licenses => JsMinifyCommentOption::PreserveSomeComments,
+            Comments::All => JsMinifyCommentOption::PreserveAllComments,
+            Comments::Regex(pattern) => {
+                let regex = CachedJsRegex::new(pattern.clone())
+                    .with_context(|| format!("invalid comment pattern `{pattern}`"))
+                    .status(Status::InvalidConfig)?;
+                JsMinifyCommentOption::PreserveRegexComments { regex }
+            }
+        };
+        Ok(BoolOrDataConfig::from_obj(option))
+    }
+}
+
+/// Which comments minification keeps, see `swc_minify_js_with_comments`
+///
+/// * `None` - Every comment is removed
+/// * `Licenses` - Block comments starting with `!` and comments containing `@license`,
+///   `@preserve` or `@copyright` are kept
+/// * `All` - Every comment is kept
+/// * `Regex` - Comments whose text matches the given regular expression are kept
+#[allow(non_camel_case_types)]
+#[repr(C)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum swc_comments {
+    None,
+    Licenses,
+    All,
+    Regex,
+}
+
+/// Whether `comment` is a license comment, by the rule swc's `"some"` shares with terser
+fn is_license(comment: &Comment) -> bool {
+    ["@lic", "@preserve", "@copyright"]
+        .iter()
+        .any(|tag| comment.text.contains(tag))
+        || (comment.kind == CommentKind::Block && comment.text.starts_with('!'))
+}
+
+/// The license comments in `comments` as written, in source order and each text only once
+pub(crate) fn licenses(comments: &SingleThreadedComments) -> Vec<String> {
+    let (leading, trailing) = comments.borrow_all();
+    let mut found = leading
+        .iter()
+        .chain(trailing.iter())
+        .flat_map(|(_, comments)| comments)
+        .filter(|comment| is_license(comment))
+        .map(|comment| {
+            let text = match comment.kind {
+                CommentKind::Block => format!("/*{}*/", comment.text),
+                CommentKind::Line => format!("//{}", comment.text),
+            };
+            (comment.span.lo, text)
+        })
+        .collect::<Vec<_>>();
+    found.sort();
+
+    let mut licenses: Vec<String> = Vec::new();
+    for (_, text) in found {
+        if !licenses.contains(&text) {
+            licenses.push(text);
+        }
+    }
+    licenses
+}
+
+/// Minifies JavaScript code, keeping the comments `comments` selects.
+///
+/// Behaves like `swc_minify_js`, which removes every comment. The license comments that
+/// remain in the code are also returned separately in `licenses`, e.g. for a third-party
+/// notices file.
+///
+/// # Parameters
+///
+/// * `code` - C string pointer containing the JavaScript code to minify
+/// * `filename` - Name of the input, used in diagnostics; may be null
+/// * `comments` - Which comments to keep, see `swc_comments`
+/// * `pattern` - Regular expression in JavaScript syntax, without slashes, that comment texts
+///   must match; required for `swc_comments::Regex`, ignored otherwise and may be null
+///
+/// # Returns
+///
+/// Pointer to an `swc_result`. `code` is null if minification failed, in which case
+/// `diagnostics` contains at least one error.
+///
+/// # Safety
+///
+/// This function is unsafe because:
+/// * It dereferences raw pointers
+/// * It allocates memory that must be freed by the caller using `swc_result_free()`
+///
+/// # Examples
+///
+/// ```c
+/// swc_result* result = swc_minify_js_with_comments(js, "vendor.js", swc_comments::Licenses, NULL);
+/// for (size_t i = 0; i < result->licenses_len; i++) {
+///     fprintf(notices, "%s\n\n", result->licenses[i]);
+/// }
+/// swc_result_free(result);
+/// ```
+#[no_mangle]
+pub extern "C" fn swc_minify_js_with_comments(
+    code: *const c_char,
+    filename: *const c_char,
+    comments: swc_comments,
+    pattern: *const c_char,
+) -> *mut swc_result {
+    into_result_ptr(guard(|| {
+        let input = input_str(code, "code")?;
+        let comments = match comments {
+            swc_comments::None => Comments::None,
+            swc_comments::Licenses => Comments::Licenses,
+            swc_comments::All => Comments::All,
+            swc_comments::Regex => Comments::Regex(
+                optional_str(pattern)
+                    .ok_or_else(|| anyhow!("`pattern` must not be null for `swc_comments::Regex`"))
+                    .status(Status::InvalidArgument)?,
+            ),
+        };
+        let file = File::FileName(input_name(filename), input.into());
+        minify_with_comments(file, &comments)
+    }))
+}
+
+/// Minifies `file` like [`crate::minify`] without a source map, keeping the comments
+/// `comments` selects.
+pub(crate) fn minify_with_comments(
+    file: File,
+    comments: &Comments,
+) -> Result<Outcome<Output>, Error> {
+    let mut options = minify_options(SourceMapMode::None, None);
+    options.format.comments = comments.to_option()?;
+    Ok(minify_with(
+        file,
+        &options,
+        KeepQuoted::default(),
+        SourceMapMode::None,
+        None,
+        false,
+    ))
+}
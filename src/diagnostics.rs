@@ -0,0 +1,254 @@
+use crate::reporter::{self, render_frame};
+use crate::status::Status;
+use anyhow::Error;
+use std::sync::{Arc, Mutex};
+use swc_common::errors::{DiagnosticBuilder, Emitter, Handler, Level, HANDLER};
+use swc_common::sync::Lrc;
+use swc_common::{SourceMap, Span};
+
+/// Severity of a [`Diagnostic`]
+///
+/// Mirrors the subset of `swc_common::errors::Level` that is meaningful to a host application.
+/// Fatal and bug levels are reported as `Error`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Severity {
+    Error,
+    Warning,
+    Note,
+    Help,
+}
+
+impl From<Level> for Severity {
+    fn from(level: Level) -> Self {
+        match level {
+            Level::Warning => Severity::Warning,
+            Level::Note => Severity::Note,
+            Level::Help => Severity::Help,
+            _ => Severity::Error,
+        }
+    }
+}
+
+/// A single error or warning reported while processing a file
+///
+/// Positions are resolved against the source map at the time the diagnostic is emitted, so the
+/// diagnostic stays meaningful after the source map is dropped.
+///
+/// * `line` is 1-based, `column` is 0-based and counted in characters
+/// * `span_start` and `span_end` are byte offsets relative to the start of `file`
+/// * `file`, `line`, `column` and the span are `None`/`0` if the diagnostic has no location
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Diagnostic {
+    pub severity: Severity,
+    pub message: String,
+    pub file: Option<String>,
+    pub line: u32,
+    pub column: u32,
+    pub span_start: u32,
+    pub span_end: u32,
+    pub code: Option<String>,
+}
+
+impl Diagnostic {
+    /// Creates an error diagnostic without a location, e.g. for I/O failures.
+    pub fn error(message: impl Into<String>) -> Self {
+        Diagnostic {
+            severity: Severity::Error,
+            message: message.into(),
+            file: None,
+            line: 0,
+            column: 0,
+            span_start: 0,
+            span_end: 0,
+            code: None,
+        }
+    }
+
+    /// Converts an swc diagnostic, resolving its primary span against `cm`.
+    fn from_swc(cm: &SourceMap, d: &swc_common::errors::Diagnostic) -> Self {
+        let mut diagnostic = Diagnostic::error(d.message());
+        diagnostic.severity = d.level.into();
+        diagnostic.code = d.code.as_ref().map(|code| match code {
+            swc_common::errors::DiagnosticId::Error(s) => s.clone(),
+            swc_common::errors::DiagnosticId::Lint(s) => s.clone(),
+        });
+
+        if let Some(span) = d.span.primary_span().filter(|s| !s.is_dummy()) {
+            diagnostic.locate(cm, span);
+        }
+
+        diagnostic
+    }
+
+    fn locate(&mut self, cm: &SourceMap, span: Span) {
+        let Ok(loc) = cm.try_lookup_char_pos(span.lo) else {
+            return;
+        };
+
+        self.file = Some(loc.file.name.to_string());
+        self.line = loc.line as u32;
+        self.column = loc.col.0 as u32;
+        self.span_start = (span.lo - loc.file.start_pos).0;
+        self.span_end = (span.hi.max(span.lo) - loc.file.start_pos).0;
+    }
+
+    pub fn is_error(&self) -> bool {
+        self.severity == Severity::Error
+    }
+}
+
+/// The outcome of an operation run through [`with_diagnostics`]
+///
+/// `value` is `None` if the operation failed, in which case `diagnostics` contains at least one
+/// error and `status` tells why. Warnings may be present in either case.
+pub(crate) struct Outcome<T> {
+    pub value: Option<T>,
+    pub status: Status,
+    pub diagnostics: Vec<Diagnostic>,
+    /// How many of the first `diagnostics` were already handed to the host's callback
+    pub reported: usize,
+}
+
+impl<T> Outcome<T> {
+    /// A successful outcome without diagnostics.
+    pub fn success(value: T) -> Self {
+        Outcome {
+            value: Some(value),
+            status: Status::Ok,
+            diagnostics: Vec::new(),
+            reported: 0,
+        }
+    }
+
+    /// A failed outcome carrying `error` as its only diagnostic.
+    pub fn failure(error: Error) -> Self {
+        Outcome {
+            value: None,
+            status: Status::of(&error).unwrap_or(Status::Internal),
+            diagnostics: vec![Diagnostic::error(format!("{error:#}"))],
+            reported: 0,
+        }
+    }
+
+    /// Converts the value of a successful outcome.
+    pub fn map<U>(self, f: impl FnOnce(T) -> U) -> Outcome<U> {
+        Outcome {
+            value: self.value.map(f),
+            status: self.status,
+            diagnostics: self.diagnostics,
+            reported: self.reported,
+        }
+    }
+
+    /// Converts the value of a successful outcome, failing the outcome if `f` fails.
+    pub fn try_map<U>(self, f: impl FnOnce(T) -> Result<U, Error>) -> Outcome<U> {
+        let Outcome {
+            value,
+            status,
+            mut diagnostics,
+            reported,
+        } = self;
+        match value.map(f).transpose() {
+            Ok(value) => Outcome {
+                value,
+                status,
+                diagnostics,
+                reported,
+            },
+            Err(error) => {
+                let failure = Outcome::failure(error);
+                diagnostics.extend(failure.diagnostics);
+                Outcome {
+                    diagnostics,
+                    reported,
+                    ..failure
+                }
+            }
+        }
+    }
+
+    /// Joins the messages of all error diagnostics, one per line.
+    pub fn error_message(&self) -> String {
+        self.diagnostics
+            .iter()
+            .filter(|d| d.is_error())
+            .map(|d| match &d.file {
+                Some(file) => format!("{}:{}:{}: {}", file, d.line, d.column + 1, d.message),
+                None => d.message.clone(),
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+}
+
+/// Emitter that converts every diagnostic into a [`Diagnostic`] as soon as it is emitted and
+/// passes it on to the host's callback
+struct Collector {
+    cm: Lrc<SourceMap>,
+    diagnostics: Arc<Mutex<Vec<Diagnostic>>>,
+}
+
+impl Emitter for Collector {
+    fn emit(&mut self, db: &mut DiagnosticBuilder<'_>) {
+        let diagnostic = Diagnostic::from_swc(&self.cm, db);
+        reporter::report(&diagnostic, || {
+            diagnostic
+                .file
+                .is_some()
+                .then(|| render_frame(&self.cm, db))
+        });
+        self.diagnostics
+            .lock()
+            .expect("failed to access the diagnostics lock")
+            .push(diagnostic);
+    }
+}
+
+/// Runs `op` with a handler that records every emitted diagnostic
+///
+/// This is the structured counterpart of `swc::try_with_handler`. The operation counts as failed
+/// if it returns an error or if any error diagnostic was emitted. An error returned by `op` is
+/// only added as a diagnostic of its own if nothing more specific was emitted, so callers are
+/// not handed a generic "failed to parse" next to the actual syntax error. Error diagnostics
+/// emitted by an otherwise successful operation are reported as [`Status::Transform`].
+pub(crate) fn with_diagnostics<T, F>(cm: Lrc<SourceMap>, op: F) -> Outcome<T>
+where
+    F: FnOnce(&Handler) -> Result<T, Error>,
+{
+    let diagnostics = Arc::new(Mutex::new(Vec::new()));
+    let handler = Handler::with_emitter(
+        true,
+        false,
+        Box::new(Collector {
+            cm,
+            diagnostics: diagnostics.clone(),
+        }),
+    );
+
+    let result = HANDLER.set(&handler, || op(&handler));
+    drop(handler);
+
+    let mut diagnostics = Arc::try_unwrap(diagnostics)
+        .map(|m| m.into_inner().unwrap_or_default())
+        .unwrap_or_default();
+    let has_errors = diagnostics.iter().any(Diagnostic::is_error);
+    let reported = diagnostics.len();
+
+    let (value, status) = match result {
+        Ok(value) if !has_errors => (Some(value), Status::Ok),
+        Ok(_) => (None, Status::Transform),
+        Err(e) => {
+            if !has_errors {
+                diagnostics.push(Diagnostic::error(format!("{e:#}")));
+            }
+            (None, Status::of(&e).unwrap_or(Status::Internal))
+        }
+    };
+
+    Outcome {
+        value,
+        status,
+        diagnostics,
+        reported,
+    }
+}
@@ -0,0 +1,122 @@
+use crate::status::{Status, StatusExt};
+use anyhow::{anyhow, Error};
+use preset_env_base::query::{targets_to_versions, Query};
+use std::collections::BTreeMap;
+use swc_ecma_preset_env::{
+    Config as EnvConfig, EnvConfig as BuiltEnvConfig, Mode, Targets, Version,
+};
+
+/// Settings for lowering syntax and adding polyfills for a set of target environments
+///
+/// Targets are resolved with the compatibility data bundled into the library, so no
+/// network access or `.browserslistrc` is involved.
+#[derive(Clone, Debug, Default)]
+pub struct EnvOptions {
+    /// Browserslist query, e.g. `> 0.5%, last 2 versions, not dead`. `None` uses
+    /// `defaults` unless engine versions are given.
+    pub query: Option<String>,
+    /// Minimum engine versions keyed by engine, e.g. `chrome` => `80` or `node` => `14.17`.
+    /// Takes precedence over the version the query resolves for the same engine.
+    pub engines: BTreeMap<String, String>,
+    /// Which core-js polyfill imports are added
+    pub polyfills: PolyfillMode,
+    /// core-js version the polyfill imports are written for, e.g. `3.38`; defaults to `3`
+    pub core_js: Option<String>,
+    /// Produces simpler but less spec-compliant code
+    pub loose: bool,
+}
+
+/// Which core-js polyfill imports are added
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum PolyfillMode {
+    /// No polyfills
+    #[default]
+    None,
+    /// Imports the polyfills for the built-ins a file uses that the targets lack
+    Usage,
+    /// Replaces `import "core-js"` by the polyfills the targets lack
+    Entry,
+}
+
+/// Which core-js polyfill imports are added
+///
+/// * `None` - No polyfills
+/// * `Usage` - Imports the polyfills for the built-ins a file uses that the targets lack
+/// * `Entry` - Replaces `import "core-js"` by the polyfills the targets lack
+#[allow(non_camel_case_types)]
+#[repr(C)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum swc_polyfill_mode {
+    None,
+    Usage,
+    Entry,
+}
+
+impl From<swc_polyfill_mode> for PolyfillMode {
+    fn from(mode: swc_polyfill_mode) -> Self {
+        match mode {
+            swc_polyfill_mode::None => PolyfillMode::None,
+            swc_polyfill_mode::Usage => PolyfillMode::Usage,
+            swc_polyfill_mode::Entry => PolyfillMode::Entry,
+        }
+    }
+}
+
+/// Parses a version such as `80`, `14.17` or `3.38.1`.
+///
+/// Rejects what the preset would panic on.
+fn parse_version(version: &str) -> Result<Version, Error> {
+    let parts: Vec<_> = version.split('.').collect();
+    let valid = parts.len() <= 3
+        && parts
+            .iter()
+            .all(|part| !part.is_empty() && part.bytes().all(|b| b.is_ascii_digit()));
+    valid
+        .then(|| version.parse().ok())
+        .flatten()
+        .ok_or_else(|| anyhow!("invalid version `{version}`"))
+        .status(Status::InvalidConfig)
+}
+
+impl EnvOptions {
+    /// Resolves the targets and builds the configuration of the preset.
+    pub(crate) fn build(&self) -> Result<BuiltEnvConfig, Error> {
+        let query = Query::Single(self.query.clone().unwrap_or_else(|| "defaults".into()));
+        let resolved = targets_to_versions(Some(Targets::Query(query.clone())), None)
+            .status(Status::InvalidConfig)?;
+
+        let targets = if self.engines.is_empty() {
+            Targets::Query(query)
+        } else {
+            let mut versions = match &self.query {
+                Some(_) => *resolved.versions,
+                None => Default::default(),
+            };
+            for (engine, version) in &self.engines {
+                if !versions.iter().any(|(name, _)| name == engine) {
+                    return Err(anyhow!("unknown engine `{engine}`")).status(Status::InvalidConfig);
+                }
+                versions.insert(engine, Some(parse_version(version)?));
+            }
+            Targets::Versions(versions)
+        };
+
+        let core_js = self.core_js.as_deref().map(parse_version).transpose()?;
+        if core_js.is_some_and(|v| !matches!(v.major, 2 | 3)) {
+            return Err(anyhow!("core-js 2 or 3 is required")).status(Status::InvalidConfig);
+        }
+
+        Ok(EnvConfig {
+            mode: match self.polyfills {
+                PolyfillMode::None => None,
+                PolyfillMode::Usage => Some(Mode::Usage),
+                PolyfillMode::Entry => Some(Mode::Entry),
+            },
+            core_js,
+            loose: self.loose,
+            targets: Some(targets),
+            ..Default::default()
+        }
+        .into())
+    }
+}
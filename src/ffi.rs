@@ -0,0 +1,94 @@
+use crate::diagnostics::Outcome;
+use crate::status::{Status, StatusExt};
+use anyhow::{anyhow, Error};
+use std::any::Any;
+use std::panic::{self, AssertUnwindSafe};
+use std::{ffi::CStr, os::raw::c_char};
+
+/// Borrows a required C string argument as `&str`.
+///
+/// # Errors
+///
+/// Returns an error naming the argument if the pointer is null or the string is not valid
+/// UTF-8, instead of panicking on the FFI boundary.
+pub(crate) fn input_str<'a>(ptr: *const c_char, name: &str) -> Result<&'a str, Error> {
+    if ptr.is_null() {
+        return Err(anyhow!("`{name}` must not be null")).status(Status::InvalidArgument);
+    }
+
+    unsafe { CStr::from_ptr(ptr) }
+        .to_str()
+        .map_err(|e| anyhow!("`{name}` is not valid UTF-8: {e}"))
+        .status(Status::InvalidUtf8)
+}
+
+/// Borrows a required length-delimited argument as `&str`.
+///
+/// The bytes may contain NUL characters. A null pointer is only accepted for an empty input.
+///
+/// # Errors
+///
+/// Returns an error naming the argument if the pointer is null while `len` is not 0, or if
+/// the bytes are not valid UTF-8.
+pub(crate) fn input_bytes<'a>(ptr: *const u8, len: usize, name: &str) -> Result<&'a str, Error> {
+    if ptr.is_null() {
+        return match len {
+            0 => Ok(""),
+            _ => Err(anyhow!("`{name}` must not be null")).status(Status::InvalidArgument),
+        };
+    }
+
+    std::str::from_utf8(unsafe { std::slice::from_raw_parts(ptr, len) })
+        .map_err(|e| anyhow!("`{name}` is not valid UTF-8: {e}"))
+        .status(Status::InvalidUtf8)
+}
+
+/// Extracts the message of a panic payload.
+fn panic_message(payload: &(dyn Any + Send)) -> &str {
+    if let Some(message) = payload.downcast_ref::<&str>() {
+        message
+    } else if let Some(message) = payload.downcast_ref::<String>() {
+        message
+    } else {
+        "unknown panic"
+    }
+}
+
+/// Runs `f`, turning a panic into an error so it never unwinds into the host.
+pub(crate) fn catch_panic<T>(f: impl FnOnce() -> Result<T, Error>) -> Result<T, Error> {
+    match panic::catch_unwind(AssertUnwindSafe(f)) {
+        Ok(result) => result,
+        Err(payload) => {
+            Err(anyhow!("internal error: {}", panic_message(&*payload))).status(Status::Internal)
+        }
+    }
+}
+
+/// Panic guard for the body of an exported function.
+///
+/// Conversion failures returned by `f` and panics raised inside it are reported as a failed
+/// [`Outcome`], so every export answers through its normal error path.
+pub(crate) fn guard<T>(f: impl FnOnce() -> Result<Outcome<T>, Error>) -> Outcome<T> {
+    catch_panic(f).unwrap_or_else(Outcome::failure)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_catch_panic_reports_message() {
+        let result: Result<(), Error> = catch_panic(|| panic!("boom"));
+        let error = result.unwrap_err();
+        assert_eq!(format!("{error:#}"), "internal error: boom");
+        assert_eq!(Status::of(&error), Some(Status::Internal));
+    }
+
+    #[test]
+    fn test_guard_turns_errors_into_failed_outcome() {
+        let outcome = guard::<()>(|| Err(anyhow!("`code` must not be null")));
+        assert!(outcome.value.is_none());
+        assert_eq!(outcome.status, Status::Internal);
+        assert_eq!(outcome.error_message(), "`code` must not be null");
+    }
+}
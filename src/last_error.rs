@@ -0,0 +1,165 @@
+use crate::diagnostics::Outcome;
+use crate::ffi::catch_panic;
+use crate::result::{
+    free_c_string, free_diagnostics, into_diagnostics_ptr, swc_diagnostic, to_c_string,
+};
+use crate::status::{swc_status, Status};
+use std::cell::RefCell;
+use std::{os::raw::c_char, ptr};
+
+/// The error and diagnostics of the most recent call on a thread
+///
+/// Owns its C strings and diagnostics, which stay valid until the next call replaces them.
+struct LastError {
+    status: Status,
+    message: *mut c_char,
+    diagnostics: *mut swc_diagnostic,
+    diagnostics_len: usize,
+}
+
+impl LastError {
+    const fn empty() -> Self {
+        LastError {
+            status: Status::Ok,
+            message: ptr::null_mut(),
+            diagnostics: ptr::null_mut(),
+            diagnostics_len: 0,
+        }
+    }
+}
+
+impl Drop for LastError {
+    fn drop(&mut self) {
+        free_c_string(self.message);
+        free_diagnostics(self.diagnostics, self.diagnostics_len);
+    }
+}
+
+thread_local! {
+    static LAST_ERROR: RefCell<LastError> = const { RefCell::new(LastError::empty()) };
+}
+
+/// Remembers the outcome of an export as the calling thread's last error.
+///
+/// A successful outcome clears the error message but still records its warnings. The message
+/// and diagnostics are left out if they cannot be converted to C strings.
+pub(crate) fn record<T>(outcome: &Outcome<T>) {
+    let message = match outcome.value {
+        Some(_) => Ok(ptr::null_mut()),
+        None => catch_panic(|| to_c_string(outcome.error_message())),
+    };
+    let message = message.unwrap_or(ptr::null_mut());
+    let diagnostics = catch_panic(|| into_diagnostics_ptr(outcome.diagnostics.clone()));
+    let (diagnostics, diagnostics_len) = diagnostics.unwrap_or((ptr::null_mut(), 0));
+
+    // The previous value is dropped, and its strings freed, by the assignment.
+    LAST_ERROR.with(|last| {
+        *last.borrow_mut() = LastError {
+            status: outcome.status,
+            message,
+            diagnostics,
+            diagnostics_len,
+        }
+    });
+}
+
+/// Returns the error message of the most recent call on the calling thread.
+///
+/// Every compile, minify and transpile export records its outcome per thread, so this is
+/// the only way to learn why functions without an error parameter, such as `transpile`
+/// or `transpile_file`, returned null.
+///
+/// # Returns
+///
+/// * If the most recent call failed: Every error message, one per line
+/// * Otherwise: Null pointer
+///
+/// # Safety
+///
+/// The returned string is owned by the library. It must not be freed and is only valid
+/// until the next call to a compile, minify or transpile function on the same thread.
+///
+/// # Examples
+///
+/// ```c
+/// char* js = transpile("app.ts", code);
+/// if (!js) {
+///     show_error_dialog(swc_last_error());
+/// }
+/// ```
+#[no_mangle]
+pub extern "C" fn swc_last_error() -> *const c_char {
+    LAST_ERROR
+        .try_with(|last| last.borrow().message as *const c_char)
+        .unwrap_or(ptr::null())
+}
+
+/// Returns the status of the most recent call on the calling thread.
+///
+/// This is the status code of the functions that do not return an `swc_result`, such as
+/// `compile_js`, `transpile` or the `*_buf` functions.
+///
+/// # Returns
+///
+/// `swc_status::Ok` if the most recent call succeeded or no call was made yet, otherwise
+/// the reason it failed
+///
+/// # Examples
+///
+/// ```c
+/// char* js = transpile_file("app.ts");
+/// if (!js && swc_last_status() == swc_status::FileNotFound) {
+///     // Skip files deleted since the build started
+/// }
+/// ```
+#[no_mangle]
+pub extern "C" fn swc_last_status() -> swc_status {
+    LAST_ERROR
+        .try_with(|last| last.borrow().status)
+        .unwrap_or(Status::Ok)
+        .into()
+}
+
+/// Returns the diagnostics of the most recent call on the calling thread.
+///
+/// Unlike `swc_last_error()`, this also reports the warnings of a successful call.
+///
+/// # Parameters
+///
+/// * `len` - Receives the number of diagnostics; ignored if null
+///
+/// # Returns
+///
+/// Pointer to an array of `*len` diagnostics, or null if there are none
+///
+/// # Safety
+///
+/// The array is owned by the library. It must not be freed and is only valid until the
+/// next call to a compile, minify or transpile function on the same thread.
+///
+/// # Examples
+///
+/// ```c
+/// size_t len = 0;
+/// const swc_diagnostic* diagnostics = swc_last_diagnostics(&len);
+/// for (size_t i = 0; i < len; i++) {
+///     printf("%s:%u: %s\n", diagnostics[i].file, diagnostics[i].line, diagnostics[i].message);
+/// }
+/// ```
+#[no_mangle]
+pub extern "C" fn swc_last_diagnostics(len: *mut usize) -> *const swc_diagnostic {
+    let (diagnostics, diagnostics_len) = LAST_ERROR
+        .try_with(|last| {
+            let last = last.borrow();
+            (
+                last.diagnostics as *const swc_diagnostic,
+                last.diagnostics_len,
+            )
+        })
+        .unwrap_or((ptr::null(), 0));
+
+    if let Some(len) = unsafe { len.as_mut() } {
+        *len = diagnostics_len;
+    }
+    diagnostics
+}
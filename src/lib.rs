@@ -1,32 +1,114 @@
-use anyhow::{Context, Error};
+//! # swc - C/C++ and Rust bindings for swc
+//!
+//! Transpiles, compiles and minifies TypeScript and JavaScript with swc. The exported C
+//! functions are declared in the generated `swc.h`; Rust code uses the [`api`] module.
+
+// The exported functions are called from C and are intentionally not `unsafe fn`;
+// pointer validity is part of each function's documented contract instead.
+#![allow(clippy::not_unsafe_ptr_arg_deref)]
+
+use anyhow::{anyhow, Context, Error};
 
 use bytes_str::BytesStr;
-use std::path::Path;
+use std::io::Write;
+use std::os::raw::c_char;
+use std::path::PathBuf;
 use std::sync::Arc;
-/// # swc_ffi - FFI bindings for swc
-///
-/// An C/C++ Interop for the swc Rust library.
-use std::{
-    ffi::{CStr, CString},
-    os::raw::c_char,
-};
-use swc::try_with_handler;
 use swc_common::comments::SingleThreadedComments;
+use swc_common::errors::Handler;
 use swc_common::sync::Lrc;
-use swc_common::{
-    FileName, Globals, Mark, SourceFile, SourceMap, GLOBALS,
-};
-use swc_ecma_ast::Pass;
+use swc_common::{FileName, Mark, SourceFile, SourceMap, GLOBALS};
+use swc_ecma_ast::{fn_pass, noop_pass, Pass};
 use swc_ecma_codegen::text_writer::JsWriter;
 use swc_ecma_codegen::{Config, Emitter};
-use swc_ecma_parser::{lexer::Lexer, Parser, StringInput, Syntax, TsSyntax};
+use swc_ecma_parser::{lexer::Lexer, Parser, StringInput};
+use swc_ecma_preset_env::{transform_from_env, Caniuse};
 use swc_ecma_transforms_base::fixer::fixer;
+use swc_ecma_transforms_base::helpers::{Helpers, HELPERS};
 use swc_ecma_transforms_base::hygiene::hygiene;
 use swc_ecma_transforms_base::resolver;
-use swc_ecma_transforms_react::{jsx, Options as JsxOptions};
+use swc_ecma_transforms_react::{react, Options as JsxOptions};
 use swc_ecma_transforms_typescript::strip;
 use swc_ecma_visit::VisitMutWith;
 
+mod alloc;
+pub mod api;
+mod buffer;
+mod comments;
+mod diagnostics;
+mod env;
+mod ffi;
+mod last_error;
+mod mangle_cache;
+mod mangle_props;
+mod minify;
+mod module;
+mod options;
+mod reporter;
+mod result;
+mod session;
+mod source_map;
+mod stats;
+mod status;
+mod stream;
+mod syntax;
+
+pub use alloc::{swc_alloc_fn, swc_free_fn, swc_set_allocator};
+pub use buffer::{swc_buffer, swc_buffer_free};
+pub use comments::{swc_comments, swc_minify_js_with_comments, Comments};
+pub use diagnostics::{Diagnostic, Severity};
+pub use env::{swc_polyfill_mode, EnvOptions, PolyfillMode};
+pub use last_error::{swc_last_diagnostics, swc_last_error, swc_last_status};
+pub use mangle_cache::{
+    swc_mangle_cache, swc_mangle_cache_free, swc_mangle_cache_from_json, swc_mangle_cache_new,
+    swc_mangle_cache_to_json,
+};
+pub use module::{
+    swc_module_format, swc_module_interop, ModuleFormat, ModuleInterop, ModuleOptions,
+};
+pub use options::{
+    swc_es_version, swc_jsx_runtime, swc_transpile_options,
+    swc_transpile_options_add_module_global, swc_transpile_options_free, swc_transpile_options_new,
+    swc_transpile_options_set_ascii_only, swc_transpile_options_set_comments,
+    swc_transpile_options_set_decorators, swc_transpile_options_set_env,
+    swc_transpile_options_set_env_core_js, swc_transpile_options_set_env_engine,
+    swc_transpile_options_set_env_loose, swc_transpile_options_set_env_polyfills,
+    swc_transpile_options_set_env_targets, swc_transpile_options_set_input_source_map,
+    swc_transpile_options_set_jsx_development, swc_transpile_options_set_jsx_import_source,
+    swc_transpile_options_set_jsx_pragma, swc_transpile_options_set_jsx_pragma_frag,
+    swc_transpile_options_set_jsx_runtime, swc_transpile_options_set_jsx_throw_if_namespace,
+    swc_transpile_options_set_minify, swc_transpile_options_set_module_format,
+    swc_transpile_options_set_module_global_name, swc_transpile_options_set_module_interop,
+    swc_transpile_options_set_module_strict, swc_transpile_options_set_module_strict_mode,
+    swc_transpile_options_set_newline, swc_transpile_options_set_source_maps,
+    swc_transpile_options_set_syntax, swc_transpile_options_set_target,
+    swc_transpile_options_set_tsx, JsxRuntime, TranspileOptions,
+};
+pub use reporter::{swc_diagnostic_fn, swc_set_diagnostic_callback};
+pub use result::{swc_diagnostic, swc_result, swc_result_free, swc_severity};
+pub use session::{
+    swc_session, swc_session_compile, swc_session_free, swc_session_minify, swc_session_new,
+    swc_session_reset, swc_session_source_file_count, swc_session_transpile,
+};
+pub use source_map::{swc_source_map_mode, SourceMapMode};
+pub use stats::{swc_minify_js_with_stats, swc_minify_stats, MinifyStats};
+pub use status::{swc_status, Status};
+pub use stream::{swc_minify_js_stream, swc_transpile_stream, swc_write_fn};
+pub use syntax::{swc_syntax, InputSyntax};
+
+use buffer::outcome_to_buffer;
+use diagnostics::{with_diagnostics, Outcome};
+use ffi::{guard, input_bytes, input_str};
+use mangle_cache::{mangle_cache_ref, MangleCache};
+use mangle_props::{take_keep_quoted, KeepQuoted};
+use options::{optional_str, transpile_options};
+use result::{free_c_string, into_result_ptr, reject_interior_nul, to_c_string};
+use source_map::{apply_mode, build_source_map, finish_stream, LineTracker};
+use status::StatusExt;
+use swc_config::merge::Merge;
+use swc_config::source_map::SourceMapContent;
+use syntax::{parse_program, parser_syntax};
+
 /// Represents a file to transpile
 ///
 /// This enum is used to represent a file to transpile. It can either be a file path or a file name with its content.
@@ -34,20 +116,43 @@ use swc_ecma_visit::VisitMutWith;
 /// # Examples
 ///
 /// ```rust
-/// use std::path::Path;
-/// use swc_ffi::File;
+/// use swc::File;
 ///
-/// let path = Path::new("test.ts");
-/// let fileFromPath = File::FilePath(path);
-///
-/// let fileFromSource = File::FileName("test.ts", "const a = 1;".to_string());
+/// let file_from_path = File::path("test.ts");
+/// let file_from_source = File::source("test.ts", "const a = 1;");
 /// ```
 ///
 pub enum File {
-    FilePath(&'static Path),
+    FilePath(PathBuf),
     FileName(FileName, String),
 }
 
+impl File {
+    /// A file read from disk when it is processed
+    pub fn path(path: impl Into<PathBuf>) -> Self {
+        File::FilePath(path.into())
+    }
+
+    /// In-memory source code; `name` is used for syntax detection and in diagnostics
+    pub fn source(name: impl Into<String>, code: impl Into<String>) -> Self {
+        File::FileName(FileName::Custom(name.into()), code.into())
+    }
+}
+
+/// Generated code together with its source map
+///
+/// `map` holds the source map as JSON if a separate map was requested. Inline maps are
+/// already part of `code`. `licenses` lists the license comments minification kept in `code`,
+/// in source order, and is empty for everything else. `stats` is only set by minification
+/// that was asked to measure itself, e.g. [`api::minify_with_stats`].
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Output {
+    pub code: String,
+    pub map: Option<String>,
+    pub licenses: Vec<String>,
+    pub stats: Option<MinifyStats>,
+}
+
 /// Transpile TypeScript/TSX to JavaScript
 ///
 /// This function transpiles TypeScript/TSX code to JavaScript.
@@ -63,19 +168,17 @@ pub enum File {
 ///
 /// # Errors
 ///
-/// This function returns an error if the transpilation fails. The error is a boxed trait
-/// object that implements the `Error` trait.
+/// This function returns an [`api::Error`] with every diagnostic if the transpilation fails.
 ///
 /// # Examples
 ///
 /// ```rust
 /// use swc_common::sync::Lrc;
 /// use swc_common::SourceMap;
-/// use swc_ffi::{transpile_tsx_to_js, File};
-/// use std::path::Path;
+/// use swc::{transpile_tsx_to_js, File};
 ///
 /// let cm: Lrc<SourceMap> = Default::default();
-/// let filename = File::FilePath(Path::new("test.ts"));
+/// let filename = File::path("test.ts");
 /// let result = transpile_tsx_to_js(cm, filename);
 ///
 /// match result {
@@ -83,37 +186,79 @@ pub enum File {
 ///         println!("{}", output);
 ///     },
 ///     Err(e) => {
-///         eprintln!("Error: {}", e);
+///         eprintln!("Error: {e}");
 ///     }
 /// }
 /// ```
 ///
 /// # Panics
 ///
-/// This function panics if the program parsing fails. This should never happen in practice.
-pub fn transpile_tsx_to_js(
+/// This function does not panic on invalid input; parsing and transform failures are
+/// returned as errors.
+pub fn transpile_tsx_to_js(cm: Lrc<SourceMap>, filename: File) -> Result<String, api::Error> {
+    api::into_result(transpile_outcome(
+        cm,
+        filename,
+        &TranspileOptions::default(),
+    ))
+    .map(|output| output.code)
+}
+
+/// Runs the transpilation pipeline and collects every diagnostic reported along the way.
+fn transpile_outcome(
+    cm: Lrc<SourceMap>,
+    filename: File,
+    options: &TranspileOptions,
+) -> Outcome<Output> {
+    GLOBALS.set(&Default::default(), || transpile_in(cm, filename, options))
+}
+
+/// Like [`transpile_outcome`], but runs within the `GLOBALS` set by the caller, so a
+/// session can reuse its source map and globals across files.
+fn transpile_in(cm: Lrc<SourceMap>, filename: File, options: &TranspileOptions) -> Outcome<Output> {
+    with_diagnostics(cm.clone(), |handler| {
+        transpile_with_handler(cm, handler, filename, options).status(Status::Transform)
+    })
+}
+
+/// The transpilation pipeline behind [`transpile_tsx_to_js`]
+///
+/// Parser errors, including recoverable ones, are emitted to `handler` so they are reported
+/// with their exact location.
+fn transpile_with_handler(
     cm: Lrc<SourceMap>,
+    handler: &Handler,
     filename: File,
-) -> Result<String, Box<dyn std::error::Error>> {
-    // let handler = Handler::with_tty_emitter(
-    //     ColorConfig::Auto,
-    //     true,
-    //     false,
-    //     Some(cm.clone())
-    // );
+    options: &TranspileOptions,
+) -> Result<Output, Error> {
+    let mut code = vec![];
+    let map = transpile_to(cm, handler, filename, options, &mut code)?;
+    Ok(Output {
+        code: String::from_utf8(code)?,
+        map,
+        ..Default::default()
+    })
+}
 
+/// Like [`transpile_with_handler`], but writes the code to `out` while it is generated.
+///
+/// Nothing is written unless parsing and every transform succeeded. Returns the source map
+/// if a separate one was requested; an inline map is written to `out` after the code.
+fn transpile_to(
+    cm: Lrc<SourceMap>,
+    handler: &Handler,
+    filename: File,
+    options: &TranspileOptions,
+    out: &mut dyn Write,
+) -> Result<Option<String>, Error> {
     // Load or create file
     let fm = get_js_file(filename, cm.clone())?;
     let comments = SingleThreadedComments::default();
 
-    // Configure parser for TypeScript/TSX
-    let syntax = Syntax::Typescript(TsSyntax {
-        tsx: fm.name.to_string().ends_with(".tsx"),
-        decorators: true,
-        dts: false,
-        no_early_errors: false,
-        disallow_ambiguous_jsx_like: false,
-    });
+    // Configure parser for the syntax of the file
+    let name = fm.name.to_string();
+    let input_syntax = options.syntax.resolve(&name);
+    let syntax = parser_syntax(input_syntax, options.tsx, options.decorators);
 
     // Create lexer and parser
     let lexer = Lexer::new(
@@ -126,45 +271,111 @@ pub fn transpile_tsx_to_js(
     let mut parser = Parser::new_from(lexer);
 
     // Parse the program
-    let mut program = parser
-        .parse_program()
-        .map_err(|_| Box::<dyn std::error::Error>::from("program parsing failed"))?;
-
-    let globals = Globals::default();
-    GLOBALS.set(&globals, || {
-        let unresolved_mark = Mark::new();
-        let top_level_mark = Mark::new();
-        let mut config: Config = Default::default();
-        let jsx_options = JsxOptions {
-            pragma: Some(BytesStr::from("React.createElement")),
-            pragma_frag: Some(BytesStr::from("React.Fragment")),
-            ..Default::default()
-        };
+    let program = parse_program(&mut parser, &name);
+
+    let errors = parser.take_errors();
+    let recovered = !errors.is_empty();
+    for e in errors {
+        e.into_diagnostic(handler).emit();
+    }
+
+    let mut program = program
+        .map_err(|e| {
+            e.into_diagnostic(handler).emit();
+            anyhow!("program parsing failed")
+        })
+        .status(Status::Syntax)?;
+    if recovered {
+        return Err(anyhow!("program parsing failed")).status(Status::Syntax);
+    }
+
+    let unresolved_mark = Mark::new();
+    let top_level_mark = Mark::new();
+    let config = Config::default()
+        .with_target(options.target)
+        .with_ascii_only(options.ascii_only)
+        .with_minify(options.minify);
+    let jsx_options = JsxOptions {
+        runtime: Some(options.jsx_runtime.into()),
+        import_source: Some(options.jsx_import_source.as_str().into()),
+        pragma: Some(BytesStr::from(options.jsx_pragma.clone())),
+        pragma_frag: Some(BytesStr::from(options.jsx_pragma_frag.clone())),
+        throw_if_namespace: Some(options.jsx_throw_if_namespace),
+        development: Some(options.jsx_development),
+        ..Default::default()
+    };
+
+    program.visit_mut_with(&mut resolver(
+        unresolved_mark,
+        top_level_mark,
+        input_syntax.is_typescript(),
+    ));
+    react(
+        cm.clone(),
+        Some(&comments),
+        jsx_options,
+        top_level_mark,
+        unresolved_mark,
+    )
+    .process(&mut program);
+    strip(unresolved_mark, top_level_mark).process(&mut program);
 
-        config.target = swc_ecma_ast::EsVersion::Es2015;
-        program.visit_mut_with(&mut resolver(unresolved_mark, top_level_mark, true));
-        program.visit_mut_with(&mut jsx(
+    let env = options.env.as_ref().map(EnvOptions::build).transpose()?;
+    let features = env.as_ref().map(|env| env.get_feature_config());
+    let caniuse = |feature| match &features {
+        Some(features) => features.caniuse(feature),
+        None => options.target.caniuse(feature),
+    };
+    HELPERS.set(&Helpers::new(false), || {
+        if let Some(env) = env {
+            transform_from_env(unresolved_mark, Some(&comments), env, Default::default())
+                .process(&mut program);
+        }
+        module::transform(
+            &mut program,
+            &name,
             cm.clone(),
-            Some(&comments),
-            jsx_options,
-            top_level_mark,
+            &comments,
             unresolved_mark,
-        ));
-        strip(unresolved_mark, top_level_mark).process(&mut program);
-        program.visit_mut_with(&mut hygiene());
-        program.visit_mut_with(&mut fixer(Some(&comments)));
-        let mut buf = vec![];
+            caniuse,
+            &options.module,
+        )
+    })?;
+    program.visit_mut_with(&mut hygiene());
+    program.visit_mut_with(&mut fixer(Some(&comments)));
+    // Transforms report through the handler and carry on, so check before writing anything
+    if handler.has_errors() {
+        return Err(anyhow!("transforms reported errors")).status(Status::Transform);
+    }
+    let mut out = LineTracker::new(out);
+    let mut mappings = vec![];
+    {
         let mut emitter = Emitter {
             cfg: config,
             cm: cm.clone(),
-            comments: Some(&comments),
-            wr: Box::new(JsWriter::new(cm.clone(), "\n", &mut buf, None)),
+            comments: options.comments.then_some(&comments),
+            wr: Box::new(JsWriter::new(
+                cm.clone(),
+                &options.newline,
+                &mut out,
+                options.source_maps.enabled().then_some(&mut mappings),
+            )),
         };
 
         emitter.emit_program(&program)?;
+    }
 
-        Ok(String::from_utf8(buf)?)
-    })
+    let map = if options.source_maps.enabled() {
+        Some(build_source_map(
+            &cm,
+            &mappings,
+            options.input_source_map.as_deref(),
+        )?)
+    } else {
+        None
+    };
+
+    Ok(finish_stream(&mut out, map, options.source_maps)?)
 }
 
 /// Compiles a TypeScript/JavaScript file to JavaScript using SWC.
@@ -175,32 +386,138 @@ pub fn transpile_tsx_to_js(
 /// # Parameters
 ///
 /// * `file` - The source file representation (either a file path or in-memory content)
+/// * `config` - swc options as JSON (the format of `.swcrc`), or `None` for the built-in defaults
+/// * `input_source_map` - Source map of the input as JSON, composed into the generated map
 ///
 /// # Returns
 ///
-/// * `Some(String)` - The compiled JavaScript code on success
-/// * `None` - If compilation fails for any reason
+/// An [`Outcome`] holding the compiled JavaScript code on success, together with every
+/// error and warning reported by the compiler
 ///
 /// # Implementation Details
 ///
-/// Uses the SWC compiler with default settings to transform TypeScript/TSX to JavaScript.
+/// Uses the SWC compiler to transform TypeScript/TSX to JavaScript. Without a config the
+/// compiler's default settings are used and no `.swcrc` is consulted. A JSON config is
+/// deserialized into `swc::config::Options`; like on the JS side, `swcrc` defaults to `true`
+/// there, so a `.swcrc` next to (or above) a file on disk is merged in. An explicit input
+/// source map takes precedence over `inputSourceMap` from the config.
 /// The compilation process is executed within the SWC global context.
-fn compile(file: File) -> Option<String> {
-    let cm: Lrc<SourceMap> = Default::default();
-    let compiler = swc::Compiler::new(cm.clone());
-    let output = GLOBALS.set(&Default::default(), || {
-        try_with_handler(cm.clone(), Default::default(), |handler| {
-            let fm = get_js_file(file, cm)?;
-            compiler
-                .process_js_file(fm, handler, &Default::default())
-                .context("failed to process file")
+fn compile(file: File, config: Option<&str>, input_source_map: Option<&str>) -> Outcome<Output> {
+    let compiler = swc_compiler::Compiler::new(Default::default());
+    GLOBALS.set(&Default::default(), || {
+        compile_in(&compiler, file, config, input_source_map)
+    })
+}
+
+/// Like [`compile`], but uses `compiler` and runs within the `GLOBALS` set by the caller.
+fn compile_in(
+    compiler: &swc_compiler::Compiler,
+    file: File,
+    config: Option<&str>,
+    input_source_map: Option<&str>,
+) -> Outcome<Output> {
+    with_diagnostics(compiler.cm.clone(), |handler| {
+        let (mut options, wrapper) = match config {
+            Some(json) => parse_config(json)?,
+            None => Default::default(),
+        };
+        if let Some(map) = input_source_map {
+            options.config.input_source_map =
+                Some(swc_compiler::config::InputSourceMap::Str(map.to_owned()));
+        }
+        compile_with_handler(compiler, handler, file, &options, wrapper)
+    })
+}
+
+/// The compilation pipeline behind [`compile_in`] and [`api::compile`]
+///
+/// `wrapper` applies the IIFE and UMD options swc itself does not understand.
+fn compile_with_handler(
+    compiler: &swc_compiler::Compiler,
+    handler: &Handler,
+    file: File,
+    options: &swc_compiler::config::Options,
+    wrapper: Option<ModuleOptions>,
+) -> Result<Output, Error> {
+    let fm = get_js_file(file, compiler.cm.clone())?;
+    let name = fm.name.to_string();
+    // Applies `globalName` and `globals`, which swc's UMD transform does not support
+    let wrapper_pass = wrapper.map(|wrapper| {
+        fn_pass(move |program| {
+            if let Err(e) = module::rewrite_wrapper(program, &name, &wrapper) {
+                handler.err(&format!("{e:#}"));
+            }
         })
     });
+    let comments = SingleThreadedComments::default();
+    let program = parse_compile_input(compiler, handler, &fm, options, &comments)?;
+    compiler
+        .process_js_with_custom_pass(
+            fm,
+            program,
+            handler,
+            options,
+            comments,
+            |_| noop_pass(),
+            |_| wrapper_pass,
+        )
+        .map(|output| Output {
+            code: output.code,
+            map: output.map,
+            ..Default::default()
+        })
+        .context("failed to process file")
+        .status(Status::Transform)
+}
 
-    match output {
-        Ok(output) => Some(output.code),
-        Err(_) => None,
+/// Parses the input of [`compile_with_handler`] with the syntax, target and module kind the
+/// options and any `.swcrc` select, so syntax errors are reported as such.
+///
+/// Returns `None` if the config cannot be read or ignores the file, leaving swc to report that.
+fn parse_compile_input(
+    compiler: &swc_compiler::Compiler,
+    handler: &Handler,
+    fm: &Arc<SourceFile>,
+    options: &swc_compiler::config::Options,
+    comments: &SingleThreadedComments,
+) -> Result<Option<swc_ecma_ast::Program>, Error> {
+    if let FileName::Real(path) = &*fm.name {
+        if !options.config.matches(path).unwrap_or(false) {
+            return Ok(None);
+        }
     }
+    let Ok(Some(swcrc)) = compiler.read_config(options, &fm.name) else {
+        return Ok(None);
+    };
+    let mut config = options.config.clone();
+    config.merge(swcrc);
+
+    compiler
+        .parse_js(
+            fm.clone(),
+            handler,
+            config.jsc.target.unwrap_or_default(),
+            config.jsc.syntax.unwrap_or_default(),
+            config.is_module.unwrap_or_default(),
+            Some(comments),
+        )
+        .context("failed to parse input file")
+        .status(Status::Syntax)
+        .map(Some)
+}
+
+/// Deserializes swc options from a JSON string in the format of `.swcrc`.
+///
+/// Also returns the IIFE and UMD options swc itself does not understand, see
+/// [`module::take_wrapper_options`].
+fn parse_config(
+    json: &str,
+) -> Result<(swc_compiler::config::Options, Option<ModuleOptions>), Error> {
+    let mut config: serde_json::Value =
+        serde_json::from_str(json).context("failed to parse config")?;
+    let wrapper = module::take_wrapper_options(&mut config)?;
+    let options = serde_json::from_value(config).context("failed to parse config")?;
+    Ok((options, wrapper))
 }
 
 /// Compiles a TypeScript/TSX file to JavaScript.
@@ -241,11 +558,14 @@ fn compile(file: File) -> Option<String> {
 /// ```
 #[no_mangle]
 pub extern "C" fn compile_file(filepath: *const c_char, error: *mut *mut c_char) -> *mut c_char {
-    let path = unsafe { CStr::from_ptr(filepath) }
-        .to_str()
-        .expect("failed to convert filepath to &str");
-    let file = File::FilePath(Path::new(path));
-    prepare_compile_result(error, file)
+    outcome_to_char_ptr(
+        error,
+        guard(|| {
+            let path = input_str(filepath, "filepath")?;
+            let file = File::path(path);
+            Ok(compile(file, None, None))
+        }),
+    )
 }
 
 /// Compiles TypeScript/JavaScript code provided as a string.
@@ -286,22 +606,28 @@ pub extern "C" fn compile_file(filepath: *const c_char, error: *mut *mut c_char)
 /// ```
 #[no_mangle]
 pub extern "C" fn compile_js(code: *const c_char, error: *mut *mut c_char) -> *mut c_char {
-    let input = unsafe { CStr::from_ptr(code) }
-        .to_str()
-        .expect("failed to convert code to &str");
-    let file = File::FileName(FileName::Custom("input.js".into()), input.into());
-    prepare_compile_result(error, file)
+    outcome_to_char_ptr(
+        error,
+        guard(|| {
+            let input = input_str(code, "code")?;
+            let file = File::FileName(FileName::Custom("input.js".into()), input.into());
+            Ok(compile(file, None, None))
+        }),
+    )
 }
 
-/// Helper function to handle the common logic for compiling TypeScript/JavaScript.
+/// Compiles TypeScript/JavaScript code with a JSON configuration.
 ///
-/// This internal function encapsulates the shared compilation and error handling logic
-/// used by both `compile_file` and `compile_js` functions.
+/// This function behaves like `compile_js`, but takes swc options as JSON in the same
+/// format as a `.swcrc` file (`jsc.parser`, `jsc.target`, `jsc.transform`, `module`, ...),
+/// so configurations from the JS side can be reused verbatim.
 ///
 /// # Parameters
 ///
+/// * `code` - C string pointer containing the TypeScript/JavaScript code to compile
+/// * `filename` - Name of the input, used for `.swcrc` discovery and in diagnostics; may be null
+/// * `config_json` - swc options as JSON; null behaves like `{}`
 /// * `error` - Mutable reference to a C char that will contain error message if compilation fails
-/// * `file` - The source file representation (either a file path or in-memory content)
 ///
 /// # Returns
 ///
@@ -310,17 +636,125 @@ pub extern "C" fn compile_js(code: *const c_char, error: *mut *mut c_char) -> *m
 ///
 /// # Safety
 ///
+/// This function is unsafe because:
+/// * It dereferences raw pointers
+/// * It converts between C and Rust string representations
+/// * It allocates memory that must be freed by the caller using `free_string()`
+///
+/// # Examples
+///
+/// ```c
+/// const char* config = R"({
+///     "jsc": { "parser": { "syntax": "typescript" }, "target": "es2020" },
+///     "module": { "type": "commonjs" }
+/// })";
+/// char* error = NULL;
+/// char* result = compile_js_with_config("export const a: number = 1;", "a.ts", config, &error);
+/// if (result) {
+///     free_string(result);
+/// } else {
+///     printf("Error: %s\n", error);
+///     free_string(error);
+/// }
+/// ```
+#[no_mangle]
+pub extern "C" fn compile_js_with_config(
+    code: *const c_char,
+    filename: *const c_char,
+    config_json: *const c_char,
+    error: *mut *mut c_char,
+) -> *mut c_char {
+    outcome_to_char_ptr(
+        error,
+        guard(|| {
+            let input = input_str(code, "code")?;
+            let config = optional_str(config_json);
+            let file = File::FileName(input_name(filename), input.into());
+            Ok(compile(file, Some(config.as_deref().unwrap_or("{}")), None))
+        }),
+    )
+}
+
+/// Compiles a TypeScript/TSX file with a JSON configuration.
+///
+/// This function behaves like `compile_file`, but takes swc options as JSON in the same
+/// format as a `.swcrc` file. Unless the config sets `"swcrc": false`, a `.swcrc` found
+/// next to the input file (or in one of its parent directories) is merged in, so passing
+/// a null config simply compiles the file the way the swc CLI would.
+///
+/// # Parameters
+///
+/// * `filepath` - C string pointer to the path of the TypeScript/TSX file to compile
+/// * `config_json` - swc options as JSON; null behaves like `{}`
+/// * `error` - Mutable reference to a C char that will contain error message if compilation fails
+///
+/// # Returns
+///
+/// * On success: Raw pointer to a null-terminated C string containing the compiled JavaScript
+/// * On failure: Null pointer, with error message populated in the `error` parameter
+///
+/// # Safety
+///
+/// This function is unsafe because:
+/// * It dereferences raw pointers
+/// * It converts between C and Rust string representations
+/// * It allocates memory that must be freed by the caller using `free_string()`
+#[no_mangle]
+pub extern "C" fn compile_file_with_config(
+    filepath: *const c_char,
+    config_json: *const c_char,
+    error: *mut *mut c_char,
+) -> *mut c_char {
+    outcome_to_char_ptr(
+        error,
+        guard(|| {
+            let path = input_str(filepath, "filepath")?;
+            let config = optional_str(config_json);
+            let file = File::path(path);
+            Ok(compile(file, Some(config.as_deref().unwrap_or("{}")), None))
+        }),
+    )
+}
+
+/// Names an in-memory input, falling back to `input.js` if no name was given.
+///
+/// A given name is treated as a real path so that `.swcrc` discovery works relative to it.
+fn input_name(filename: *const c_char) -> FileName {
+    match optional_str(filename) {
+        Some(name) => FileName::Real(PathBuf::from(name)),
+        None => FileName::Custom("input.js".into()),
+    }
+}
+
+/// Helper function to hand the outcome of an operation to the legacy C API.
+///
+/// This internal function encapsulates the shared result and error handling logic
+/// used by the `compile_*` and `minify_*` functions.
+///
+/// # Parameters
+///
+/// * `error` - Mutable reference to a C char that will contain error message if the operation fails;
+///   ignored if null
+/// * `outcome` - The outcome of the compilation or minification
+///
+/// # Returns
+///
+/// * On success: Raw pointer to a null-terminated C string containing the generated JavaScript
+/// * On failure: Null pointer, with every reported error message populated in the `error` parameter
+///
+/// # Safety
+///
 /// This function is unsafe because it manipulates raw pointers when setting the error message.
-fn prepare_compile_result(error: *mut *mut c_char, file: File) -> *mut c_char {
-    match compile(file) {
-        Some(output) => CString::new(output)
-            .expect("failed to serialize code")
-            .into_raw(),
+fn outcome_to_char_ptr(error: *mut *mut c_char, outcome: Outcome<Output>) -> *mut c_char {
+    let outcome = guard(|| Ok(reject_interior_nul(outcome).try_map(|o| to_c_string(o.code))));
+    reporter::report_rest(&outcome);
+    last_error::record(&outcome);
+    match outcome.value {
+        Some(code) => code,
         None => {
-            unsafe {
-                *error = CString::new("failed to compile file")
-                    .expect("failed to convert error message to CString")
-                    .into_raw()
+            if !error.is_null() {
+                let message = to_c_string(outcome.error_message());
+                unsafe { *error = message.unwrap_or(std::ptr::null_mut()) }
             }
             std::ptr::null_mut()
         }
@@ -335,47 +769,164 @@ fn prepare_compile_result(error: *mut *mut c_char, file: File) -> *mut c_char {
 /// # Parameters
 ///
 /// * `file` - The source file representation (either a file path or in-memory content)
+/// * `source_maps` - Whether and how a source map is generated
+/// * `input_source_map` - Source map of the input as JSON, composed into the generated map
 ///
 /// # Returns
 ///
-/// * `Some(String)` - The minified JavaScript code on success
-/// * `None` - If minification fails for any reason
+/// An [`Outcome`] holding the minified JavaScript code on success, together with every
+/// error and warning reported by the minifier
 ///
 /// # Implementation Details
 ///
-/// Uses the SWC compiler with optimized minification settings:
+/// Uses the SWC compiler with the settings of [`minify_options`]:
 /// - Compression enabled (reduces code size through various optimizations)
 /// - Name mangling enabled (shortens variable/function names)
-/// - Uses a simple mangle cache to ensure consistent name replacements
-fn minify(file: File) -> Option<String> {
-    let cm: Lrc<SourceMap> = Default::default();
-    let compiler = swc::Compiler::new(cm.clone());
-    let output = GLOBALS.set(&Default::default(), || {
-        try_with_handler(cm.clone(), Default::default(), |handler| {
-            let fm = get_js_file(file, cm)?;
-            compiler
-                .minify(
-                    fm,
-                    handler,
-                    &swc::config::JsMinifyOptions {
-                        compress: swc::BoolOrDataConfig::from_bool(true),
-                        mangle: swc::BoolOrDataConfig::from_bool(true),
-                        ..Default::default()
-                    },
-                    swc::JsMinifyExtras::default().with_mangle_name_cache(Some(Arc::new(
-                        swc_ecma_minifier::option::SimpleMangleCache::default(),
-                    ))),
-                )
-                .context("failed to minify")
-        })
-    });
+/// - Uses a fresh mangle cache to ensure consistent name replacements
+/// - Source map generation according to `source_maps`, embedding the original sources
+fn minify(
+    file: File,
+    source_maps: SourceMapMode,
+    input_source_map: Option<&str>,
+) -> Outcome<Output> {
+    minify_with(
+        file,
+        &minify_options(source_maps, input_source_map),
+        KeepQuoted::default(),
+        source_maps,
+        None,
+        false,
+    )
+}
+
+/// Like [`minify`], but with every minifier option given by the caller.
+///
+/// `source_maps` only decides how a map generated according to `options.source_map` is
+/// returned. Names already in `cache` are reused and new ones are added to it. `stats` asks
+/// for [`Output::stats`].
+fn minify_with(
+    file: File,
+    options: &swc_compiler::config::JsMinifyOptions,
+    keep_quoted: KeepQuoted,
+    source_maps: SourceMapMode,
+    cache: Option<&MangleCache>,
+    stats: bool,
+) -> Outcome<Output> {
+    let compiler = swc_compiler::Compiler::new(Default::default());
+    GLOBALS.set(&Default::default(), || {
+        minify_in(
+            &compiler,
+            file,
+            options,
+            keep_quoted,
+            source_maps,
+            cache,
+            stats,
+        )
+    })
+}
+
+/// The minifier options behind [`minify`]
+///
+/// Compresses and mangles like swc's own `minify()` and generates a source map if
+/// `source_maps` asks for one, composed onto `input_source_map`.
+fn minify_options(
+    source_maps: SourceMapMode,
+    input_source_map: Option<&str>,
+) -> swc_compiler::config::JsMinifyOptions {
+    swc_compiler::config::JsMinifyOptions {
+        compress: swc_compiler::BoolOrDataConfig::from_bool(true),
+        mangle: swc_compiler::BoolOrDataConfig::from_bool(true),
+        source_map: match input_source_map {
+            Some(map) if source_maps.enabled() => swc_compiler::BoolOrDataConfig::from_obj(
+                swc_compiler::config::TerserSourceMapOption {
+                    content: Some(SourceMapContent::Json(map.to_owned())),
+                    ..Default::default()
+                },
+            ),
+            _ => swc_compiler::BoolOrDataConfig::from_bool(source_maps.enabled()),
+        },
+        inline_sources_content: true,
+        ..Default::default()
+    }
+}
+
+/// Deserializes minifier options from JSON in the format of swc's and terser's `minify()`.
+///
+/// `mangle.props.keep_quoted` is returned separately, swc's options have no field for it.
+fn parse_minify_options(
+    json: &str,
+) -> Result<(swc_compiler::config::JsMinifyOptions, KeepQuoted), Error> {
+    let mut options: serde_json::Value =
+        serde_json::from_str(json).context("failed to parse minify options")?;
+    let keep_quoted = take_keep_quoted(&mut options)
+        .context("failed to parse minify options")
+        .status(Status::InvalidConfig)?;
+    let options = serde_json::from_value(options).context("failed to parse minify options")?;
+    Ok((options, keep_quoted))
+}
 
-    match output {
-        Ok(output) => Some(output.code),
-        Err(_) => None,
+/// How the source map requested by `options` is returned: separately if `sourceMap` is
+/// enabled, otherwise there is none.
+fn requested_source_maps(options: &swc_compiler::config::JsMinifyOptions) -> SourceMapMode {
+    if options.source_map.is_true() || options.source_map.is_obj() {
+        SourceMapMode::Separate
+    } else {
+        SourceMapMode::None
     }
 }
 
+/// Minifies `file` with options given as JSON, see [`parse_minify_options`]. Null JSON
+/// behaves like `{}`, which compresses and mangles with swc's defaults.
+fn minify_json(
+    file: File,
+    options_json: Option<&str>,
+    cache: Option<&MangleCache>,
+) -> Result<Outcome<Output>, Error> {
+    let (options, keep_quoted) = parse_minify_options(options_json.unwrap_or("{}"))?;
+    Ok(minify_with(
+        file,
+        &options,
+        keep_quoted,
+        requested_source_maps(&options),
+        cache,
+        false,
+    ))
+}
+
+/// Like [`minify_with`], but uses `compiler` and runs within the `GLOBALS` set by the caller.
+fn minify_in(
+    compiler: &swc_compiler::Compiler,
+    file: File,
+    options: &swc_compiler::config::JsMinifyOptions,
+    keep_quoted: KeepQuoted,
+    source_maps: SourceMapMode,
+    cache: Option<&MangleCache>,
+    stats: bool,
+) -> Outcome<Output> {
+    let cm = compiler.cm.clone();
+    with_diagnostics(cm.clone(), |handler| {
+        let fm = get_js_file(file, cm)?;
+        minify::minify(compiler, fm, handler, options, keep_quoted, cache, stats)
+            .map(|minified| {
+                let output = minified.output;
+                let stats = minified.stats.map(|mut stats| {
+                    stats.measure_output(&output.code);
+                    stats
+                });
+                let (code, map) = apply_mode(output.code, output.map, source_maps);
+                Output {
+                    code,
+                    map,
+                    licenses: minified.licenses,
+                    stats,
+                }
+            })
+            .context("failed to minify")
+            .status(Status::Minify)
+    })
+}
+
 /// Minifies JavaScript code from a file path.
 ///
 /// This function takes a C-style null-terminated string path to a JavaScript file,
@@ -416,23 +967,14 @@ fn minify(file: File) -> Option<String> {
 /// ```
 #[no_mangle]
 pub extern "C" fn minify_js_file(filepath: *const c_char, error: *mut *mut c_char) -> *mut c_char {
-    let path = unsafe { CStr::from_ptr(filepath) }
-        .to_str()
-        .expect("failed to convert filepath to &str");
-    let file = File::FilePath(Path::new(path));
-    match minify(file) {
-        Some(output) => CString::new(output)
-            .expect("failed to serialize code")
-            .into_raw(),
-        None => {
-            unsafe {
-                *error = CString::new("failed to minify file")
-                    .expect("failed to convert error message to CString")
-                    .into_raw()
-            };
-            std::ptr::null_mut()
-        }
-    }
+    outcome_to_char_ptr(
+        error,
+        guard(|| {
+            let path = input_str(filepath, "filepath")?;
+            let file = File::path(path);
+            Ok(minify(file, SourceMapMode::None, None))
+        }),
+    )
 }
 
 /// Minifies JavaScript code provided as a string.
@@ -475,23 +1017,170 @@ pub extern "C" fn minify_js_file(filepath: *const c_char, error: *mut *mut c_cha
 /// ```
 #[no_mangle]
 pub extern "C" fn minify_js(code: *const c_char, error: *mut *mut c_char) -> *mut c_char {
-    let input = unsafe { CStr::from_ptr(code) }
-        .to_str()
-        .expect("failed to convert code to &str");
-    let file = File::FileName(FileName::Custom("input.js".into()), input.into());
-    match minify(file) {
-        Some(output) => CString::new(output)
-            .expect("failed to serialize code")
-            .into_raw(),
-        None => {
-            unsafe {
-                *error = CString::new("failed to minify code")
-                    .expect("failed to convert error message to CString")
-                    .into_raw()
-            };
-            std::ptr::null_mut()
-        }
-    }
+    outcome_to_char_ptr(
+        error,
+        guard(|| {
+            let input = input_str(code, "code")?;
+            let file = File::FileName(FileName::Custom("input.js".into()), input.into());
+            Ok(minify(file, SourceMapMode::None, None))
+        }),
+    )
+}
+
+/// Minifies JavaScript code with the given minifier options.
+///
+/// Behaves like `minify_js`, but takes the full set of options of swc's `minify()` as JSON,
+/// e.g. `ecma`, `module`, `toplevel`, `keep_classnames`, `keep_fnames`, `compress` (with
+/// `drop_console`, `passes`, `pure_funcs`, ...), `mangle` (with `reserved`, ...) and
+/// `format`. Options that are left out keep swc's defaults; unlike with `minify_js`, the
+/// code is therefore parsed as a script and its top-level names are kept unless `module` or
+/// `toplevel` is set.
+///
+/// Object properties are mangled if `mangle.props` is set, limited to names matching its
+/// `regex` and not in `reserved` or one of the DOM and JavaScript built-ins. Its
+/// `keep_quoted` keeps quoted names as in `obj["name"]`: `true` keeps every use of such a
+/// name, `"strict"` only the quoted ones.
+///
+/// # Parameters
+///
+/// * `code` - C string pointer containing the JavaScript code to minify
+/// * `options_json` - Minifier options as JSON; null behaves like `{}`
+/// * `error` - Mutable reference to a C char that will contain error message if minification fails
+///
+/// # Returns
+///
+/// * On success: Raw pointer to a null-terminated C string containing the minified JavaScript
+/// * On failure: Null pointer, with error message populated in the `error` parameter
+///
+/// # Safety
+///
+/// This function is unsafe because:
+/// * It dereferences raw pointers
+/// * It converts between C and Rust string representations
+/// * It allocates memory that must be freed by the caller
+///
+/// # Examples
+///
+/// ```c
+/// // A debuggable build: compressed, but readable names and no dead `console` calls
+/// const char* debug = "{\"compress\": {\"drop_console\": true}, \"mangle\": false}";
+/// // The production build
+/// const char* release = "{\"compress\": {\"passes\": 3}, \"toplevel\": true, \"mangle\": {\"reserved\": [\"$\"]}}";
+///
+/// char* error = NULL;
+/// char* result = minify_js_with_options(js_code, release, &error);
+/// ```
+#[no_mangle]
+pub extern "C" fn minify_js_with_options(
+    code: *const c_char,
+    options_json: *const c_char,
+    error: *mut *mut c_char,
+) -> *mut c_char {
+    outcome_to_char_ptr(
+        error,
+        guard(|| {
+            let input = input_str(code, "code")?;
+            let options = optional_str(options_json);
+            let file = File::FileName(FileName::Custom("input.js".into()), input.into());
+            minify_json(file, options.as_deref(), None)
+        }),
+    )
+}
+
+/// Minifies a JavaScript file with the given minifier options.
+///
+/// Behaves like `minify_js_file`, but takes the minifier options as JSON, see
+/// `minify_js_with_options`.
+///
+/// # Parameters
+///
+/// * `filepath` - C string pointer to the path of the JavaScript file to minify
+/// * `options_json` - Minifier options as JSON; null behaves like `{}`
+/// * `error` - Mutable reference to a C char that will contain error message if minification fails
+///
+/// # Returns
+///
+/// * On success: Raw pointer to a null-terminated C string containing the minified JavaScript
+/// * On failure: Null pointer, with error message populated in the `error` parameter
+///
+/// # Safety
+///
+/// This function is unsafe because:
+/// * It dereferences raw pointers
+/// * It converts between C and Rust string representations
+/// * It allocates memory that must be freed by the caller
+#[no_mangle]
+pub extern "C" fn minify_js_file_with_options(
+    filepath: *const c_char,
+    options_json: *const c_char,
+    error: *mut *mut c_char,
+) -> *mut c_char {
+    outcome_to_char_ptr(
+        error,
+        guard(|| {
+            let path = input_str(filepath, "filepath")?;
+            let options = optional_str(options_json);
+            minify_json(File::path(path), options.as_deref(), None)
+        }),
+    )
+}
+
+/// Minifies JavaScript code with names shared through a mangle cache.
+///
+/// Behaves like `minify_js_with_options`, but gives top-level variables the names `cache`
+/// holds for them and adds the names picked for new ones. Minifying every chunk of a build
+/// with one cache mangles a global they share the same way in all of them, and restoring the
+/// cache with `swc_mangle_cache_from_json()` keeps the names stable across builds.
+///
+/// Top-level names are only mangled in modules or with `"toplevel": true`; otherwise the
+/// cache is left untouched.
+///
+/// # Parameters
+///
+/// * `code` - C string pointer containing the JavaScript code to minify
+/// * `options_json` - Minifier options as JSON; null behaves like `{}`
+/// * `cache` - Cache created with `swc_mangle_cache_new()` or `swc_mangle_cache_from_json()`
+/// * `error` - Mutable reference to a C char that will contain error message if minification fails
+///
+/// # Returns
+///
+/// * On success: Raw pointer to a null-terminated C string containing the minified JavaScript
+/// * On failure: Null pointer, with error message populated in the `error` parameter
+///
+/// # Safety
+///
+/// This function is unsafe because:
+/// * It dereferences raw pointers
+/// * It converts between C and Rust string representations
+/// * It allocates memory that must be freed by the caller
+///
+/// # Examples
+///
+/// ```c
+/// swc_mangle_cache* cache = swc_mangle_cache_new();
+/// for (size_t i = 0; i < chunk_count; i++) {
+///     char* error = NULL;
+///     char* minified = minify_js_with_cache(chunks[i], "{\"toplevel\": true}", cache, &error);
+///     // Write minified...
+/// }
+/// ```
+#[no_mangle]
+pub extern "C" fn minify_js_with_cache(
+    code: *const c_char,
+    options_json: *const c_char,
+    cache: *const swc_mangle_cache,
+    error: *mut *mut c_char,
+) -> *mut c_char {
+    outcome_to_char_ptr(
+        error,
+        guard(|| {
+            let input = input_str(code, "code")?;
+            let options = optional_str(options_json);
+            let cache = mangle_cache_ref(cache)?;
+            let file = File::FileName(FileName::Custom("input.js".into()), input.into());
+            minify_json(file, options.as_deref(), Some(cache))
+        }),
+    )
 }
 
 /// Get a JavaScript file
@@ -512,36 +1201,30 @@ pub extern "C" fn minify_js(code: *const c_char, error: *mut *mut c_char) -> *mu
 /// This function returns an error if the file cannot be loaded or created.
 fn get_js_file(file: File, cm: Arc<SourceMap>) -> Result<Arc<SourceFile>, Error> {
     Ok(match file {
-        File::FilePath(path) => cm.load_file(path)?,
-        File::FileName(name, source) => cm.new_source_file(
-            Lrc::new(name),
-            BytesStr::from(source)
-        ),
+        File::FilePath(path) => cm
+            .load_file(&path)
+            .with_context(|| format!("failed to load {}", path.display()))?,
+        File::FileName(name, source) => cm.new_source_file(Lrc::new(name), BytesStr::from(source)),
     })
 }
 
-/// Converts a result to a C string pointer
+/// Converts an outcome to a C string pointer
 ///
-/// This function converts a result to a C string pointer. If the result is an error,
-/// the error is printed to stderr.
+/// This function converts an outcome to a C string pointer. If the operation failed,
+/// the error is available to the caller through `swc_last_error()`.
 ///
 /// # Arguments
 ///
-/// * `result` - The result to convert
+/// * `outcome` - The outcome to convert
 ///
 /// # Returns
 ///
-/// The result as a C string pointer or a null pointer if the result is an error
-fn result_to_char_ptr(result: Result<String, Box<dyn std::error::Error>>) -> *mut c_char {
-    match result {
-        Ok(output) => CString::new(output)
-            .expect("failed to convert output to CString")
-            .into_raw(),
-        Err(e) => {
-            eprintln!("Error: {}", e);
-            std::ptr::null_mut()
-        }
-    }
+/// The generated code as a C string pointer or a null pointer if the operation failed
+fn result_to_char_ptr(outcome: Outcome<Output>) -> *mut c_char {
+    let outcome = guard(|| Ok(reject_interior_nul(outcome).try_map(|o| to_c_string(o.code))));
+    reporter::report_rest(&outcome);
+    last_error::record(&outcome);
+    outcome.value.unwrap_or(std::ptr::null_mut())
 }
 
 /// Transpile TypeScript/TSX to JavaScript
@@ -584,13 +1267,13 @@ fn result_to_char_ptr(result: Result<String, Box<dyn std::error::Error>>) -> *mu
 ///
 /// # Panics
 ///
-/// This function panics if the conversion from a raw pointer to a string fails.
-/// This should never happen in practice.
+/// This function never panics across the FFI boundary. Null pointers, invalid UTF-8
+/// and internal panics are reported like any other transpilation failure.
 ///
 /// # Errors
 ///
 /// This function returns a null pointer if the transpilation fails.
-/// The error is printed to stderr.
+/// The error can be retrieved with `swc_last_error()` and `swc_last_diagnostics()`.
 ///
 /// # Memory Management
 ///
@@ -605,29 +1288,29 @@ fn result_to_char_ptr(result: Result<String, Box<dyn std::error::Error>>) -> *mu
 /// * `File`
 #[no_mangle]
 pub extern "C" fn transpile(file: *const c_char, input: *const c_char) -> *mut c_char {
-    let file = unsafe { CStr::from_ptr(file) }
-        .to_str()
-        .expect("failed to convert file to &str");
-    let input = unsafe { CStr::from_ptr(input) }
-        .to_str()
-        .expect("failed to convert input to &str");
-    let cm: Lrc<SourceMap> = Default::default();
-    result_to_char_ptr(transpile_tsx_to_js(
-        cm,
-        File::FileName(FileName::Custom(String::from(file)), input.into()),
-    ))
+    result_to_char_ptr(guard(|| {
+        let file = input_str(file, "file")?;
+        let input = input_str(input, "input")?;
+        let cm: Lrc<SourceMap> = Default::default();
+        Ok(transpile_outcome(
+            cm,
+            File::FileName(FileName::Custom(String::from(file)), input.into()),
+            &TranspileOptions::default(),
+        ))
+    }))
 }
 
-/// Transpile a TypeScript/TSX file to JavaScript
-///
-/// This function transpiles a TypeScript/TSX file to JavaScript.
+/// Transpile TypeScript/TSX to JavaScript with custom options
 ///
-/// ### Deprecated
-/// This function is deprecated. Use the `compile_file` function instead.
+/// This function behaves like `transpile`, but takes its pipeline settings (target,
+/// JSX pragmas, decorators, TSX override, newline, ASCII-only output, compact codegen
+/// and comment handling) from an options handle instead of the built-in defaults.
 ///
 /// # Arguments
 ///
-/// * `filename` - The file to transpile
+/// * `file` - The file name
+/// * `input` - The TypeScript/TSX code
+/// * `options` - Options created with `swc_transpile_options_new`, or null for the defaults
 ///
 /// # Returns
 ///
@@ -639,11 +1322,16 @@ pub extern "C" fn transpile(file: *const c_char, input: *const c_char) -> *mut c
 /// #include "swc.h"
 ///
 /// int main() {
-///     const char* file_name = "example.ts";
-///     char* js_code = transpile_file(file_name);
+///     swc_transpile_options* options = swc_transpile_options_new();
+///     swc_transpile_options_set_target(options, swc_es_version::Es2020);
+///     swc_transpile_options_set_jsx_pragma(options, "h");
+///     swc_transpile_options_set_jsx_pragma_frag(options, "Fragment");
+///
+///     char* js_code = transpile_with_options("app.tsx", "const a = <div />;", options);
 ///     printf("%s\n", js_code);
-///     free_string(js_code);
 ///
+///     free_string(js_code);
+///     swc_transpile_options_free(options);
 ///     return 0;
 /// }
 /// ```
@@ -653,145 +1341,2819 @@ pub extern "C" fn transpile(file: *const c_char, input: *const c_char) -> *mut c
 /// This function is unsafe because it dereferences raw pointers.
 /// The caller must ensure that the pointers are valid.
 ///
-/// # Panics
-///
-/// This function panics if the conversion from a raw pointer to a string fails.
-/// This should never happen in practice.
-///
 /// # Errors
 ///
 /// This function returns a null pointer if the transpilation fails.
-/// The error is printed to stderr.
+/// The error can be retrieved with `swc_last_error()` and `swc_last_diagnostics()`.
 ///
 /// # Memory Management
 ///
 /// The caller is responsible for freeing the memory allocated by this
-/// function using the `free_string` function.
+/// function using the `free_string` function. The options handle stays owned
+/// by the caller and can be reused for further calls.
 ///
 /// # See Also
 ///
-/// * `free_string`
 /// * `transpile`
-/// * `transpile_tsx_to_js`
+/// * `swc_transpile_options_new`
+/// * `TranspileOptions`
+#[no_mangle]
+pub extern "C" fn transpile_with_options(
+    file: *const c_char,
+    input: *const c_char,
+    options: *const swc_transpile_options,
+) -> *mut c_char {
+    result_to_char_ptr(guard(|| {
+        let file = input_str(file, "file")?;
+        let input = input_str(input, "input")?;
+        let cm: Lrc<SourceMap> = Default::default();
+        Ok(transpile_outcome(
+            cm,
+            File::FileName(FileName::Custom(String::from(file)), input.into()),
+            &transpile_options(options),
+        ))
+    }))
+}
+
+/// Transpile a TypeScript/TSX file to JavaScript
+///
+/// This function transpiles a TypeScript/TSX file to JavaScript.
+///
+/// ### Deprecated
+/// This function is deprecated. Use the `compile_file` function instead.
+///
+/// # Arguments
+///
+/// * `filename` - The file to transpile
+///
+/// # Returns
+///
+/// The transpiled JavaScript code as a string or a null pointer if the transpilation fails
+///
+/// # Examples
+///
+/// ```c
+/// #include "swc.h"
+///
+/// int main() {
+///     const char* file_name = "example.ts";
+///     char* js_code = transpile_file(file_name);
+///     printf("%s\n", js_code);
+///     free_string(js_code);
+///
+///     return 0;
+/// }
+/// ```
+///
+/// # Safety
+///
+/// This function is unsafe because it dereferences raw pointers.
+/// The caller must ensure that the pointers are valid.
+///
+/// # Panics
+///
+/// This function never panics across the FFI boundary. Null pointers, invalid UTF-8
+/// and internal panics are reported like any other transpilation failure.
+///
+/// # Errors
+///
+/// This function returns a null pointer if the transpilation fails.
+/// The error can be retrieved with `swc_last_error()` and `swc_last_diagnostics()`.
+///
+/// # Memory Management
+///
+/// The caller is responsible for freeing the memory allocated by this
+/// function using the `free_string` function.
+///
+/// # See Also
+///
+/// * `free_string`
+/// * `transpile`
+/// * `transpile_tsx_to_js`
 /// * `File`
 ///
 /// ***Note: Deprecated***
 #[no_mangle]
 pub extern "C" fn transpile_file(filename: *const c_char) -> *mut c_char {
-    let file = unsafe { CStr::from_ptr(filename) }
-        .to_str()
-        .expect("failed to convert filename to &str");
-    let cm: Lrc<SourceMap> = Default::default();
-    result_to_char_ptr(transpile_tsx_to_js(cm, File::FilePath(Path::new(file))))
+    result_to_char_ptr(guard(|| {
+        let file = input_str(filename, "filename")?;
+        let cm: Lrc<SourceMap> = Default::default();
+        Ok(transpile_outcome(
+            cm,
+            File::path(file),
+            &TranspileOptions::default(),
+        ))
+    }))
 }
 
-/// Frees memory allocated by string-returning FFI functions.
+/// Compiles TypeScript/JavaScript code and reports structured diagnostics.
 ///
-/// This function properly deallocates memory that was allocated by functions
-/// like `minify_js` and `minify_js_file` which return a `*mut c_char`.
-/// It safely converts the raw C string pointer back to a Rust CString,
-/// which is then automatically dropped at the end of the function scope.
+/// Behaves like `compile_js`, but instead of a single error string it returns an
+/// `swc_result` carrying the compiled code together with every error and warning,
+/// each with its file, line, column and byte span.
 ///
 /// # Parameters
 ///
-/// * `s` - Mutable pointer to a C string previously returned by an FFI function
+/// * `code` - C string pointer containing the TypeScript/JavaScript code to compile
+///
+/// # Returns
+///
+/// Pointer to an `swc_result`. `code` is null if compilation failed, in which case
+/// `diagnostics` contains at least one error.
 ///
 /// # Safety
 ///
 /// This function is unsafe because:
-/// * It converts a raw pointer back to a Rust type
-/// * The pointer must have been previously allocated by Rust's `CString::into_raw()`
-/// * The pointer must not have been freed already
-/// * The pointer must not be used after this call
+/// * It dereferences raw pointers
+/// * It allocates memory that must be freed by the caller using `swc_result_free()`
 ///
 /// # Examples
 ///
 /// ```c
-/// char error[256] = {0};
-/// char* result = minify_js(js_code, error);
-/// if (result) {
-///     // Use result...
-///     free_string(result); // Must free when done
+/// swc_result* result = swc_compile_js("const a = ;");
+/// for (size_t i = 0; i < result->diagnostics_len; i++) {
+///     const swc_diagnostic* d = &result->diagnostics[i];
+///     printf("%s:%u:%u: %s\n", d->file, d->line, d->column + 1, d->message);
 /// }
+/// swc_result_free(result);
 /// ```
 #[no_mangle]
-pub extern "C" fn free_string(s: *mut c_char) {
-    unsafe {
-        let _ = CString::from_raw(s);
-    };
+pub extern "C" fn swc_compile_js(code: *const c_char) -> *mut swc_result {
+    into_result_ptr(guard(|| {
+        let input = input_str(code, "code")?;
+        let file = File::FileName(FileName::Custom("input.js".into()), input.into());
+        Ok(compile(file, None, None))
+    }))
 }
 
-/// Frees memory allocated for constant string pointers.
+/// Compiles a TypeScript/TSX file and reports structured diagnostics.
 ///
-/// This function properly deallocates memory pointed to by a constant C string pointer.
-/// It is used for freeing strings that were allocated by Rust but returned as read-only
-/// pointers to C. The function safely converts the raw C string pointer back to a Rust
-/// CString, which is then automatically dropped at the end of the function scope.
+/// Behaves like `compile_file`, but returns an `swc_result` carrying the compiled code
+/// together with every error and warning.
 ///
 /// # Parameters
 ///
-/// * `s` - Constant pointer to a C string previously allocated by a Rust FFI function
+/// * `filepath` - C string pointer to the path of the TypeScript/TSX file to compile
+///
+/// # Returns
+///
+/// Pointer to an `swc_result`. `code` is null if compilation failed, in which case
+/// `diagnostics` contains at least one error.
 ///
 /// # Safety
 ///
 /// This function is unsafe because:
-/// * It converts a raw pointer back to a Rust type
-/// * It casts a const pointer to a mutable pointer
-/// * The pointer must have been previously allocated by Rust's `CString::into_raw()`
-/// * The pointer must not have been freed already
-/// * The pointer must not be used after this call
+/// * It dereferences raw pointers
+/// * It allocates memory that must be freed by the caller using `swc_result_free()`
+#[no_mangle]
+pub extern "C" fn swc_compile_file(filepath: *const c_char) -> *mut swc_result {
+    into_result_ptr(guard(|| {
+        let path = input_str(filepath, "filepath")?;
+        Ok(compile(File::path(path), None, None))
+    }))
+}
+
+/// Compiles TypeScript/JavaScript code with a JSON configuration and reports structured
+/// diagnostics.
+///
+/// Behaves like `compile_js_with_config`, but returns an `swc_result` carrying the compiled
+/// code together with every error and warning.
+///
+/// If the input was itself generated (e.g. by `transpile`), pass its source map as
+/// `input_source_map` and enable `"sourceMaps"` in the config: the returned map then points
+/// back to the original sources instead of the intermediate code.
+///
+/// # Parameters
+///
+/// * `code` - C string pointer containing the TypeScript/JavaScript code to compile
+/// * `filename` - Name of the input, used for `.swcrc` discovery and in diagnostics; may be null
+/// * `config_json` - swc options as JSON; null behaves like `{}`
+/// * `input_source_map` - Source map of `code` as JSON; may be null
+///
+/// # Returns
+///
+/// Pointer to an `swc_result`. `code` is null if compilation failed, in which case
+/// `diagnostics` contains at least one error.
+///
+/// # Safety
+///
+/// This function is unsafe because:
+/// * It dereferences raw pointers
+/// * It allocates memory that must be freed by the caller using `swc_result_free()`
+#[no_mangle]
+pub extern "C" fn swc_compile_js_with_config(
+    code: *const c_char,
+    filename: *const c_char,
+    config_json: *const c_char,
+    input_source_map: *const c_char,
+) -> *mut swc_result {
+    into_result_ptr(guard(|| {
+        let input = input_str(code, "code")?;
+        let config = optional_str(config_json);
+        let input_source_map = optional_str(input_source_map);
+        let file = File::FileName(input_name(filename), input.into());
+        Ok(compile(
+            file,
+            Some(config.as_deref().unwrap_or("{}")),
+            input_source_map.as_deref(),
+        ))
+    }))
+}
+
+/// Compiles a TypeScript/TSX file with a JSON configuration and reports structured
+/// diagnostics.
+///
+/// Behaves like `compile_file_with_config`, including `.swcrc` discovery, but returns an
+/// `swc_result` carrying the compiled code together with every error and warning.
+///
+/// # Parameters
+///
+/// * `filepath` - C string pointer to the path of the TypeScript/TSX file to compile
+/// * `config_json` - swc options as JSON; null behaves like `{}`
+///
+/// # Returns
+///
+/// Pointer to an `swc_result`. `code` is null if compilation failed, in which case
+/// `diagnostics` contains at least one error.
+///
+/// # Safety
+///
+/// This function is unsafe because:
+/// * It dereferences raw pointers
+/// * It allocates memory that must be freed by the caller using `swc_result_free()`
+#[no_mangle]
+pub extern "C" fn swc_compile_file_with_config(
+    filepath: *const c_char,
+    config_json: *const c_char,
+) -> *mut swc_result {
+    into_result_ptr(guard(|| {
+        let path = input_str(filepath, "filepath")?;
+        let config = optional_str(config_json);
+        let file = File::path(path);
+        Ok(compile(file, Some(config.as_deref().unwrap_or("{}")), None))
+    }))
+}
+
+/// Minifies JavaScript code and reports structured diagnostics.
+///
+/// Behaves like `minify_js`, but returns an `swc_result` carrying the minified code
+/// together with every error and warning.
+///
+/// # Parameters
+///
+/// * `code` - C string pointer containing the JavaScript code to minify
+///
+/// # Returns
+///
+/// Pointer to an `swc_result`. `code` is null if minification failed, in which case
+/// `diagnostics` contains at least one error.
+///
+/// # Safety
+///
+/// This function is unsafe because:
+/// * It dereferences raw pointers
+/// * It allocates memory that must be freed by the caller using `swc_result_free()`
+#[no_mangle]
+pub extern "C" fn swc_minify_js(code: *const c_char) -> *mut swc_result {
+    into_result_ptr(guard(|| {
+        let input = input_str(code, "code")?;
+        let file = File::FileName(FileName::Custom("input.js".into()), input.into());
+        Ok(minify(file, SourceMapMode::None, None))
+    }))
+}
+
+/// Minifies JavaScript code and generates a source map.
+///
+/// Behaves like `swc_minify_js`, but also produces a source map pointing from the
+/// minified code back to `code`. The map embeds the original source.
+///
+/// When `code` was generated by an earlier step, such as `swc_compile_file`, pass that step's
+/// map as `input_source_map`. The result is a single map from the minified output back to
+/// the original sources.
+///
+/// # Parameters
+///
+/// * `code` - C string pointer containing the JavaScript code to minify
+/// * `filename` - Name recorded as the source in the map; may be null
+/// * `input_source_map` - Source map of `code` as JSON; may be null
+/// * `mode` - `Separate` returns the map in `map`, `Inline` appends it to `code` as a data URL
+///
+/// # Returns
+///
+/// Pointer to an `swc_result`. `code` is null if minification failed, in which case
+/// `diagnostics` contains at least one error.
+///
+/// # Safety
+///
+/// This function is unsafe because:
+/// * It dereferences raw pointers
+/// * It allocates memory that must be freed by the caller using `swc_result_free()`
 ///
 /// # Examples
 ///
 /// ```c
-/// const char* config = get_configuration();
-/// // Use config...
-/// free_const_string(config); // Must free when done
+/// swc_result* result = swc_minify_js_with_source_map(js, "bundle.js", NULL, swc_source_map_mode::Separate);
+/// if (result->code) {
+///     write_file("bundle.min.js", result->code);
+///     write_file("bundle.min.js.map", result->map);
+/// }
+/// swc_result_free(result);
 /// ```
 #[no_mangle]
-pub extern "C" fn free_const_string(s: *const c_char) {
-    unsafe {
-        let _ = CString::from_raw(s as *mut c_char);
-    };
+pub extern "C" fn swc_minify_js_with_source_map(
+    code: *const c_char,
+    filename: *const c_char,
+    input_source_map: *const c_char,
+    mode: swc_source_map_mode,
+) -> *mut swc_result {
+    into_result_ptr(guard(|| {
+        let input = input_str(code, "code")?;
+        let input_source_map = optional_str(input_source_map);
+        let file = File::FileName(input_name(filename), input.into());
+        Ok(minify(file, mode.into(), input_source_map.as_deref()))
+    }))
 }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use std::ffi::CStr;
-    use std::ffi::CString;
-
-    #[test]
-    fn test_transpile() {
-        let file = CString::new("test.ts").expect("failed to convert file to CString");
-        let input =
-            CString::new("const a: number = 1;").expect("failed to convert input to CString");
-        let output = transpile(file.as_ptr(), input.as_ptr());
-        assert_eq!(
-            unsafe { CStr::from_ptr(output).to_str().unwrap() },
-            "const a = 1;\n"
-        );
-        free_string(output);
-    }
+/// Minifies a JavaScript file and reports structured diagnostics.
+///
+/// Behaves like `minify_js_file`, but returns an `swc_result` carrying the minified code
+/// together with every error and warning.
+///
+/// # Parameters
+///
+/// * `filepath` - C string pointer to the path of the JavaScript file to minify
+///
+/// # Returns
+///
+/// Pointer to an `swc_result`. `code` is null if minification failed, in which case
+/// `diagnostics` contains at least one error.
+///
+/// # Safety
+///
+/// This function is unsafe because:
+/// * It dereferences raw pointers
+/// * It allocates memory that must be freed by the caller using `swc_result_free()`
+#[no_mangle]
+pub extern "C" fn swc_minify_js_file(filepath: *const c_char) -> *mut swc_result {
+    into_result_ptr(guard(|| {
+        let path = input_str(filepath, "filepath")?;
+        Ok(minify(File::path(path), SourceMapMode::None, None))
+    }))
+}
 
-    #[test]
-    fn test_transpile_ts() {
-        let code = r#"
-            interface User {
-                name: string;
-            }
+/// Minifies JavaScript code with the given minifier options and reports structured
+/// diagnostics.
+///
+/// Behaves like `minify_js_with_options`, but returns an `swc_result`. If the options enable
+/// `sourceMap`, the map is returned in `map`; its `content` may hold the input source map.
+///
+/// # Parameters
+///
+/// * `code` - C string pointer containing the JavaScript code to minify
+/// * `filename` - Name of the input, used as source name in the map; may be null
+/// * `options_json` - Minifier options as JSON; null behaves like `{}`
+///
+/// # Returns
+///
+/// Pointer to an `swc_result`. `code` is null if minification failed, in which case
+/// `diagnostics` contains at least one error. Invalid options fail with
+/// `swc_status::InvalidConfig`.
+///
+/// # Safety
+///
+/// This function is unsafe because:
+/// * It dereferences raw pointers
+/// * It allocates memory that must be freed by the caller using `swc_result_free()`
+#[no_mangle]
+pub extern "C" fn swc_minify_js_with_options(
+    code: *const c_char,
+    filename: *const c_char,
+    options_json: *const c_char,
+) -> *mut swc_result {
+    into_result_ptr(guard(|| {
+        let input = input_str(code, "code")?;
+        let options = optional_str(options_json);
+        let file = File::FileName(input_name(filename), input.into());
+        minify_json(file, options.as_deref(), None)
+    }))
+}
 
-            const greet = (user: User) => `Hello ${user.name}`;
+/// Minifies a JavaScript file with the given minifier options and reports structured
+/// diagnostics.
+///
+/// Behaves like `swc_minify_js_with_options`, but reads the code from `filepath`.
+///
+/// # Parameters
+///
+/// * `filepath` - C string pointer to the path of the JavaScript file to minify
+/// * `options_json` - Minifier options as JSON; null behaves like `{}`
+///
+/// # Returns
+///
+/// Pointer to an `swc_result`, see `swc_minify_js_with_options`
+///
+/// # Safety
+///
+/// This function is unsafe because:
+/// * It dereferences raw pointers
+/// * It allocates memory that must be freed by the caller using `swc_result_free()`
+#[no_mangle]
+pub extern "C" fn swc_minify_js_file_with_options(
+    filepath: *const c_char,
+    options_json: *const c_char,
+) -> *mut swc_result {
+    into_result_ptr(guard(|| {
+        let path = input_str(filepath, "filepath")?;
+        let options = optional_str(options_json);
+        minify_json(File::path(path), options.as_deref(), None)
+    }))
+}
 
-            const user: User = { name: 'Manne' };
+/// Minifies JavaScript code with names shared through a mangle cache and reports structured
+/// diagnostics.
+///
+/// Behaves like `minify_js_with_cache`, but returns an `swc_result` like
+/// `swc_minify_js_with_options`.
+///
+/// # Parameters
+///
+/// * `code` - C string pointer containing the JavaScript code to minify
+/// * `filename` - Name of the input, used as source name in the map; may be null
+/// * `options_json` - Minifier options as JSON; null behaves like `{}`
+/// * `cache` - Cache created with `swc_mangle_cache_new()` or `swc_mangle_cache_from_json()`
+///
+/// # Returns
+///
+/// Pointer to an `swc_result`, see `swc_minify_js_with_options`
+///
+/// # Safety
+///
+/// This function is unsafe because:
+/// * It dereferences raw pointers
+/// * It allocates memory that must be freed by the caller using `swc_result_free()`
+#[no_mangle]
+pub extern "C" fn swc_minify_js_with_cache(
+    code: *const c_char,
+    filename: *const c_char,
+    options_json: *const c_char,
+    cache: *const swc_mangle_cache,
+) -> *mut swc_result {
+    into_result_ptr(guard(|| {
+        let input = input_str(code, "code")?;
+        let options = optional_str(options_json);
+        let cache = mangle_cache_ref(cache)?;
+        let file = File::FileName(input_name(filename), input.into());
+        minify_json(file, options.as_deref(), Some(cache))
+    }))
+}
 
-            console.log(greet(user));
-        "#;
-        let result = "const greet = (user)=>`Hello ${user.name}`;\nconst user = {\n    name: 'Manne'\n};\nconsole.log(greet(user));\n";
-        let file = CString::new("test.ts").expect("failed to convert file to CString");
-        let input = CString::new(code).expect("failed to convert code to CString");
-        let output = transpile(file.as_ptr(), input.as_ptr());
-        assert_eq!(unsafe { CStr::from_ptr(output).to_str().unwrap() }, result);
-        free_string(output);
+/// Transpiles TypeScript/TSX code and reports structured diagnostics.
+///
+/// Behaves like `transpile`, but returns an `swc_result` carrying the transpiled code
+/// together with every error and warning instead of a bare null pointer.
+///
+/// # Parameters
+///
+/// * `file` - The file name, used to detect TSX and reported in diagnostics
+/// * `input` - The TypeScript/TSX code
+///
+/// # Returns
+///
+/// Pointer to an `swc_result`. `code` is null if transpilation failed, in which case
+/// `diagnostics` contains at least one error.
+///
+/// # Safety
+///
+/// This function is unsafe because:
+/// * It dereferences raw pointers
+/// * It allocates memory that must be freed by the caller using `swc_result_free()`
+#[no_mangle]
+pub extern "C" fn swc_transpile(file: *const c_char, input: *const c_char) -> *mut swc_result {
+    into_result_ptr(guard(|| {
+        let file = input_str(file, "file")?;
+        let input = input_str(input, "input")?;
+        let cm: Lrc<SourceMap> = Default::default();
+        Ok(transpile_outcome(
+            cm,
+            File::FileName(FileName::Custom(String::from(file)), input.into()),
+            &TranspileOptions::default(),
+        ))
+    }))
+}
+
+/// Transpiles TypeScript/TSX code with custom options and reports structured diagnostics.
+///
+/// Behaves like `transpile_with_options`, but returns an `swc_result` carrying the
+/// transpiled code together with every error and warning.
+///
+/// # Parameters
+///
+/// * `file` - The file name, used to detect TSX and reported in diagnostics
+/// * `input` - The TypeScript/TSX code
+/// * `options` - Options created with `swc_transpile_options_new()`, or null for the defaults
+///
+/// # Returns
+///
+/// Pointer to an `swc_result`. `code` is null if transpilation failed, in which case
+/// `diagnostics` contains at least one error.
+///
+/// # Safety
+///
+/// This function is unsafe because:
+/// * It dereferences raw pointers
+/// * It allocates memory that must be freed by the caller using `swc_result_free()`
+#[no_mangle]
+pub extern "C" fn swc_transpile_with_options(
+    file: *const c_char,
+    input: *const c_char,
+    options: *const swc_transpile_options,
+) -> *mut swc_result {
+    into_result_ptr(guard(|| {
+        let file = input_str(file, "file")?;
+        let input = input_str(input, "input")?;
+        let cm: Lrc<SourceMap> = Default::default();
+        Ok(transpile_outcome(
+            cm,
+            File::FileName(FileName::Custom(String::from(file)), input.into()),
+            &transpile_options(options),
+        ))
+    }))
+}
+
+/// Transpiles a TypeScript/TSX file and reports structured diagnostics.
+///
+/// Behaves like `transpile_file`, but returns an `swc_result` carrying the transpiled code
+/// together with every error and warning instead of a bare null pointer.
+///
+/// # Parameters
+///
+/// * `filename` - C string pointer to the path of the TypeScript/TSX file
+///
+/// # Returns
+///
+/// Pointer to an `swc_result`. `code` is null if transpilation failed, in which case
+/// `diagnostics` contains at least one error.
+///
+/// # Safety
+///
+/// This function is unsafe because:
+/// * It dereferences raw pointers
+/// * It allocates memory that must be freed by the caller using `swc_result_free()`
+#[no_mangle]
+pub extern "C" fn swc_transpile_file(filename: *const c_char) -> *mut swc_result {
+    into_result_ptr(guard(|| {
+        let file = input_str(filename, "filename")?;
+        let cm: Lrc<SourceMap> = Default::default();
+        Ok(transpile_outcome(
+            cm,
+            File::path(file),
+            &TranspileOptions::default(),
+        ))
+    }))
+}
+
+/// Compiles TypeScript/JavaScript code given as a length-delimited buffer.
+///
+/// This function behaves like `compile_js`, but takes the input as `(data, len)` and returns
+/// an `swc_buffer`. Neither side needs to be NUL-terminated, so sources containing `\0` in
+/// string literals round-trip and `std::string_view` inputs can be passed without a copy.
+///
+/// # Parameters
+///
+/// * `data` - Pointer to `len` bytes of UTF-8 encoded source code; may be null if `len` is 0
+/// * `len` - Number of bytes in `data`
+/// * `error` - Buffer that receives the error message if compilation fails; may be null
+///
+/// # Returns
+///
+/// * On success: A buffer with the compiled JavaScript
+/// * On failure: A buffer with a null `ptr`, with the error message populated in `error`
+///
+/// # Safety
+///
+/// This function is unsafe because:
+/// * It dereferences raw pointers
+/// * It allocates memory that must be freed by the caller using `swc_buffer_free()`
+///
+/// # Examples
+///
+/// ```c
+/// std::string_view src = "const a = 1;";
+/// swc_buffer error = {0};
+/// swc_buffer js = swc_compile_js_buf((const uint8_t*)src.data(), src.size(), &error);
+/// if (js.ptr) {
+///     fwrite(js.ptr, 1, js.len, stdout);
+///     swc_buffer_free(js);
+/// } else {
+///     fwrite(error.ptr, 1, error.len, stderr);
+///     swc_buffer_free(error);
+/// }
+/// ```
+#[no_mangle]
+pub extern "C" fn swc_compile_js_buf(
+    data: *const u8,
+    len: usize,
+    error: *mut swc_buffer,
+) -> swc_buffer {
+    outcome_to_buffer(
+        error,
+        guard(|| {
+            let input = input_bytes(data, len, "data")?;
+            let file = File::FileName(FileName::Custom("input.js".into()), input.into());
+            Ok(compile(file, None, None))
+        }),
+    )
+}
+
+/// Minifies JavaScript code given as a length-delimited buffer.
+///
+/// This function behaves like `minify_js`, but takes the input as `(data, len)` and returns
+/// an `swc_buffer`, so neither side needs to be NUL-terminated.
+///
+/// # Parameters
+///
+/// * `data` - Pointer to `len` bytes of UTF-8 encoded JavaScript; may be null if `len` is 0
+/// * `len` - Number of bytes in `data`
+/// * `error` - Buffer that receives the error message if minification fails; may be null
+///
+/// # Returns
+///
+/// * On success: A buffer with the minified JavaScript
+/// * On failure: A buffer with a null `ptr`, with the error message populated in `error`
+///
+/// # Safety
+///
+/// This function is unsafe because:
+/// * It dereferences raw pointers
+/// * It allocates memory that must be freed by the caller using `swc_buffer_free()`
+#[no_mangle]
+pub extern "C" fn swc_minify_js_buf(
+    data: *const u8,
+    len: usize,
+    error: *mut swc_buffer,
+) -> swc_buffer {
+    outcome_to_buffer(
+        error,
+        guard(|| {
+            let input = input_bytes(data, len, "data")?;
+            let file = File::FileName(FileName::Custom("input.js".into()), input.into());
+            Ok(minify(file, SourceMapMode::None, None))
+        }),
+    )
+}
+
+/// Transpiles TypeScript/TSX code given as a length-delimited buffer.
+///
+/// This function behaves like `transpile_with_options`, but takes the file name and the
+/// input as `(pointer, length)` pairs and returns an `swc_buffer`, so neither side needs to
+/// be NUL-terminated.
+///
+/// # Parameters
+///
+/// * `filename` - Pointer to `filename_len` bytes of UTF-8 encoded file name
+/// * `filename_len` - Number of bytes in `filename`
+/// * `data` - Pointer to `len` bytes of UTF-8 encoded TypeScript/TSX; may be null if `len` is 0
+/// * `len` - Number of bytes in `data`
+/// * `options` - Options created with `swc_transpile_options_new()`, or null for the defaults
+/// * `error` - Buffer that receives the error message if transpilation fails; may be null
+///
+/// # Returns
+///
+/// * On success: A buffer with the transpiled JavaScript
+/// * On failure: A buffer with a null `ptr`, with the error message populated in `error`
+///
+/// # Safety
+///
+/// This function is unsafe because:
+/// * It dereferences raw pointers
+/// * It allocates memory that must be freed by the caller using `swc_buffer_free()`
+#[no_mangle]
+pub extern "C" fn swc_transpile_buf(
+    filename: *const u8,
+    filename_len: usize,
+    data: *const u8,
+    len: usize,
+    options: *const swc_transpile_options,
+    error: *mut swc_buffer,
+) -> swc_buffer {
+    outcome_to_buffer(
+        error,
+        guard(|| {
+            let file = input_bytes(filename, filename_len, "filename")?;
+            let input = input_bytes(data, len, "data")?;
+            let cm: Lrc<SourceMap> = Default::default();
+            Ok(transpile_outcome(
+                cm,
+                File::FileName(FileName::Custom(String::from(file)), input.into()),
+                &transpile_options(options),
+            ))
+        }),
+    )
+}
+
+/// Frees memory allocated by string-returning FFI functions.
+///
+/// This function properly deallocates memory that was allocated by functions
+/// like `minify_js` and `minify_js_file` which return a `*mut c_char`.
+/// The string is released with the allocator it was allocated with, see
+/// `swc_set_allocator`.
+///
+/// # Parameters
+///
+/// * `s` - Mutable pointer to a C string previously returned by an FFI function
+///
+/// Passing a null pointer is a no-op.
+///
+/// # Safety
+///
+/// This function is unsafe because:
+/// * It converts a raw pointer back to a Rust type
+/// * The pointer must have been returned by this library
+/// * The pointer must not have been freed already
+/// * The pointer must not be used after this call
+///
+/// # Examples
+///
+/// ```c
+/// char error[256] = {0};
+/// char* result = minify_js(js_code, error);
+/// if (result) {
+///     // Use result...
+///     free_string(result); // Must free when done
+/// }
+/// ```
+#[no_mangle]
+pub extern "C" fn free_string(s: *mut c_char) {
+    free_c_string(s);
+}
+
+/// Frees memory allocated for constant string pointers.
+///
+/// This function properly deallocates memory pointed to by a constant C string pointer.
+/// It is used for freeing strings that were allocated by Rust but returned as read-only
+/// pointers to C. The string is released with the allocator it was allocated with, see
+/// `swc_set_allocator`.
+///
+/// # Parameters
+///
+/// * `s` - Constant pointer to a C string previously allocated by a Rust FFI function
+///
+/// Passing a null pointer is a no-op.
+///
+/// # Safety
+///
+/// This function is unsafe because:
+/// * It converts a raw pointer back to a Rust type
+/// * It casts a const pointer to a mutable pointer
+/// * The pointer must have been returned by this library
+/// * The pointer must not have been freed already
+/// * The pointer must not be used after this call
+///
+/// # Examples
+///
+/// ```c
+/// const char* config = get_configuration();
+/// // Use config...
+/// free_const_string(config); // Must free when done
+/// ```
+#[no_mangle]
+pub extern "C" fn free_const_string(s: *const c_char) {
+    free_c_string(s as *mut c_char);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::ffi::CString;
+    use std::ffi::{c_void, CStr};
+
+    #[test]
+    fn test_transpile() {
+        let file = CString::new("test.ts").expect("failed to convert file to CString");
+        let input =
+            CString::new("const a: number = 1;").expect("failed to convert input to CString");
+        let output = transpile(file.as_ptr(), input.as_ptr());
+        assert_eq!(
+            unsafe { CStr::from_ptr(output).to_str().unwrap() },
+            "const a = 1;\n"
+        );
+        free_string(output);
+    }
+
+    #[test]
+    fn test_transpile_ts() {
+        let code = r#"
+            interface User {
+                name: string;
+            }
+
+            const greet = (user: User) => `Hello ${user.name}`;
+
+            const user: User = { name: 'Manne' };
+
+            console.log(greet(user));
+        "#;
+        let result = "const greet = (user)=>`Hello ${user.name}`;\nconst user = {\n    name: 'Manne'\n};\nconsole.log(greet(user));\n";
+        let file = CString::new("test.ts").expect("failed to convert file to CString");
+        let input = CString::new(code).expect("failed to convert code to CString");
+        let output = transpile(file.as_ptr(), input.as_ptr());
+        assert_eq!(unsafe { CStr::from_ptr(output).to_str().unwrap() }, result);
+        free_string(output);
+    }
+
+    fn diagnostics(result: &swc_result) -> &[swc_diagnostic] {
+        if result.diagnostics.is_null() {
+            return &[];
+        }
+        unsafe { std::slice::from_raw_parts(result.diagnostics, result.diagnostics_len) }
+    }
+
+    #[test]
+    fn test_swc_transpile_reports_syntax_error_location() {
+        let file = CString::new("broken.ts").expect("failed to convert file to CString");
+        let input = CString::new("const a = 1;\nconst b: number = ;\n")
+            .expect("failed to convert input to CString");
+        let result = swc_transpile(file.as_ptr(), input.as_ptr());
+        let result_ref = unsafe { &*result };
+
+        assert!(result_ref.code.is_null());
+        let diagnostics = diagnostics(result_ref);
+        assert_eq!(diagnostics.len(), 1);
+
+        let d = &diagnostics[0];
+        assert_eq!(d.severity, swc_severity::Error);
+        assert_eq!(
+            unsafe { CStr::from_ptr(d.file).to_str().unwrap() },
+            "broken.ts"
+        );
+        assert_eq!(d.line, 2);
+        assert_eq!(d.column, 18);
+        assert_eq!(d.span_start, 31);
+        assert!(!unsafe { CStr::from_ptr(d.message).to_bytes() }.is_empty());
+        swc_result_free(result);
+    }
+
+    #[test]
+    fn test_swc_compile_js_success_has_no_diagnostics() {
+        let input = CString::new("const a = 1;").expect("failed to convert input to CString");
+        let result = swc_compile_js(input.as_ptr());
+        let result_ref = unsafe { &*result };
+
+        assert!(!result_ref.code.is_null());
+        assert!(diagnostics(result_ref).is_empty());
+        swc_result_free(result);
+    }
+
+    #[test]
+    fn test_swc_minify_js_file_reports_missing_file() {
+        let path = CString::new("does/not/exist.js").expect("failed to convert path to CString");
+        let result = swc_minify_js_file(path.as_ptr());
+        let result_ref = unsafe { &*result };
+
+        assert!(result_ref.code.is_null());
+        let diagnostics = diagnostics(result_ref);
+        assert_eq!(diagnostics.len(), 1);
+        assert!(diagnostics[0].file.is_null());
+        assert!(
+            unsafe { CStr::from_ptr(diagnostics[0].message).to_str().unwrap() }
+                .contains("does/not/exist.js")
+        );
+        swc_result_free(result);
+    }
+
+    #[test]
+    fn test_compile_js_error_message_contains_location() {
+        let input = CString::new("let x = {;").expect("failed to convert input to CString");
+        let mut error: *mut c_char = std::ptr::null_mut();
+        let output = compile_js(input.as_ptr(), &mut error);
+
+        assert!(output.is_null());
+        let message = unsafe { CStr::from_ptr(error).to_str().unwrap() }.to_owned();
+        assert!(message.starts_with("input.js:1:"), "{message}");
+        free_string(error);
+    }
+
+    #[test]
+    fn test_transpile_with_options() {
+        let options = swc_transpile_options_new();
+        let pragma = CString::new("h").expect("failed to convert pragma to CString");
+        let frag = CString::new("Fragment").expect("failed to convert pragma to CString");
+        swc_transpile_options_set_jsx_pragma(options, pragma.as_ptr());
+        swc_transpile_options_set_jsx_pragma_frag(options, frag.as_ptr());
+        swc_transpile_options_set_tsx(options, true);
+        swc_transpile_options_set_minify(options, true);
+        swc_transpile_options_set_comments(options, false);
+
+        let file = CString::new("input").expect("failed to convert file to CString");
+        let input = CString::new("/* c */ const a: number = 1; const b = <><p /></>;")
+            .expect("failed to convert input to CString");
+        let output = transpile_with_options(file.as_ptr(), input.as_ptr(), options);
+        assert_eq!(
+            unsafe { CStr::from_ptr(output).to_str().unwrap() },
+            "const a=1;const b=h(Fragment,null,h(\"p\",null));"
+        );
+        free_string(output);
+        swc_transpile_options_free(options);
+    }
+
+    #[test]
+    fn test_transpile_with_null_options_uses_defaults() {
+        let file = CString::new("test.ts").expect("failed to convert file to CString");
+        let input =
+            CString::new("const a: number = 1;").expect("failed to convert input to CString");
+        let output = transpile_with_options(file.as_ptr(), input.as_ptr(), std::ptr::null());
+        assert_eq!(
+            unsafe { CStr::from_ptr(output).to_str().unwrap() },
+            "const a = 1;\n"
+        );
+        free_string(output);
+    }
+
+    #[test]
+    fn test_compile_js_with_config() {
+        let input = CString::new("export const a: number = 1;")
+            .expect("failed to convert input to CString");
+        let filename = CString::new("a.ts").expect("failed to convert filename to CString");
+        let config = CString::new(
+            r#"{ "swcrc": false, "jsc": { "parser": { "syntax": "typescript" }, "target": "es2020" }, "module": { "type": "commonjs" } }"#,
+        )
+        .expect("failed to convert config to CString");
+        let mut error: *mut c_char = std::ptr::null_mut();
+        let output = compile_js_with_config(
+            input.as_ptr(),
+            filename.as_ptr(),
+            config.as_ptr(),
+            &mut error,
+        );
+
+        assert!(error.is_null());
+        let output_str = unsafe { CStr::from_ptr(output).to_str().unwrap() };
+        assert!(output_str.contains("exports"), "{output_str}");
+        assert!(output_str.contains("const a = 1;"), "{output_str}");
+        free_string(output);
+    }
+
+    #[test]
+    fn test_compile_js_with_invalid_config() {
+        let input = CString::new("const a = 1;").expect("failed to convert input to CString");
+        let config = CString::new(r#"{ "jsc": { "target": 42 } }"#)
+            .expect("failed to convert config to CString");
+        let result = swc_compile_js_with_config(
+            input.as_ptr(),
+            std::ptr::null(),
+            config.as_ptr(),
+            std::ptr::null(),
+        );
+        let result_ref = unsafe { &*result };
+
+        assert!(result_ref.code.is_null());
+        let diagnostics = diagnostics(result_ref);
+        assert_eq!(diagnostics.len(), 1);
+        assert!(
+            unsafe { CStr::from_ptr(diagnostics[0].message).to_str().unwrap() }
+                .starts_with("failed to parse config")
+        );
+        swc_result_free(result);
+    }
+
+    #[test]
+    fn test_compile_file_with_config_discovers_swcrc() {
+        let dir = std::env::temp_dir().join(format!("swc_cxx_swcrc_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).expect("failed to create temp dir");
+        std::fs::write(
+            dir.join(".swcrc"),
+            r#"{ "jsc": { "parser": { "syntax": "typescript" } }, "module": { "type": "commonjs" } }"#,
+        )
+        .expect("failed to write .swcrc");
+        let file = dir.join("input.ts");
+        std::fs::write(&file, "export const a: number = 1;").expect("failed to write input");
+
+        let path = CString::new(file.to_str().unwrap()).expect("failed to convert path to CString");
+        let mut error: *mut c_char = std::ptr::null_mut();
+        let output = compile_file_with_config(path.as_ptr(), std::ptr::null(), &mut error);
+        std::fs::remove_dir_all(&dir).ok();
+
+        assert!(error.is_null());
+        let output_str = unsafe { CStr::from_ptr(output).to_str().unwrap() };
+        assert!(output_str.contains("exports"), "{output_str}");
+        free_string(output);
+    }
+
+    #[test]
+    fn test_swc_transpile_with_separate_source_map() {
+        let options = swc_transpile_options_new();
+        swc_transpile_options_set_source_maps(options, swc_source_map_mode::Separate);
+        let file = CString::new("app.ts").expect("failed to convert file to CString");
+        let input = CString::new("const a: number = 1;\nconsole.log(a);")
+            .expect("failed to convert input to CString");
+        let result = swc_transpile_with_options(file.as_ptr(), input.as_ptr(), options);
+        let result_ref = unsafe { &*result };
+
+        assert_eq!(
+            unsafe { CStr::from_ptr(result_ref.code).to_str().unwrap() },
+            "const a = 1;\nconsole.log(a);\n"
+        );
+        let map = unsafe { CStr::from_ptr(result_ref.map).to_str().unwrap() };
+        assert!(map.contains(r#""sources":["app.ts"]"#), "{map}");
+        assert!(map.contains("const a: number = 1;"), "{map}");
+        swc_result_free(result);
+        swc_transpile_options_free(options);
+    }
+
+    #[test]
+    fn test_swc_minify_js_with_inline_source_map() {
+        let input = CString::new("function add(first, second) { return first + second; }")
+            .expect("failed to convert input to CString");
+        let file = CString::new("add.js").expect("failed to convert file to CString");
+        let result = swc_minify_js_with_source_map(
+            input.as_ptr(),
+            file.as_ptr(),
+            std::ptr::null(),
+            swc_source_map_mode::Inline,
+        );
+        let result_ref = unsafe { &*result };
+
+        assert!(result_ref.map.is_null());
+        let code = unsafe { CStr::from_ptr(result_ref.code).to_str().unwrap() };
+        assert!(
+            code.contains("\n//# sourceMappingURL=data:application/json;base64,"),
+            "{code}"
+        );
+        swc_result_free(result);
+    }
+
+    #[test]
+    fn test_swc_compile_js_with_config_returns_source_map() {
+        let input = CString::new("const a = 1;").expect("failed to convert input to CString");
+        let config = CString::new(r#"{ "swcrc": false, "sourceMaps": true }"#)
+            .expect("failed to convert config to CString");
+        let result = swc_compile_js_with_config(
+            input.as_ptr(),
+            std::ptr::null(),
+            config.as_ptr(),
+            std::ptr::null(),
+        );
+        let result_ref = unsafe { &*result };
+
+        assert!(!result_ref.code.is_null());
+        assert!(unsafe { CStr::from_ptr(result_ref.map).to_str().unwrap() }.contains("mappings"));
+        swc_result_free(result);
+    }
+
+    #[test]
+    fn test_transpile_then_minify_chains_source_maps() {
+        let options = swc_transpile_options_new();
+        swc_transpile_options_set_source_maps(options, swc_source_map_mode::Separate);
+        let file = CString::new("greet.ts").expect("failed to convert file to CString");
+        let input = CString::new(
+            "function greet(name: string): string {\n    return `Hello ${name}`;\n}\nconsole.log(greet('World'));\n",
+        )
+        .expect("failed to convert input to CString");
+        let transpiled = swc_transpile_with_options(file.as_ptr(), input.as_ptr(), options);
+        let transpiled_ref = unsafe { &*transpiled };
+
+        let minified_name = CString::new("greet.js").expect("failed to convert file to CString");
+        let minified = swc_minify_js_with_source_map(
+            transpiled_ref.code,
+            minified_name.as_ptr(),
+            transpiled_ref.map,
+            swc_source_map_mode::Separate,
+        );
+        let minified_ref = unsafe { &*minified };
+
+        let map = unsafe { CStr::from_ptr(minified_ref.map).to_str().unwrap() };
+        assert!(map.contains(r#""sources":["greet.ts"]"#), "{map}");
+        assert!(map.contains("name: string"), "{map}");
+        swc_result_free(minified);
+        swc_result_free(transpiled);
+        swc_transpile_options_free(options);
+    }
+
+    #[test]
+    fn test_transpile_with_invalid_input_source_map() {
+        let options = swc_transpile_options_new();
+        let map = CString::new("not a source map").expect("failed to convert map to CString");
+        swc_transpile_options_set_source_maps(options, swc_source_map_mode::Separate);
+        swc_transpile_options_set_input_source_map(options, map.as_ptr());
+        let file = CString::new("a.ts").expect("failed to convert file to CString");
+        let input = CString::new("const a = 1;").expect("failed to convert input to CString");
+        let result = swc_transpile_with_options(file.as_ptr(), input.as_ptr(), options);
+        let result_ref = unsafe { &*result };
+
+        assert!(result_ref.code.is_null());
+        assert!(unsafe {
+            CStr::from_ptr(diagnostics(result_ref)[0].message)
+                .to_str()
+                .unwrap()
+        }
+        .starts_with("failed to parse input source map"));
+        swc_result_free(result);
+        swc_transpile_options_free(options);
+    }
+
+    /// Inputs that used to abort the host: a null pointer and a string that is not UTF-8.
+    fn malformed_inputs(invalid_utf8: &CString) -> [*const c_char; 2] {
+        [std::ptr::null(), invalid_utf8.as_ptr()]
+    }
+
+    type LegacyExport = dyn Fn(*const c_char, *mut *mut c_char) -> *mut c_char;
+
+    fn invalid_utf8() -> CString {
+        CString::new(vec![b'a', 0xff, 0xfe]).expect("failed to convert input to CString")
+    }
+
+    #[test]
+    fn test_legacy_exports_reject_malformed_input() {
+        let invalid = invalid_utf8();
+        let valid = CString::new("test.ts").expect("failed to convert input to CString");
+        let valid = valid.as_ptr();
+        let exports: [(&str, &LegacyExport); 6] = [
+            ("compile_file", &|p, e| compile_file(p, e)),
+            ("compile_js", &|p, e| compile_js(p, e)),
+            ("compile_js_with_config", &|p, e| {
+                compile_js_with_config(p, std::ptr::null(), std::ptr::null(), e)
+            }),
+            ("compile_file_with_config", &|p, e| {
+                compile_file_with_config(p, std::ptr::null(), e)
+            }),
+            ("minify_js_file", &|p, e| minify_js_file(p, e)),
+            ("minify_js", &|p, e| minify_js(p, e)),
+        ];
+
+        for (name, export) in exports {
+            for input in malformed_inputs(&invalid) {
+                let mut error: *mut c_char = std::ptr::null_mut();
+                assert!(export(input, &mut error).is_null(), "{name}");
+                assert!(!error.is_null(), "{name}");
+                free_string(error);
+            }
+            assert!(
+                export(std::ptr::null(), std::ptr::null_mut()).is_null(),
+                "{name}"
+            );
+        }
+
+        for input in malformed_inputs(&invalid) {
+            assert!(transpile(input, valid).is_null());
+            assert!(transpile(valid, input).is_null());
+            assert!(transpile_with_options(input, valid, std::ptr::null()).is_null());
+            assert!(transpile_with_options(valid, input, std::ptr::null()).is_null());
+            assert!(transpile_file(input).is_null());
+        }
+    }
+
+    #[test]
+    fn test_swc_exports_report_malformed_input() {
+        let invalid = invalid_utf8();
+        let valid = CString::new("test.ts").expect("failed to convert input to CString");
+        let valid = valid.as_ptr();
+        let null = std::ptr::null();
+
+        for input in malformed_inputs(&invalid) {
+            let results = [
+                swc_compile_js(input),
+                swc_compile_file(input),
+                swc_compile_js_with_config(input, null, null, null),
+                swc_compile_file_with_config(input, null),
+                swc_minify_js(input),
+                swc_minify_js_file(input),
+                swc_minify_js_with_source_map(input, null, null, swc_source_map_mode::Separate),
+                swc_transpile(input, valid),
+                swc_transpile(valid, input),
+                swc_transpile_with_options(input, valid, std::ptr::null()),
+                swc_transpile_with_options(valid, input, std::ptr::null()),
+                swc_transpile_file(input),
+                swc_session_compile(std::ptr::null_mut(), null, valid),
+                swc_session_transpile(std::ptr::null_mut(), valid, valid),
+            ];
+
+            for result in results {
+                let result_ref = unsafe { &*result };
+                assert!(result_ref.code.is_null());
+                let message = unsafe { CStr::from_ptr(diagnostics(result_ref)[0].message) }
+                    .to_str()
+                    .unwrap();
+                assert!(
+                    message.ends_with("must not be null") || message.contains("not valid UTF-8"),
+                    "{message}"
+                );
+                swc_result_free(result);
+            }
+        }
+    }
+
+    #[test]
+    fn test_free_functions_accept_null() {
+        free_string(std::ptr::null_mut());
+        free_const_string(std::ptr::null());
+        swc_result_free(std::ptr::null_mut());
+        swc_transpile_options_free(std::ptr::null_mut());
+        swc_transpile_options_set_jsx_pragma(std::ptr::null_mut(), std::ptr::null());
+        swc_transpile_options_set_decorators(std::ptr::null_mut(), false);
+        swc_session_free(std::ptr::null_mut());
+        swc_session_reset(std::ptr::null_mut());
+        assert_eq!(swc_session_source_file_count(std::ptr::null()), 0);
+    }
+
+    fn buffer_str(buffer: &swc_buffer) -> &str {
+        std::str::from_utf8(unsafe { std::slice::from_raw_parts(buffer.ptr, buffer.len) }).unwrap()
+    }
+
+    #[test]
+    fn test_transpile_buf_keeps_nul_in_string_literals() {
+        let filename = "nul.ts";
+        let input = "const s: string = \"a\0b\";";
+        let mut error = swc_buffer::null();
+        let output = swc_transpile_buf(
+            filename.as_ptr(),
+            filename.len(),
+            input.as_ptr(),
+            input.len(),
+            std::ptr::null(),
+            &mut error,
+        );
+
+        assert!(error.ptr.is_null());
+        assert_eq!(buffer_str(&output), "const s = \"a\0b\";\n");
+        swc_buffer_free(output);
+    }
+
+    #[test]
+    fn test_output_with_nul_fails_instead_of_being_cut() {
+        let output = |code: &str, licenses: Vec<String>| {
+            Outcome::success(Output {
+                code: code.to_owned(),
+                licenses,
+                ..Default::default()
+            })
+        };
+
+        let error = to_c_string("a\0b").unwrap_err();
+        assert_eq!(Status::of(&error), Some(Status::InvalidArgument));
+
+        let code = result_to_char_ptr(output("const s = \"a\0b\";", Vec::new()));
+        assert!(code.is_null());
+        assert_eq!(swc_last_status(), swc_status::InvalidArgument);
+
+        let license = String::from("/*! a\0b */");
+        let result = into_result_ptr(output("const a = 1;", vec![license]));
+        let result_ref = unsafe { &*result };
+        assert!(result_ref.code.is_null());
+        assert!(result_ref.licenses.is_null());
+        assert_eq!(
+            unsafe { CStr::from_ptr(diagnostics(result_ref)[0].message) }.to_str(),
+            Ok("generated output contains an interior NUL byte")
+        );
+        assert_eq!(result_status(result), swc_status::InvalidArgument);
+    }
+
+    #[test]
+    fn test_compile_js_buf_reads_only_len_bytes() {
+        let input = "const a = 1;const b = ";
+        let mut error = swc_buffer::null();
+        let output = swc_compile_js_buf(input.as_ptr(), 12, &mut error);
+
+        assert!(error.ptr.is_null());
+        assert_eq!(buffer_str(&output), "var a = 1;\n");
+        swc_buffer_free(output);
+    }
+
+    #[test]
+    fn test_minify_js_buf_reports_errors() {
+        let input = [b'a', 0xff];
+        let mut error = swc_buffer::null();
+        let output = swc_minify_js_buf(input.as_ptr(), input.len(), &mut error);
+
+        assert!(output.ptr.is_null());
+        assert!(buffer_str(&error).contains("not valid UTF-8"));
+        swc_buffer_free(error);
+        swc_buffer_free(output);
+
+        let output = swc_minify_js_buf(std::ptr::null(), 0, std::ptr::null_mut());
+        assert!(!output.ptr.is_null());
+        assert_eq!(output.len, 0);
+        swc_buffer_free(output);
+    }
+
+    fn result_code(result: *mut swc_result) -> String {
+        let result_ref = unsafe { &*result };
+        assert!(!result_ref.code.is_null(), "{:?}", unsafe {
+            CStr::from_ptr(diagnostics(result_ref)[0].message)
+        });
+        let code = unsafe { CStr::from_ptr(result_ref.code) }
+            .to_str()
+            .unwrap()
+            .to_owned();
+        swc_result_free(result);
+        code
+    }
+
+    #[test]
+    fn test_session_reuses_compiler_across_files() {
+        let options = swc_transpile_options_new();
+        let pragma = CString::new("h").unwrap();
+        swc_transpile_options_set_jsx_pragma(options, pragma.as_ptr());
+        let session = swc_session_new(std::ptr::null(), options);
+        swc_transpile_options_free(options);
+
+        let tsx = CString::new("app.tsx").unwrap();
+        let jsx = CString::new("const a = <div />;").unwrap();
+        let js = CString::new("function add(a, b) { return a + b; } add(1, 2);").unwrap();
+
+        for _ in 0..3 {
+            assert_eq!(
+                result_code(swc_session_transpile(session, tsx.as_ptr(), jsx.as_ptr())),
+                "const a = /*#__PURE__*/ h(\"div\", null);\n"
+            );
+            assert_eq!(
+                result_code(swc_session_compile(session, std::ptr::null(), js.as_ptr())),
+                result_code(swc_compile_js(js.as_ptr()))
+            );
+            assert_eq!(
+                result_code(swc_session_minify(session, std::ptr::null(), js.as_ptr())),
+                result_code(swc_minify_js(js.as_ptr()))
+            );
+        }
+        assert!(swc_session_source_file_count(session) >= 9);
+
+        swc_session_reset(session);
+        assert_eq!(swc_session_source_file_count(session), 0);
+        assert_eq!(
+            result_code(swc_session_transpile(session, tsx.as_ptr(), jsx.as_ptr())),
+            "const a = /*#__PURE__*/ h(\"div\", null);\n"
+        );
+        swc_session_free(session);
+    }
+
+    #[test]
+    fn test_session_compile_uses_config() {
+        let config = CString::new(r#"{"jsc":{"target":"es2020"}}"#).unwrap();
+        let session = swc_session_new(config.as_ptr(), std::ptr::null());
+        let code = CString::new("const f = async () => a?.b;").unwrap();
+
+        let output = result_code(swc_session_compile(
+            session,
+            std::ptr::null(),
+            code.as_ptr(),
+        ));
+        assert!(output.contains("async ()=>a?.b"), "{output}");
+        swc_session_free(session);
+    }
+
+    #[test]
+    fn test_last_error_reports_legacy_transpile_failure() {
+        let file = CString::new("broken.ts").unwrap();
+        let input = CString::new("const a = ;").unwrap();
+        assert!(transpile(file.as_ptr(), input.as_ptr()).is_null());
+
+        let message = unsafe { CStr::from_ptr(swc_last_error()) }
+            .to_str()
+            .unwrap();
+        assert!(message.starts_with("broken.ts:1:11: "), "{message}");
+
+        let mut len = 0;
+        let diagnostics = swc_last_diagnostics(&mut len);
+        assert_eq!(len, 1);
+        let diagnostic = unsafe { &*diagnostics };
+        assert_eq!(diagnostic.severity, swc_severity::Error);
+        assert_eq!((diagnostic.line, diagnostic.column), (1, 10));
+
+        let input = CString::new("const a = 1;").unwrap();
+        let output = transpile(file.as_ptr(), input.as_ptr());
+        assert!(!output.is_null());
+        assert!(swc_last_error().is_null());
+        assert!(swc_last_diagnostics(std::ptr::null_mut()).is_null());
+        free_string(output);
+    }
+
+    #[test]
+    fn test_last_error_is_per_thread() {
+        assert!(transpile_file(std::ptr::null()).is_null());
+        assert!(!swc_last_error().is_null());
+
+        std::thread::spawn(|| assert!(swc_last_error().is_null()))
+            .join()
+            .unwrap();
+        assert!(!swc_last_error().is_null());
+    }
+
+    fn result_status(result: *mut swc_result) -> swc_status {
+        let status = unsafe { &*result }.status;
+        swc_result_free(result);
+        status
+    }
+
+    #[test]
+    fn test_swc_results_carry_status() {
+        let null = std::ptr::null();
+        let invalid = invalid_utf8();
+        let valid = CString::new("const a = 1;").unwrap();
+        let broken = CString::new("const a = ;").unwrap();
+        let file = CString::new("broken.ts").unwrap();
+        let missing = CString::new("does/not/exist.ts").unwrap();
+        let bad_json = CString::new("{").unwrap();
+
+        let options = swc_transpile_options_new();
+        swc_transpile_options_set_source_maps(options, swc_source_map_mode::Separate);
+        swc_transpile_options_set_input_source_map(options, bad_json.as_ptr());
+
+        let cases = [
+            (swc_compile_js(valid.as_ptr()), swc_status::Ok),
+            (swc_compile_js(null), swc_status::InvalidArgument),
+            (swc_compile_js(invalid.as_ptr()), swc_status::InvalidUtf8),
+            (swc_compile_file(missing.as_ptr()), swc_status::FileNotFound),
+            (
+                swc_compile_js_with_config(valid.as_ptr(), null, bad_json.as_ptr(), null),
+                swc_status::InvalidConfig,
+            ),
+            (
+                swc_transpile_with_options(file.as_ptr(), valid.as_ptr(), options),
+                swc_status::InvalidSourceMap,
+            ),
+            (swc_compile_js(broken.as_ptr()), swc_status::Syntax),
+            (swc_minify_js(broken.as_ptr()), swc_status::Syntax),
+            (
+                swc_transpile(file.as_ptr(), broken.as_ptr()),
+                swc_status::Syntax,
+            ),
+        ];
+        swc_transpile_options_free(options);
+
+        for (i, (result, expected)) in cases.into_iter().enumerate() {
+            assert_eq!(result_status(result), expected, "case {i}");
+        }
+    }
+
+    #[test]
+    fn test_last_status_covers_legacy_exports() {
+        let missing = CString::new("does/not/exist.ts").unwrap();
+        assert!(transpile_file(missing.as_ptr()).is_null());
+        assert_eq!(swc_last_status(), swc_status::FileNotFound);
+
+        let code = CString::new("const a = 1;").unwrap();
+        let output = compile_js(code.as_ptr(), std::ptr::null_mut());
+        assert_eq!(swc_last_status(), swc_status::Ok);
+        free_string(output);
+    }
+
+    fn transpile_as(name: &str, code: &str, syntax: swc_syntax) -> *mut swc_result {
+        let options = swc_transpile_options_new();
+        swc_transpile_options_set_syntax(options, syntax);
+        let name = CString::new(name).unwrap();
+        let code = CString::new(code).unwrap();
+        let result = swc_transpile_with_options(name.as_ptr(), code.as_ptr(), options);
+        swc_transpile_options_free(options);
+        result
+    }
+
+    #[test]
+    fn test_transpile_detects_syntax_from_extension() {
+        let jsx = "const a = <div />;";
+        assert_eq!(
+            result_code(transpile_as("app.jsx", jsx, swc_syntax::Auto)),
+            "const a = /*#__PURE__*/ React.createElement(\"div\", null);\n"
+        );
+
+        let ts = "const a: number = 1;";
+        assert_eq!(
+            result_status(transpile_as("app.js", ts, swc_syntax::Auto)),
+            swc_status::Syntax
+        );
+
+        let dts = "export declare function f(a: number): void;";
+        assert_eq!(
+            result_code(transpile_as("types.d.ts", dts, swc_syntax::Auto)),
+            "export { };\n"
+        );
+    }
+
+    #[test]
+    fn test_transpile_module_kind_from_extension() {
+        let esm = "import a from \"a\";\nexport default a;";
+        assert_eq!(
+            result_status(transpile_as("lib.cjs", esm, swc_syntax::Auto)),
+            swc_status::Syntax
+        );
+        assert_eq!(
+            result_code(transpile_as("lib.mjs", esm, swc_syntax::Auto)),
+            "import a from \"a\";\nexport default a;\n"
+        );
+
+        let cjs = "return module.exports;";
+        assert_eq!(
+            result_code(transpile_as("lib.cjs", cjs, swc_syntax::Auto)),
+            "return module.exports;\n"
+        );
+    }
+
+    #[test]
+    fn test_transpile_syntax_override() {
+        let tsx = "const a = <div>{1 as number}</div>;";
+        assert_eq!(
+            result_status(transpile_as("input", tsx, swc_syntax::Auto)),
+            swc_status::Syntax
+        );
+        assert_eq!(
+            result_code(transpile_as("input", tsx, swc_syntax::Tsx)),
+            "const a = /*#__PURE__*/ React.createElement(\"div\", null, 1);\n"
+        );
+    }
+
+    fn transpile_jsx(code: &str, configure: impl FnOnce(*mut swc_transpile_options)) -> String {
+        let options = swc_transpile_options_new();
+        configure(options);
+        let name = CString::new("app.tsx").unwrap();
+        let code = CString::new(code).unwrap();
+        let result = swc_transpile_with_options(name.as_ptr(), code.as_ptr(), options);
+        swc_transpile_options_free(options);
+        result_code(result)
+    }
+
+    #[test]
+    fn test_transpile_jsx_automatic_runtime() {
+        let preact = CString::new("preact").unwrap();
+        let output = transpile_jsx("export const a = <div><b /></div>;", |o| {
+            swc_transpile_options_set_jsx_runtime(o, swc_jsx_runtime::Automatic);
+            swc_transpile_options_set_jsx_import_source(o, preact.as_ptr());
+        });
+        assert_eq!(
+            output,
+            "import { jsx as _jsx } from \"preact/jsx-runtime\";\n\
+             export const a = /*#__PURE__*/ _jsx(\"div\", {\n    \
+             children: /*#__PURE__*/ _jsx(\"b\", {})\n});\n"
+        );
+    }
+
+    #[test]
+    fn test_transpile_jsx_development() {
+        let output = transpile_jsx("export const a = <div />;", |o| {
+            swc_transpile_options_set_jsx_runtime(o, swc_jsx_runtime::Automatic);
+            swc_transpile_options_set_jsx_development(o, true);
+        });
+        assert!(
+            output.starts_with("import { jsxDEV as _jsxDEV } from \"react/jsx-dev-runtime\";"),
+            "{output}"
+        );
+        assert!(output.contains("fileName: \"app.tsx\""), "{output}");
+
+        let output = transpile_jsx("const a = <div />;", |o| {
+            swc_transpile_options_set_jsx_development(o, true);
+        });
+        assert!(output.contains("__source: {"), "{output}");
+        assert!(output.contains("__self: this"), "{output}");
+    }
+
+    #[test]
+    fn test_transpile_jsx_pragma_comments() {
+        let output = transpile_jsx("/** @jsx h */\nconst a = <div />;", |_| {});
+        assert_eq!(
+            output,
+            "/** @jsx h */ const a = /*#__PURE__*/ h(\"div\", null);\n"
+        );
+
+        let output = transpile_jsx(
+            "/** @jsxImportSource preact */\nexport const a = <div />;",
+            |_| {},
+        );
+        assert!(
+            output.starts_with("import { jsx as _jsx } from \"preact/jsx-runtime\";"),
+            "{output}"
+        );
+    }
+
+    #[test]
+    fn test_transpile_jsx_throw_if_namespace() {
+        let code = CString::new("const a = <svg:rect />;").unwrap();
+        let name = CString::new("app.tsx").unwrap();
+        assert_eq!(
+            result_status(swc_transpile(name.as_ptr(), code.as_ptr())),
+            swc_status::Transform
+        );
+
+        let output = transpile_jsx("const a = <svg:rect />;", |o| {
+            swc_transpile_options_set_jsx_throw_if_namespace(o, false);
+        });
+        assert_eq!(
+            output,
+            "const a = /*#__PURE__*/ React.createElement(\"svg:rect\", null);\n"
+        );
+    }
+
+    #[test]
+    fn test_transpile_module_formats() {
+        let code = "import React from 'react';\nexport const version = React.version;\n";
+        let transpile = |format, interop| {
+            result_code(transpile_module("my-lib.ts", code, |o| {
+                swc_transpile_options_set_module_format(o, format);
+                swc_transpile_options_set_module_interop(o, interop);
+            }))
+        };
+
+        let es = transpile(swc_module_format::Es, swc_module_interop::Swc);
+        assert!(es.starts_with("import React from 'react';"), "{es}");
+
+        let cjs = transpile(swc_module_format::CommonJs, swc_module_interop::Swc);
+        assert!(cjs.starts_with("\"use strict\";"), "{cjs}");
+        assert!(
+            cjs.contains("_interop_require_default(require(\"react\"))"),
+            "{cjs}"
+        );
+        assert!(
+            cjs.contains("function _interop_require_default(obj)"),
+            "{cjs}"
+        );
+
+        let cjs = transpile(swc_module_format::CommonJs, swc_module_interop::None);
+        assert!(cjs.contains("const _react = require(\"react\");"), "{cjs}");
+        assert!(!cjs.contains("_interop_require_default"), "{cjs}");
+
+        let amd = transpile(swc_module_format::Amd, swc_module_interop::Swc);
+        assert!(amd.starts_with("define([\n    \"require\",\n    \"exports\",\n    \"react\"\n], function(require, exports, _react) {"), "{amd}");
+
+        let system = transpile(swc_module_format::SystemJs, swc_module_interop::Swc);
+        assert!(
+            system
+                .starts_with("System.register([\n    \"react\"\n], function(_export, _context) {"),
+            "{system}"
+        );
+
+        let umd = transpile(swc_module_format::Umd, swc_module_interop::Swc);
+        assert!(
+            umd.contains("factory(global.myLib = {}, global.react);"),
+            "{umd}"
+        );
+    }
+
+    #[test]
+    fn test_transpile_iife_with_globals() {
+        let code = "import React from 'react';\nimport { x } from 'lodash/fp';\n\
+                    export const version = React.version + x;\n";
+        let react = CString::new("react").unwrap();
+        let react_global = CString::new("React").unwrap();
+        let global_name = CString::new("MyLib").unwrap();
+        let configure = |o, format| {
+            swc_transpile_options_set_module_format(o, format);
+            swc_transpile_options_set_module_interop(o, swc_module_interop::None);
+            swc_transpile_options_set_module_strict(o, true);
+            swc_transpile_options_add_module_global(o, react.as_ptr(), react_global.as_ptr());
+            swc_transpile_options_set_module_global_name(o, global_name.as_ptr());
+        };
+
+        let iife = result_code(transpile_module("lib.ts", code, |o| {
+            configure(o, swc_module_format::Iife)
+        }));
+        assert_eq!(
+            iife,
+            "var MyLib = function(exports, _react, _fp) {\n    \
+             \"use strict\";\n    \
+             Object.defineProperty(exports, \"version\", {\n        \
+             enumerable: true,\n        \
+             get: function() {\n            \
+             return version;\n        \
+             }\n    \
+             });\n    \
+             const version = _react.default.version + _fp.x;\n    \
+             return exports;\n\
+             }({}, React, fp);\n"
+        );
+
+        let umd = result_code(transpile_module("lib.ts", code, |o| {
+            configure(o, swc_module_format::Umd)
+        }));
+        assert!(
+            umd.contains("factory(global.MyLib = {}, global.React, global.fp);"),
+            "{umd}"
+        );
+
+        let script = result_code(transpile_module("app.ts", "console.log(1);", |o| {
+            swc_transpile_options_set_module_format(o, swc_module_format::Iife);
+        }));
+        assert_eq!(
+            script,
+            "(function() {\n    \"use strict\";\n    console.log(1);\n})();\n"
+        );
+    }
+
+    #[test]
+    fn test_transpile_iife_with_dotted_global_name() {
+        let global_name = CString::new("acme.widgets.Chart").unwrap();
+        let iife = result_code(transpile_module("chart.ts", "export const a = 1;", |o| {
+            swc_transpile_options_set_module_format(o, swc_module_format::Iife);
+            swc_transpile_options_set_module_global_name(o, global_name.as_ptr());
+        }));
+        assert!(
+            iife.starts_with(
+                "this.acme = this.acme || {};\n\
+                 this.acme.widgets = this.acme.widgets || {};\n\
+                 this.acme.widgets.Chart = function(exports) {"
+            ),
+            "{iife}"
+        );
+    }
+
+    #[test]
+    fn test_transpile_rejects_invalid_global_name() {
+        let global_name = CString::new("my-lib").unwrap();
+        let result = transpile_module("lib.ts", "export const a = 1;", |o| {
+            swc_transpile_options_set_module_format(o, swc_module_format::Iife);
+            swc_transpile_options_set_module_global_name(o, global_name.as_ptr());
+        });
+        assert_eq!(result_status(result), swc_status::InvalidConfig);
+    }
+
+    #[test]
+    fn test_rewrite_wrapper_fails_without_umd_wrapper() {
+        let mut program = swc_ecma_ast::Program::Module(Default::default());
+        let options = ModuleOptions {
+            format: ModuleFormat::Iife,
+            ..Default::default()
+        };
+        let error = module::rewrite_wrapper(&mut program, "lib.ts", &options).unwrap_err();
+        assert_eq!(Status::of(&error), Some(Status::Internal));
+    }
+
+    #[test]
+    fn test_compile_js_with_iife_config() {
+        let code =
+            CString::new("import React from 'react';\nexport default React.version;\n").unwrap();
+        let config = CString::new(
+            r#"{"module":{"type":"iife","globalName":"MyLib","globals":{"react":"React"}}}"#,
+        )
+        .unwrap();
+        let output = result_code(swc_compile_js_with_config(
+            code.as_ptr(),
+            std::ptr::null(),
+            config.as_ptr(),
+            std::ptr::null(),
+        ));
+        assert!(
+            output.starts_with("var MyLib = (function(exports, _react) {"),
+            "{output}"
+        );
+        assert!(
+            output.ends_with("    return exports;\n})({}, React);\n"),
+            "{output}"
+        );
+
+        let config = CString::new(r#"{"module":{"type":"umd","globalName":1}}"#).unwrap();
+        let result = swc_compile_js_with_config(
+            code.as_ptr(),
+            std::ptr::null(),
+            config.as_ptr(),
+            std::ptr::null(),
+        );
+        assert_eq!(result_status(result), swc_status::InvalidConfig);
+    }
+
+    fn transpile_module(
+        name: &str,
+        code: &str,
+        configure: impl FnOnce(*mut swc_transpile_options),
+    ) -> *mut swc_result {
+        let options = swc_transpile_options_new();
+        configure(options);
+        let name = CString::new(name).unwrap();
+        let code = CString::new(code).unwrap();
+        let result = swc_transpile_with_options(name.as_ptr(), code.as_ptr(), options);
+        swc_transpile_options_free(options);
+        result
+    }
+
+    #[test]
+    fn test_transpile_env_lowers_syntax_for_targets() {
+        let code = "class A {\n    x = 1;\n    async f() {\n        return this?.x ?? [1].includes(2);\n    }\n}\nexport default A;\n";
+
+        let output = result_code(transpile_module("a.ts", code, |_| {}));
+        assert!(output.contains("return this?.x ?? ["), "{output}");
+
+        let ie = CString::new("ie 11").unwrap();
+        let output = result_code(transpile_module("a.ts", code, |o| {
+            swc_transpile_options_set_env_targets(o, ie.as_ptr());
+            swc_transpile_options_set_env_polyfills(o, swc_polyfill_mode::Usage);
+        }));
+        assert!(!output.contains("class A"), "{output}");
+        assert!(!output.contains("?."), "{output}");
+        assert!(
+            output.contains("function _async_to_generator(fn)"),
+            "{output}"
+        );
+        assert!(
+            output.contains("import \"core-js/modules/es.array.includes.js\";"),
+            "{output}"
+        );
+
+        let chrome = CString::new("chrome").unwrap();
+        let version = CString::new("120").unwrap();
+        let output = result_code(transpile_module("a.ts", code, |o| {
+            swc_transpile_options_set_env_engine(o, chrome.as_ptr(), version.as_ptr());
+        }));
+        assert_eq!(output, result_code(transpile_module("a.ts", code, |_| {})));
+
+        let old_version = CString::new("60").unwrap();
+        let output = result_code(transpile_module("a.ts", code, |o| {
+            swc_transpile_options_set_env_targets(o, ie.as_ptr());
+            swc_transpile_options_set_env_engine(o, chrome.as_ptr(), old_version.as_ptr());
+            swc_transpile_options_set_env_targets(o, std::ptr::null());
+        }));
+        assert!(!output.contains("?."), "{output}");
+        assert!(output.contains("class A"), "{output}");
+
+        let output = result_code(transpile_module("a.ts", code, |o| {
+            swc_transpile_options_set_env_targets(o, ie.as_ptr());
+            swc_transpile_options_set_env(o, false);
+        }));
+        assert_eq!(output, result_code(transpile_module("a.ts", code, |_| {})));
+    }
+
+    #[test]
+    fn test_transpile_env_rejects_invalid_targets() {
+        let query = CString::new("ie 11 or whatever").unwrap();
+        let engine = CString::new("netscape").unwrap();
+        let chrome = CString::new("chrome").unwrap();
+        let version = CString::new("80").unwrap();
+        let bad_version = CString::new("80.x").unwrap();
+        let core_js = CString::new("4").unwrap();
+        let cases: [&dyn Fn(*mut swc_transpile_options); 4] = [
+            &|o| swc_transpile_options_set_env_targets(o, query.as_ptr()),
+            &|o| swc_transpile_options_set_env_engine(o, engine.as_ptr(), version.as_ptr()),
+            &|o| swc_transpile_options_set_env_engine(o, chrome.as_ptr(), bad_version.as_ptr()),
+            &|o| swc_transpile_options_set_env_core_js(o, core_js.as_ptr()),
+        ];
+
+        for configure in cases {
+            let result = transpile_module("a.ts", "export const a = 1;", configure);
+            assert_eq!(result_status(result), swc_status::InvalidConfig);
+        }
+    }
+
+    #[test]
+    fn test_api_transpile_returns_typed_error() {
+        let options = api::TranspileOptions {
+            module: ModuleOptions {
+                format: ModuleFormat::CommonJs,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let output = api::transpile(
+            api::File::source("a.ts", "export const a: number = 1;"),
+            &options,
+        )
+        .expect("transpile failed");
+        assert!(output.code.contains("exports"), "{}", output.code);
+
+        let error = api::transpile(api::File::source("a.ts", "const = 1;"), &options).unwrap_err();
+        assert_eq!(error.status(), api::Status::Syntax);
+        assert_eq!(error.to_string(), error.message());
+        let diagnostic = &error.diagnostics()[0];
+        assert_eq!(
+            (diagnostic.file.as_deref(), diagnostic.line),
+            (Some("a.ts"), 1)
+        );
+    }
+
+    #[test]
+    fn test_api_compile_with_typed_options() {
+        let mut options = api::CompileOptions::default();
+        options.config.jsc.target = Some(api::EsVersion::Es5);
+        let output = api::compile(api::File::source("a.js", "const f = () => 1;"), &options)
+            .expect("compile failed");
+        assert_eq!(output.code, "var f = function f() {\n    return 1;\n};\n");
+    }
+
+    #[test]
+    fn test_api_accepts_owned_paths() {
+        let path = std::env::temp_dir().join(format!("swc_cxx_api_{}.ts", std::process::id()));
+        std::fs::write(&path, "const a: number = 1;").expect("failed to write input");
+        let code = transpile_tsx_to_js(Default::default(), api::File::path(&path));
+        std::fs::remove_file(&path).ok();
+        assert_eq!(code.expect("transpile failed"), "const a = 1;\n");
+
+        let error = transpile_tsx_to_js(Default::default(), api::File::path(path)).unwrap_err();
+        assert_eq!(error.status(), api::Status::FileNotFound);
+    }
+
+    #[test]
+    fn test_api_session_shares_source_map() {
+        let mut session = api::Session::new();
+        let minified = session
+            .minify(
+                api::File::source("a.js", "function f(long) { return long; } f(1);"),
+                SourceMapMode::None,
+            )
+            .expect("minify failed");
+        assert!(!minified.code.contains("long"), "{}", minified.code);
+        session
+            .transpile(
+                api::File::source("b.ts", "let b: string;"),
+                &Default::default(),
+            )
+            .expect("transpile failed");
+        assert!(session.source_file_count() >= 2);
+
+        session.reset();
+        assert_eq!(session.source_file_count(), 0);
+    }
+
+    unsafe extern "C" fn collect(ctx: *mut c_void, data: *const c_char, len: usize) {
+        let out = unsafe { &mut *(ctx as *mut Vec<u8>) };
+        out.extend_from_slice(unsafe { std::slice::from_raw_parts(data as *const u8, len) });
+    }
+
+    #[test]
+    fn test_transpile_stream_matches_buffered_output() {
+        let options = swc_transpile_options_new();
+        swc_transpile_options_set_source_maps(options, swc_source_map_mode::Inline);
+        let file = CString::new("app.ts").unwrap();
+        let input = CString::new("const a: number = 1;\nconsole.log(a);").unwrap();
+
+        let mut streamed = Vec::new();
+        let result = swc_transpile_stream(
+            file.as_ptr(),
+            input.as_ptr(),
+            options,
+            Some(collect),
+            &mut streamed as *mut Vec<u8> as *mut c_void,
+        );
+        let result_ref = unsafe { &*result };
+        assert_eq!(result_ref.status, swc_status::Ok);
+        assert!(result_ref.code.is_null() && result_ref.map.is_null());
+        swc_result_free(result);
+
+        let buffered = result_code(swc_transpile_with_options(
+            file.as_ptr(),
+            input.as_ptr(),
+            options,
+        ));
+        let streamed = String::from_utf8(streamed).unwrap();
+        assert!(
+            streamed.contains("//# sourceMappingURL=data:application/json;base64,"),
+            "{streamed}"
+        );
+        assert_eq!(streamed, buffered);
+        swc_transpile_options_free(options);
+    }
+
+    #[test]
+    fn test_minify_stream_matches_buffered_output() {
+        let file = CString::new("app.js").unwrap();
+        let input = CString::new(
+            "/*! keep me */\nconst long = () => 1;\nexport function run(value) { return long() + value; }",
+        )
+        .unwrap();
+
+        for mode in [swc_source_map_mode::Separate, swc_source_map_mode::Inline] {
+            let mut streamed = Vec::new();
+            let result = swc_minify_js_stream(
+                input.as_ptr(),
+                file.as_ptr(),
+                mode,
+                Some(collect),
+                &mut streamed as *mut Vec<u8> as *mut c_void,
+            );
+            let result_ref = unsafe { &*result };
+            assert_eq!(result_ref.status, swc_status::Ok);
+            assert!(result_ref.code.is_null());
+            let map = (!result_ref.map.is_null())
+                .then(|| unsafe { CStr::from_ptr(result_ref.map) }.to_owned());
+            swc_result_free(result);
+
+            let buffered = swc_minify_js_with_source_map(
+                input.as_ptr(),
+                file.as_ptr(),
+                std::ptr::null(),
+                mode,
+            );
+            let buffered_map = unsafe { &*buffered }.map;
+            assert_eq!(
+                map.as_deref(),
+                (!buffered_map.is_null()).then(|| unsafe { CStr::from_ptr(buffered_map) })
+            );
+            assert_eq!(map.is_some(), mode == swc_source_map_mode::Separate);
+            assert_eq!(String::from_utf8(streamed).unwrap(), result_code(buffered));
+        }
+    }
+
+    #[test]
+    fn test_stream_reports_errors_without_writing() {
+        let file = CString::new("broken.ts").unwrap();
+        let broken = CString::new("const = 1;").unwrap();
+        let mut written = Vec::new();
+        let ctx = &mut written as *mut Vec<u8> as *mut c_void;
+
+        let result = swc_transpile_stream(
+            file.as_ptr(),
+            broken.as_ptr(),
+            std::ptr::null(),
+            Some(collect),
+            ctx,
+        );
+        let result_ref = unsafe { &*result };
+        assert_eq!(result_ref.status, swc_status::Syntax);
+        assert!(result_ref.diagnostics_len > 0);
+        swc_result_free(result);
+
+        let result = swc_minify_js_stream(
+            broken.as_ptr(),
+            std::ptr::null(),
+            swc_source_map_mode::None,
+            Some(collect),
+            ctx,
+        );
+        assert_eq!(result_status(result), swc_status::Syntax);
+
+        let jsx = CString::new("component.tsx").unwrap();
+        let namespaced = CString::new("const x = <svg:rect />;").unwrap();
+        let result = swc_transpile_stream(
+            jsx.as_ptr(),
+            namespaced.as_ptr(),
+            std::ptr::null(),
+            Some(collect),
+            ctx,
+        );
+        assert_eq!(result_status(result), swc_status::Transform);
+
+        // The code is generated before the input source map turns out to be invalid
+        let options = swc_transpile_options_new();
+        let map = CString::new("not a source map").unwrap();
+        swc_transpile_options_set_source_maps(options, swc_source_map_mode::Separate);
+        swc_transpile_options_set_input_source_map(options, map.as_ptr());
+        let valid = CString::new("const a = 1;").unwrap();
+        let result =
+            swc_transpile_stream(file.as_ptr(), valid.as_ptr(), options, Some(collect), ctx);
+        assert_eq!(result_status(result), swc_status::InvalidSourceMap);
+        swc_transpile_options_free(options);
+        assert!(written.is_empty(), "{}", String::from_utf8_lossy(&written));
+
+        let result = swc_transpile_stream(
+            file.as_ptr(),
+            valid.as_ptr(),
+            std::ptr::null(),
+            None,
+            std::ptr::null_mut(),
+        );
+        assert_eq!(result_status(result), swc_status::InvalidArgument);
+    }
+
+    fn minify_with_options_json(code: &str, options: &str) -> String {
+        let code = CString::new(code).unwrap();
+        let options = CString::new(options).unwrap();
+        let mut error = std::ptr::null_mut();
+        let output = minify_js_with_options(code.as_ptr(), options.as_ptr(), &mut error);
+        assert!(
+            error.is_null(),
+            "{}",
+            unsafe { CStr::from_ptr(error) }.to_str().unwrap()
+        );
+        let minified = unsafe { CStr::from_ptr(output) }
+            .to_str()
+            .unwrap()
+            .to_owned();
+        free_string(output);
+        minified
+    }
+
+    /// Minifies `code` as one chunk of a build sharing `cache`.
+    fn minify_chunk(options: &api::MinifyOptions, cache: &api::MangleCache, code: &str) -> String {
+        api::minify_with_cache(
+            api::File::source("chunk.js", code),
+            options,
+            api::KeepQuoted::No,
+            cache,
+        )
+        .expect("minify failed")
+        .code
+    }
+
+    #[test]
+    fn test_minify_js_with_options() {
+        let code =
+            "function greet(name) { console.log(name); return name; }\nwindow.a = greet;\nwindow.b = greet;";
+
+        let readable = minify_with_options_json(code, r#"{"compress": false, "mangle": false}"#);
+        assert!(readable.contains("function greet(name)"), "{readable}");
+        assert!(readable.contains("console.log(name)"), "{readable}");
+
+        let release = minify_with_options_json(
+            code,
+            r#"{"toplevel": true, "compress": {"drop_console": true, "passes": 2},
+                "mangle": {"reserved": ["greet"]}, "format": {"comments": false}}"#,
+        );
+        assert!(!release.contains("console"), "{release}");
+        assert!(release.contains("function greet("), "{release}");
+        assert!(!release.contains("name"), "{release}");
+
+        let input = CString::new(code).unwrap();
+        let mut error = std::ptr::null_mut();
+        let defaults = minify_js_with_options(input.as_ptr(), std::ptr::null(), &mut error);
+        assert_eq!(
+            unsafe { CStr::from_ptr(defaults) }.to_str().unwrap(),
+            minify_with_options_json(code, "{}")
+        );
+        free_string(defaults);
+    }
+
+    #[test]
+    fn test_minify_matches_swc() {
+        let code = "/*! lib | MIT */\n/* note */ function long(first, second) {\n  \"use strict\";\n  return first(second) + second;\n}\nconst unused = () => 1;\nwindow.b = long;";
+        let cases = [
+            "{}",
+            r#"{"toplevel": true, "keep_fnames": true}"#,
+            r#"{"compress": false, "mangle": false, "format": {"comments": "all"}}"#,
+            r#"{"compress": {"passes": 2}, "sourceMap": true, "format": {"preamble": "// built"}}"#,
+            r#"{"ecma": 5, "format": {"ascii_only": true}, "module": true}"#,
+        ];
+
+        for json in cases {
+            let options: swc_compiler::config::JsMinifyOptions =
+                serde_json::from_str(json).unwrap();
+            let expected = GLOBALS.set(&Default::default(), || {
+                let compiler = swc_compiler::Compiler::new(Default::default());
+                let fm = compiler
+                    .cm
+                    .new_source_file(FileName::Custom("lib.js".into()).into(), code.to_owned());
+                swc_compiler::try_with_handler(compiler.cm.clone(), Default::default(), |handler| {
+                    compiler.minify(fm, handler, &options, Default::default())
+                })
+                .expect("swc failed to minify")
+            });
+            let output = minify_with(
+                File::source("lib.js", code),
+                &options,
+                KeepQuoted::No,
+                requested_source_maps(&options),
+                None,
+                false,
+            )
+            .value
+            .expect("minify failed");
+            assert_eq!(output.code, expected.code, "{json}");
+            assert_eq!(output.map, expected.map, "{json}");
+        }
+    }
+
+    #[test]
+    fn test_minify_js_with_options_reports_invalid_options() {
+        let code = CString::new("var a = 1;").unwrap();
+        for options in [r#"{"compress": "#, r#"{"no_such_option": true}"#] {
+            let options = CString::new(options).unwrap();
+            let result =
+                swc_minify_js_with_options(code.as_ptr(), std::ptr::null(), options.as_ptr());
+            assert_eq!(result_status(result), swc_status::InvalidConfig);
+        }
+    }
+
+    #[test]
+    fn test_minify_js_file_with_options_and_source_map() {
+        let path = std::env::temp_dir().join(format!("swc_cxx_minify_{}.js", std::process::id()));
+        std::fs::write(
+            &path,
+            "class Greeter { greet() { return 1; } }\nwindow.greeter = new Greeter();",
+        )
+        .expect("failed to write input");
+        let filepath = CString::new(path.to_str().unwrap()).unwrap();
+        let options =
+            CString::new(r#"{"toplevel": true, "compress": {"keep_classnames": true}, "mangle": {"keep_classnames": true}, "sourceMap": true}"#)
+                .unwrap();
+
+        let result = swc_minify_js_file_with_options(filepath.as_ptr(), options.as_ptr());
+        let result_ref = unsafe { &*result };
+        let code = unsafe { CStr::from_ptr(result_ref.code) }.to_str().unwrap();
+        assert!(code.contains("class Greeter"), "{code}");
+        assert!(!result_ref.map.is_null());
+        swc_result_free(result);
+
+        let mut error = std::ptr::null_mut();
+        let output = minify_js_file_with_options(filepath.as_ptr(), options.as_ptr(), &mut error);
+        std::fs::remove_file(&path).ok();
+        assert!(unsafe { CStr::from_ptr(output) }
+            .to_str()
+            .unwrap()
+            .contains("class Greeter"));
+        free_string(output);
+    }
+
+    #[test]
+    fn test_api_minify_with_typed_options() {
+        let options = api::MinifyOptions {
+            compress: api::BoolOrDataConfig::from_bool(false),
+            keep_fnames: true,
+            toplevel: Some(true),
+            ..Default::default()
+        };
+        let output = api::minify_with_options(
+            api::File::source(
+                "a.js",
+                "function named(long) { return long; } window.a = named(1);",
+            ),
+            &options,
+            api::KeepQuoted::No,
+        )
+        .expect("minify failed");
+        assert!(output.code.contains("named"), "{}", output.code);
+        assert!(!output.code.contains("long"), "{}", output.code);
+        assert!(output.map.is_none());
+    }
+
+    #[test]
+    fn test_mangle_cache_keeps_names_across_chunks_and_builds() {
+        let options = api::MinifyOptions {
+            toplevel: Some(true),
+            compress: api::BoolOrDataConfig::from_bool(false),
+            ..Default::default()
+        };
+        let chunk = |cache: &api::MangleCache, code: &str| minify_chunk(&options, cache, code);
+        let first = "var alpha = 1, shared = 2; window.a = alpha + shared;";
+        let second = "var beta = 3, shared = 4; window.b = shared + beta;";
+
+        let cache = api::MangleCache::new();
+        let a = chunk(&cache, first);
+        let b = chunk(&cache, second);
+        let json = cache.to_json();
+        let vars: serde_json::Value = serde_json::from_str(&json).unwrap();
+        let name = |var: &str| vars["vars"][var].as_str().unwrap().to_owned();
+        assert_eq!(cache.len(), 3);
+        assert!(a.contains(&format!("{}=2", name("shared"))), "{a}");
+        assert!(b.contains(&format!("{}=4", name("shared"))), "{b}");
+        assert_ne!(name("alpha"), name("beta"));
+
+        let restored = api::MangleCache::from_json(&json).expect("invalid cache");
+        assert_eq!(restored.to_json(), json);
+        assert_eq!(chunk(&restored, second), b);
+        assert_eq!(chunk(&restored, first), a);
+    }
+
+    #[test]
+    fn test_mangle_cache_renames_variables_named_like_cached_names() {
+        let options = api::MinifyOptions {
+            toplevel: Some(true),
+            compress: api::BoolOrDataConfig::from_bool(false),
+            ..Default::default()
+        };
+        let chunk = |cache: &api::MangleCache, code: &str| minify_chunk(&options, cache, code);
+        let cache = api::MangleCache::new();
+        chunk(&cache, "var shared = 2; window.x = shared;");
+        let json = cache.to_json();
+        let vars: serde_json::Value = serde_json::from_str(&json).unwrap();
+        let shared = vars["vars"]["shared"].as_str().unwrap().to_owned();
+
+        let local = chunk(
+            &cache,
+            &format!("var shared = 4, {shared} = 5; window.y = shared + {shared};"),
+        );
+        assert!(local.contains(&format!("{shared}=4,")), "{local}");
+        assert!(!local.contains(&format!("{shared}=5")), "{local}");
+
+        let param = chunk(
+            &cache,
+            &format!("function f({shared}) {{ return {shared} + shared; }} window.f = f;"),
+        );
+        assert!(param.contains(&format!("+{shared}}}")), "{param}");
+        assert!(!param.contains(&format!("({shared})")), "{param}");
+
+        // A global by that name cannot be renamed, so `shared` gets another name here
+        let global = chunk(
+            &cache,
+            &format!("var shared = 6; window.z = shared + {shared};"),
+        );
+        assert!(global.ends_with(&format!("+{shared};")), "{global}");
+        assert!(!global.contains(&format!("var {shared}=")), "{global}");
+    }
+
+    #[test]
+    fn test_mangle_cache_shared_between_threads() {
+        let options = api::MinifyOptions {
+            toplevel: Some(true),
+            compress: api::BoolOrDataConfig::from_bool(false),
+            ..Default::default()
+        };
+        let cache = api::MangleCache::new();
+        std::thread::scope(|scope| {
+            for thread in 0..8 {
+                let (options, cache) = (&options, &cache);
+                scope.spawn(move || {
+                    for chunk in 0..4 {
+                        let var = format!("v{thread}_{chunk}");
+                        minify_chunk(
+                            options,
+                            cache,
+                            &format!("var {var} = 1; window.{var} = {var};"),
+                        );
+                    }
+                });
+            }
+        });
+
+        let json: serde_json::Value = serde_json::from_str(&cache.to_json()).unwrap();
+        let vars = json["vars"].as_object().unwrap();
+        let names = vars.values().map(|name| name.as_str().unwrap());
+        assert_eq!(vars.len(), 32);
+        assert_eq!(
+            names.collect::<std::collections::BTreeSet<_>>().len(),
+            32,
+            "{json}"
+        );
+    }
+
+    #[test]
+    fn test_mangle_cache_handle() {
+        let options = CString::new(r#"{"toplevel": true, "compress": false}"#).unwrap();
+        let code =
+            CString::new("var counter = 0; window.next = function () { return ++counter; };")
+                .unwrap();
+        let cache = swc_mangle_cache_new();
+        let result =
+            swc_minify_js_with_cache(code.as_ptr(), std::ptr::null(), options.as_ptr(), cache);
+        let minified = CString::new(result_code(result)).unwrap();
+
+        let json = swc_mangle_cache_to_json(cache);
+        swc_mangle_cache_free(cache);
+        let saved = unsafe { std::slice::from_raw_parts(json.ptr, json.len) };
+        assert!(std::str::from_utf8(saved).unwrap().contains("\"counter\""));
+        let restored = swc_mangle_cache_from_json(json.ptr, json.len);
+        swc_buffer_free(json);
+        assert!(!restored.is_null());
+
+        let mut error = std::ptr::null_mut();
+        let again = minify_js_with_cache(code.as_ptr(), options.as_ptr(), restored, &mut error);
+        assert!(error.is_null());
+        assert_eq!(unsafe { CStr::from_ptr(again) }, minified.as_c_str());
+        free_string(again);
+        swc_mangle_cache_free(restored);
+
+        let invalid = br#"{"vars": 1}"#;
+        assert!(swc_mangle_cache_from_json(invalid.as_ptr(), invalid.len()).is_null());
+        assert_eq!(swc_last_status(), swc_status::InvalidConfig);
+        assert!(swc_mangle_cache_to_json(std::ptr::null()).ptr.is_null());
+        let result = swc_minify_js_with_cache(
+            code.as_ptr(),
+            std::ptr::null(),
+            options.as_ptr(),
+            std::ptr::null(),
+        );
+        assert_eq!(result_status(result), swc_status::InvalidArgument);
+        swc_mangle_cache_free(std::ptr::null_mut());
+    }
+
+    #[test]
+    fn test_mangle_props_with_regex_and_reserved() {
+        let options =
+            r#"{"compress": false, "mangle": {"props": {"regex": "^_", "reserved": ["_keep"]}}}"#;
+        let minified = minify_with_options_json(
+            "window.api = { _count: 1, _keep: 2, visible: 3 }; window.api._count++;",
+            options,
+        );
+        assert_eq!(
+            minified,
+            "window.api={a:1,_keep:2,visible:3};window.api.a++;"
+        );
+
+        // Without a regex every property is mangled but those of the built-ins
+        let minified = minify_with_options_json(
+            "var list = { queued: [], push(item) { this.queued.push(item); } }; window.list = list;",
+            r#"{"compress": false, "mangle": {"props": {}}}"#,
+        );
+        assert_eq!(
+            minified,
+            "var list={a:[],push(s){this.a.push(s)}};window.list=list;"
+        );
+    }
+
+    #[test]
+    fn test_mangle_props_keep_quoted() {
+        let code = r#"var o = {}; o._a = 1; o["_b"] = 2; o._b = 3; o["_c"] = 4; window.o = o;"#;
+        let minify = |keep_quoted: &str| {
+            let props = format!(r#"{{"regex": "^_", "keep_quoted": {keep_quoted}}}"#);
+            let options = format!(r#"{{"compress": false, "mangle": {{"props": {props}}}}}"#);
+            minify_with_options_json(code, &options)
+        };
+
+        assert_eq!(
+            minify("false"),
+            r#"var o={};o.a=1;o["b"]=2;o.b=3;o["c"]=4;window.o=o;"#
+        );
+        assert_eq!(
+            minify("true"),
+            r#"var o={};o.a=1;o["_b"]=2;o._b=3;o["_c"]=4;window.o=o;"#
+        );
+        assert_eq!(
+            minify(r#""strict""#),
+            r#"var o={};o.a=1;o["_b"]=2;o.b=3;o["_c"]=4;window.o=o;"#
+        );
+
+        let code = CString::new(code).unwrap();
+        let invalid = CString::new(r#"{"mangle": {"props": {"keep_quoted": "always"}}}"#).unwrap();
+        let result = swc_minify_js_with_options(code.as_ptr(), std::ptr::null(), invalid.as_ptr());
+        assert_eq!(result_status(result), swc_status::InvalidConfig);
+    }
+
+    #[test]
+    fn test_mangle_props_keep_quoted_through_api() {
+        let options: api::MinifyOptions =
+            serde_json::from_str(r#"{"compress": false, "mangle": {"props": {"regex": "^_"}}}"#)
+                .unwrap();
+        let code = r#"var o = {}; o._a = 1; o["_b"] = 2; o._b = 3; window.o = o;"#;
+        let output = api::minify_with_options(
+            api::File::source("o.js", code),
+            &options,
+            api::KeepQuoted::Strict,
+        )
+        .expect("minify failed");
+        assert_eq!(output.code, r#"var o={};o.a=1;o["_b"]=2;o.b=3;window.o=o;"#);
+    }
+
+    #[test]
+    fn test_mangle_props_reject_cached_names_of_kept_properties() {
+        let options: api::MinifyOptions =
+            serde_json::from_str(r#"{"compress": false, "mangle": {"props": {"regex": "^_"}}}"#)
+                .unwrap();
+        let cache = api::MangleCache::new();
+        let first = minify_chunk(&options, &cache, "var o = {}; o._x = 1; window.o = o;");
+        assert_eq!(first, "var o={};o.a=1;window.o=o;");
+        let json = cache.to_json();
+
+        let error = api::minify_with_cache(
+            api::File::source("chunk.js", "var p = {a: 1, _x: 2, _y: 3}; window.p = p;"),
+            &options,
+            api::KeepQuoted::No,
+            &cache,
+        )
+        .expect_err("cached name of a kept property was used");
+        assert_eq!(error.status(), Status::Minify);
+        assert!(
+            error
+                .message()
+                .contains("`_x` cannot be mangled to its cached name `a`"),
+            "{error}"
+        );
+        // Nothing picked by the failed chunk is cached
+        assert_eq!(cache.to_json(), json);
+    }
+
+    #[test]
+    fn test_mangle_props_share_names_through_cache() {
+        let options: api::MinifyOptions =
+            serde_json::from_str(r#"{"compress": false, "mangle": {"props": {"regex": "^_"}}}"#)
+                .unwrap();
+        let chunk = |cache: &api::MangleCache, code: &str| minify_chunk(&options, cache, code);
+        let first = "var api = { _count: 0, _step: 1 }; window.api = api;";
+        let second = "var state = { _total: 0 }; state._count = api._step; window.state = state;";
+
+        let cache = api::MangleCache::new();
+        assert_eq!(chunk(&cache, first), "var api={a:0,b:1};window.api=api;");
+        assert_eq!(
+            chunk(&cache, second),
+            "var state={c:0};state.a=api.b;window.state=state;"
+        );
+
+        let json = cache.to_json();
+        let restored = api::MangleCache::from_json(&json).expect("invalid cache");
+        assert_eq!(
+            chunk(&restored, second),
+            "var state={c:0};state.a=api.b;window.state=state;"
+        );
+        assert!(json.contains(r#""_total": "c""#), "{json}");
+    }
+
+    #[test]
+    fn test_minify_comments_policies() {
+        let code = "/*! lib v1 | MIT */\n// @license Apache-2.0\n/* note */ var a = 1;\n// keep: yes\nwindow.a = a;";
+        let minify = |comments: api::Comments| {
+            api::minify_with_comments(api::File::source("lib.js", code), &comments)
+                .expect("minify failed")
+        };
+
+        let none = minify(api::Comments::None);
+        assert!(
+            !none.code.contains("/*") && !none.code.contains("//"),
+            "{}",
+            none.code
+        );
+        assert!(none.licenses.is_empty());
+
+        let licenses = minify(api::Comments::Licenses);
+        assert!(
+            licenses.code.contains("/*! lib v1 | MIT */"),
+            "{}",
+            licenses.code
+        );
+        assert!(
+            licenses.code.contains("// @license Apache-2.0"),
+            "{}",
+            licenses.code
+        );
+        assert!(!licenses.code.contains("note"), "{}", licenses.code);
+        assert_eq!(
+            licenses.licenses,
+            ["/*! lib v1 | MIT */", "// @license Apache-2.0"]
+        );
+
+        let all = minify(api::Comments::All);
+        assert!(all.code.contains("/* note */"), "{}", all.code);
+        assert_eq!(all.licenses, licenses.licenses);
+
+        let regex = minify(api::Comments::Regex("^ keep:".into()));
+        assert!(regex.code.contains("// keep: yes"), "{}", regex.code);
+        assert!(!regex.code.contains("MIT"), "{}", regex.code);
+        assert!(regex.licenses.is_empty());
+
+        let error = api::minify_with_comments(
+            api::File::source("lib.js", code),
+            &api::Comments::Regex("(".into()),
+        )
+        .unwrap_err();
+        assert_eq!(error.status(), api::Status::InvalidConfig);
+    }
+
+    #[test]
+    fn test_swc_minify_js_with_comments() {
+        let code = CString::new("/*! a | MIT */ var a = 1; /*! a | MIT */ window.a = a;").unwrap();
+        let result = swc_minify_js_with_comments(
+            code.as_ptr(),
+            std::ptr::null(),
+            swc_comments::Licenses,
+            std::ptr::null(),
+        );
+        let result_ref = unsafe { &*result };
+        let licenses =
+            unsafe { std::slice::from_raw_parts(result_ref.licenses, result_ref.licenses_len) };
+        assert_eq!(licenses.len(), 1);
+        assert_eq!(
+            unsafe { CStr::from_ptr(licenses[0]) }.to_str().unwrap(),
+            "/*! a | MIT */"
+        );
+        assert!(result_code(result).starts_with("/*! a | MIT */"));
+
+        let result = swc_minify_js_with_comments(
+            code.as_ptr(),
+            std::ptr::null(),
+            swc_comments::None,
+            std::ptr::null(),
+        );
+        assert!(unsafe { &*result }.licenses.is_null());
+        assert_eq!(result_code(result), "window.a=1;");
+
+        let result = swc_minify_js_with_comments(
+            code.as_ptr(),
+            std::ptr::null(),
+            swc_comments::Regex,
+            std::ptr::null(),
+        );
+        assert_eq!(result_status(result), swc_status::InvalidArgument);
+    }
+
+    #[test]
+    fn test_minify_with_stats() {
+        let body = "total += values[index] * factor;".repeat(50);
+        let code = format!(
+            "function sum(values, factor) {{ var total = 0; for (var index = 0; index < values.length; index++) {{ {body} }} return total; }} window.api = {{ _sum: sum }};"
+        );
+        let options: api::MinifyOptions =
+            serde_json::from_str(r#"{"compress": false, "mangle": {"props": {"regex": "^_"}}}"#)
+                .unwrap();
+
+        let output = api::minify_with_stats(
+            api::File::source("sum.js", code.as_str()),
+            &options,
+            api::KeepQuoted::No,
+        )
+        .expect("minify failed");
+        let stats = output.stats.expect("no stats");
+        assert_eq!(stats.input_size, code.len());
+        assert_eq!(stats.output_size, output.code.len());
+        assert!(
+            stats.gzip_size > 0 && stats.gzip_size < stats.output_size / 4,
+            "{stats:?}"
+        );
+        assert!(
+            stats.brotli_size > 0 && stats.brotli_size < stats.output_size / 4,
+            "{stats:?}"
+        );
+        // `values`, `factor`, `total`, `index` and `_sum`; the global `sum` keeps its name
+        assert_eq!(stats.mangled, 5, "{}", output.code);
+        assert!(stats.compress.is_zero(), "{stats:?}");
+        assert!(
+            !stats.mangle.is_zero() && !stats.codegen.is_zero(),
+            "{stats:?}"
+        );
+
+        let compressed = api::minify_with_stats(
+            api::File::source("sum.js", code.as_str()),
+            &api::MinifyOptions::default(),
+            api::KeepQuoted::No,
+        )
+        .expect("minify failed");
+        let compressed = compressed.stats.unwrap();
+        // Both stages are timed when both run, split where the mangler picks names
+        assert!(
+            !compressed.compress.is_zero() && !compressed.mangle.is_zero(),
+            "{compressed:?}"
+        );
+
+        let unmangled: api::MinifyOptions = serde_json::from_str(r#"{"mangle": false}"#).unwrap();
+        let unmangled = api::minify_with_stats(
+            api::File::source("sum.js", code.as_str()),
+            &unmangled,
+            api::KeepQuoted::No,
+        )
+        .expect("minify failed")
+        .stats
+        .unwrap();
+        assert!(
+            !unmangled.compress.is_zero() && unmangled.mangle.is_zero(),
+            "{unmangled:?}"
+        );
+
+        let quoted = api::minify_with_stats(
+            api::File::source("sum.js", code.replace("_sum:", "\"_sum\":")),
+            &options,
+            api::KeepQuoted::Yes,
+        )
+        .expect("minify failed");
+        assert!(quoted.code.contains("_sum"), "{}", quoted.code);
+        assert_eq!(quoted.stats.unwrap().mangled, 4);
+
+        let plain = api::minify_with_options(
+            api::File::source("sum.js", code),
+            &options,
+            api::KeepQuoted::No,
+        )
+        .expect("minify failed");
+        assert_eq!(plain.code, output.code);
+        assert_eq!(plain.stats, None);
+    }
+
+    #[test]
+    fn test_swc_minify_js_with_stats() {
+        let code = CString::new("var answer = 42; console.log(answer);").unwrap();
+        let options = CString::new(r#"{"toplevel": true}"#).unwrap();
+        let result = swc_minify_js_with_stats(code.as_ptr(), std::ptr::null(), options.as_ptr());
+        let stats = unsafe { *(*result).stats };
+        assert_eq!(stats.input_size, code.as_bytes().len());
+        assert_eq!(result_code(result).len(), stats.output_size);
+        assert!(stats.codegen_ms > 0.0);
+
+        let result = swc_minify_js_with_options(code.as_ptr(), std::ptr::null(), options.as_ptr());
+        assert!(unsafe { &*result }.stats.is_null());
+        swc_result_free(result);
+
+        let broken = CString::new("var = ;").unwrap();
+        let result = swc_minify_js_with_stats(broken.as_ptr(), std::ptr::null(), std::ptr::null());
+        assert!(unsafe { &*result }.stats.is_null());
+        assert_eq!(result_status(result), swc_status::Syntax);
+    }
+
+    /// Diagnostics received by `record_diagnostic`: file, message and frame
+    static REPORTED: std::sync::Mutex<Vec<(String, String, Option<String>)>> =
+        std::sync::Mutex::new(Vec::new());
+
+    /// Messages of the errors without a location received by `record_diagnostic`
+    static REPORTED_ERRORS: std::sync::Mutex<Vec<String>> = std::sync::Mutex::new(Vec::new());
+
+    unsafe extern "C" fn record_diagnostic(
+        user_data: *mut c_void,
+        diagnostic: *const swc_diagnostic,
+        frame: *const c_char,
+    ) {
+        assert_eq!(user_data as usize, 7);
+        let d = unsafe { &*diagnostic };
+        let string = |s: *const c_char| unsafe { CStr::from_ptr(s) }.to_str().unwrap().to_owned();
+        if d.file.is_null() {
+            if d.severity == swc_severity::Error {
+                assert!(frame.is_null());
+                REPORTED_ERRORS.lock().unwrap().push(string(d.message));
+            }
+            return;
+        }
+        if !string(d.file).starts_with("reported") {
+            return;
+        }
+        assert_eq!(d.severity, swc_severity::Error);
+        let frame = (!frame.is_null()).then(|| string(frame));
+        REPORTED
+            .lock()
+            .unwrap()
+            .push((string(d.file), string(d.message), frame));
+    }
+
+    // The callback is global, so this is the only test that installs one
+    #[test]
+    fn test_diagnostic_callback_receives_code_frames() {
+        swc_set_diagnostic_callback(Some(record_diagnostic), 7 as *mut c_void);
+
+        let file = CString::new("reported.ts").unwrap();
+        let broken = CString::new("let ok = 1;\nconst = 1;").unwrap();
+        let output = transpile(file.as_ptr(), broken.as_ptr());
+        assert!(output.is_null());
+
+        let file = CString::new("reported.js").unwrap();
+        let result = swc_minify_js_with_source_map(
+            broken.as_ptr(),
+            file.as_ptr(),
+            std::ptr::null(),
+            swc_source_map_mode::None,
+        );
+        assert_eq!(result_status(result), swc_status::Syntax);
+
+        let result = swc_transpile(file.as_ptr(), std::ptr::null());
+        assert_eq!(result_status(result), swc_status::InvalidArgument);
+        let output = transpile(file.as_ptr(), invalid_utf8().as_ptr());
+        assert!(output.is_null());
+        let json = br#"{"reported": {}}"#;
+        let cache = swc_mangle_cache_from_json(json.as_ptr(), json.len());
+        assert!(cache.is_null());
+
+        swc_set_diagnostic_callback(None, std::ptr::null_mut());
+        assert!(transpile(file.as_ptr(), broken.as_ptr()).is_null());
+
+        let errors = std::mem::take(&mut *REPORTED_ERRORS.lock().unwrap());
+        assert!(
+            errors.iter().any(|e| e == "`input` must not be null"),
+            "{errors:?}"
+        );
+        assert!(
+            errors
+                .iter()
+                .any(|e| e.starts_with("`input` is not valid UTF-8")),
+            "{errors:?}"
+        );
+        let unknown = errors
+            .iter()
+            .filter(|e| *e == "unknown section `reported` in mangle cache")
+            .count();
+        assert_eq!(unknown, 1, "{errors:?}");
+
+        let reported = std::mem::take(&mut *REPORTED.lock().unwrap());
+        let files: Vec<_> = reported.iter().map(|(file, ..)| file.as_str()).collect();
+        assert_eq!(files, ["reported.ts", "reported.js"]);
+        for (file, message, frame) in &reported {
+            let frame = frame.as_deref().expect("no code frame");
+            assert!(frame.contains(message.as_str()), "{frame}");
+            assert!(frame.contains(&format!("--> {file}:2:")), "{frame}");
+            assert!(frame.contains("2 | const = 1;"), "{frame}");
+        }
+    }
+
+    /// Checks that `sources` compile against the generated headers with the compiler named
+    /// by the environment variable `env`, or `default`. Skipped if there is no compiler.
+    fn assert_compiles(env: &str, default: &str, flags: &[&str], sources: &[&str]) {
+        let root = std::path::Path::new(env!("CARGO_MANIFEST_DIR"));
+        let compiler = std::env::var(env).unwrap_or_else(|_| String::from(default));
+
+        for source in sources {
+            let output = std::process::Command::new(&compiler)
+                .args(flags)
+                .args(["-fsyntax-only", "-Wall", "-Wextra", "-Werror", "-I"])
+                .arg(root)
+                .arg(root.join(source))
+                .output();
+            let output = match output {
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                    eprintln!("skipping {source}: no compiler `{compiler}` found");
+                    return;
+                }
+                output => output.expect("failed to run the compiler"),
+            };
+            assert!(
+                output.status.success(),
+                "{source} does not compile:\n{}",
+                String::from_utf8_lossy(&output.stderr)
+            );
+        }
+    }
+
+    #[test]
+    fn test_cxx_header_compiles() {
+        assert_compiles(
+            "CXX",
+            "c++",
+            &["-std=c++17"],
+            &["tests/swc_hpp.cpp", "examples/example.cpp"],
+        );
+    }
+
+    #[test]
+    fn test_c_header_compiles() {
+        assert_compiles("CC", "cc", &["-std=c99", "-pedantic"], &["tests/swc_c.c"]);
+    }
+
+    #[test]
+    fn test_c_header_only_declares_prefixed_functions() {
+        let root = std::path::Path::new(env!("CARGO_MANIFEST_DIR"));
+        let header = std::fs::read_to_string(root.join("swc_c.h")).unwrap();
+        let functions = header
+            .lines()
+            .filter(|line| !line.starts_with([' ', '#', '/', '}', '\t']))
+            .filter(|line| !line.starts_with("typedef"))
+            .filter_map(|line| line.split_once('(').map(|(declaration, _)| declaration))
+            .filter_map(|declaration| declaration.rsplit([' ', '*']).next())
+            .collect::<Vec<_>>();
+
+        assert!(functions.contains(&"swc_compile_js"));
+        let unprefixed = functions
+            .iter()
+            .filter(|name| !name.starts_with("swc_"))
+            .collect::<Vec<_>>();
+        assert!(unprefixed.is_empty(), "swc_c.h declares {unprefixed:?}");
     }
 }
@@ -0,0 +1,327 @@
+use crate::api;
+use crate::buffer::swc_buffer;
+use crate::diagnostics::Outcome;
+use crate::ffi::{catch_panic, guard, input_bytes};
+use crate::last_error;
+use crate::reporter;
+use crate::status::{Status, StatusExt};
+use anyhow::{anyhow, Context, Error};
+use std::collections::{BTreeMap, BTreeSet};
+use std::ptr;
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+use swc_atoms::Atom;
+use swc_common::SyntaxContext;
+use swc_ecma_ast::{Id, Ident, Program};
+use swc_ecma_minifier::option::MangleOptions;
+use swc_ecma_transforms_base::rename::{rename, RenameMap};
+use swc_ecma_visit::{Visit, VisitMutWith, VisitWith};
+
+/// Mangled names shared between minify calls
+///
+/// Holds the name every top-level variable and mangled property was given, like the
+/// `nameCache` of terser. A minification that uses the cache gives the names it already holds
+/// the same name again, including references to globals declared by another chunk, and adds
+/// the names it picks for new ones. Minifying all chunks of a build with one cache thus
+/// mangles a shared name the same way everywhere, and a cache restored with
+/// [`MangleCache::from_json`] keeps the names of unchanged code stable across builds.
+///
+/// Variables are only cached when top-level names are mangled, i.e. for modules or with
+/// `toplevel`, and properties when `mangle.props` is set. Clones share the same names, and
+/// minifications sharing them take turns while they pick names.
+#[derive(Clone, Debug, Default)]
+pub struct MangleCache {
+    names: Arc<Mutex<Names>>,
+}
+
+/// Original names mapped to mangled ones
+#[derive(Debug, Default)]
+pub(crate) struct Names {
+    vars: BTreeMap<String, String>,
+    pub props: BTreeMap<String, String>,
+}
+
+impl MangleCache {
+    pub fn new() -> Self {
+        MangleCache::default()
+    }
+
+    /// Restores a cache saved with [`MangleCache::to_json`].
+    pub fn from_json(json: &str) -> Result<Self, api::Error> {
+        parse(json).map_err(api::failure)
+    }
+
+    /// Serializes the cache to JSON, with a `vars` and a `props` object mapping the original
+    /// names to the mangled ones. Keys are sorted, so an unchanged cache serializes to the same
+    /// text.
+    pub fn to_json(&self) -> String {
+        let names = self.lock();
+        let json = serde_json::json!({ "vars": names.vars, "props": names.props });
+        serde_json::to_string_pretty(&json).expect("failed to serialize the mangle cache")
+    }
+
+    /// Number of variables and properties with a mangled name
+    pub fn len(&self) -> usize {
+        let names = self.lock();
+        names.vars.len() + names.props.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Locks the names for a minification, which holds the lock from applying cached names
+    /// until it added its own, so other minifications cannot pick the same names meanwhile.
+    pub(crate) fn lock(&self) -> MutexGuard<'_, Names> {
+        self.names.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+impl Names {
+    /// Renames the top-level variables of `program` the cache holds and reserves every cached
+    /// name in `mangle`, so the mangler keeps them and does not hand them out again.
+    ///
+    /// Other variables of the chunk that already go by a cached name, e.g. a parameter `w`, are
+    /// renamed out of the way first so they cannot end up sharing it. A cached name that is
+    /// also a global the chunk uses is left out for this chunk, as the global cannot be renamed.
+    ///
+    /// `top_level` and `unresolved` are the contexts of top-level declarations and of
+    /// references to globals after `resolver` ran. Returns the number of variables renamed.
+    pub(crate) fn apply(
+        &self,
+        program: &mut Program,
+        mangle: &mut MangleOptions,
+        top_level: SyntaxContext,
+        unresolved: SyntaxContext,
+    ) -> usize {
+        let vars = &self.vars;
+        if vars.is_empty() {
+            return 0;
+        }
+
+        let mut collector = CachedIdents {
+            vars,
+            mangled: vars.values().map(|name| name.as_str()).collect(),
+            contexts: [top_level, unresolved],
+            map: RenameMap::default(),
+            clashing: BTreeSet::new(),
+            used: BTreeSet::new(),
+        };
+        program.visit_with(&mut collector);
+
+        let globals = collector
+            .clashing
+            .iter()
+            .filter(|id| id.1 == unresolved && !collector.map.contains_key(*id))
+            .map(|(name, _)| name.clone())
+            .collect::<BTreeSet<_>>();
+        collector
+            .map
+            .retain(|_, mangled| !globals.contains(mangled));
+
+        let mut map = collector.map.clone();
+        for id in &collector.clashing {
+            if id.1 == unresolved || map.contains_key(id) {
+                continue;
+            }
+            let free = (1..)
+                .map(|n| Atom::from(format!("{}_{n}", id.0)))
+                .find(|name| !collector.used.contains(name))
+                .expect("ran out of names");
+            collector.used.insert(free.clone());
+            map.insert(id.clone(), free);
+        }
+        if !map.is_empty() {
+            program.visit_mut_with(&mut rename(&map));
+        }
+
+        let reserved = mangle.reserved.iter().cloned().collect::<BTreeSet<_>>();
+        mangle.reserved.extend(
+            vars.values()
+                .map(|name| Atom::from(name.as_str()))
+                .filter(|name| !reserved.contains(name)),
+        );
+        collector
+            .map
+            .iter()
+            .filter(|((name, _), mangled)| name != *mangled)
+            .count()
+    }
+
+    /// Adds the names the mangler picked for top-level variables, keeping cached ones.
+    pub(crate) fn update(&mut self, renamed: &RenameMap, top_level: SyntaxContext) {
+        for ((name, ctxt), mangled) in renamed {
+            if *ctxt == top_level {
+                self.vars
+                    .entry(name.to_string())
+                    .or_insert_with(|| mangled.to_string());
+            }
+        }
+    }
+}
+
+/// Finds the identifiers with a top-level or global context whose name is cached, and those
+/// that already go by a name the cache hands out
+struct CachedIdents<'a> {
+    vars: &'a BTreeMap<String, String>,
+    /// The mangled names of `vars`
+    mangled: BTreeSet<&'a str>,
+    contexts: [SyntaxContext; 2],
+    map: RenameMap,
+    /// Identifiers named like a mangled name, in any scope
+    clashing: BTreeSet<Id>,
+    /// Every name the program uses
+    used: BTreeSet<Atom>,
+}
+
+impl Visit for CachedIdents<'_> {
+    fn visit_ident(&mut self, ident: &Ident) {
+        self.used.insert(ident.sym.clone());
+        if self.mangled.contains(ident.sym.as_str()) {
+            self.clashing.insert(ident.to_id());
+        }
+        if !self.contexts.contains(&ident.ctxt) {
+            return;
+        }
+        if let Some(mangled) = self.vars.get(ident.sym.as_str()) {
+            let id: Id = ident.to_id();
+            self.map.insert(id, Atom::from(mangled.as_str()));
+        }
+    }
+}
+
+/// Parses the JSON written by [`MangleCache::to_json`].
+fn parse(json: &str) -> Result<MangleCache, Error> {
+    let mut sections: BTreeMap<String, BTreeMap<String, String>> =
+        serde_json::from_str(json).context("failed to parse mangle cache")?;
+    let vars = sections.remove("vars").unwrap_or_default();
+    let props = sections.remove("props").unwrap_or_default();
+    if let Some(section) = sections.keys().next() {
+        return Err(anyhow!("unknown section `{section}` in mangle cache"))
+            .status(Status::InvalidConfig);
+    }
+
+    Ok(MangleCache {
+        names: Arc::new(Mutex::new(Names { vars, props })),
+    })
+}
+
+/// Opaque handle holding mangled names shared between minify calls
+///
+/// Pass the same handle to every `minify_js_with_cache` or `swc_minify_js_with_cache` call
+/// whose output should use the same names, e.g. all chunks of a build, and save it with
+/// `swc_mangle_cache_to_json` so the next build picks the same names again.
+///
+/// A handle may be used from several threads at once. The calls then take turns while they
+/// pick names, so two chunks never give different variables or properties the same name,
+/// but which chunk gets the shorter names depends on the order they run in.
+#[allow(non_camel_case_types)]
+pub struct swc_mangle_cache {
+    pub(crate) cache: MangleCache,
+}
+
+/// Borrows the cache behind `cache`, rejecting null handles.
+pub(crate) fn mangle_cache_ref<'a>(
+    cache: *const swc_mangle_cache,
+) -> Result<&'a MangleCache, Error> {
+    unsafe { cache.as_ref() }
+        .map(|handle| &handle.cache)
+        .ok_or_else(|| anyhow!("`cache` must not be null"))
+        .status(Status::InvalidArgument)
+}
+
+/// Creates an empty mangle cache.
+///
+/// # Returns
+///
+/// Pointer to a new cache that must be released with `swc_mangle_cache_free()`
+#[no_mangle]
+pub extern "C" fn swc_mangle_cache_new() -> *mut swc_mangle_cache {
+    Box::into_raw(Box::new(swc_mangle_cache {
+        cache: MangleCache::new(),
+    }))
+}
+
+/// Restores a mangle cache saved with `swc_mangle_cache_to_json()`.
+///
+/// # Parameters
+///
+/// * `json` - Pointer to the saved cache as UTF-8; may be null if `len` is 0
+/// * `len` - Number of bytes at `json`
+///
+/// # Returns
+///
+/// * On success: Pointer to a new cache that must be released with `swc_mangle_cache_free()`
+/// * On failure: Null pointer; `swc_last_error()` tells why
+///
+/// # Safety
+///
+/// This function is unsafe because:
+/// * It dereferences raw pointers
+/// * It allocates memory that must be freed by the caller using `swc_mangle_cache_free()`
+///
+/// # Examples
+///
+/// ```c
+/// swc_buffer saved = read_file("build/mangle-cache.json");
+/// swc_mangle_cache* cache = saved.ptr ? swc_mangle_cache_from_json(saved.ptr, saved.len)
+///                                     : swc_mangle_cache_new();
+/// ```
+#[no_mangle]
+pub extern "C" fn swc_mangle_cache_from_json(json: *const u8, len: usize) -> *mut swc_mangle_cache {
+    let outcome = guard(|| {
+        let json = input_bytes(json, len, "json")?;
+        Ok(Outcome::success(parse(json)?))
+    });
+    reporter::report_rest(&outcome);
+    last_error::record(&outcome);
+    outcome.value.map_or(ptr::null_mut(), |cache| {
+        Box::into_raw(Box::new(swc_mangle_cache { cache }))
+    })
+}
+
+/// Serializes a mangle cache to JSON.
+///
+/// The JSON has a `vars` and a `props` object mapping the original names of top-level
+/// variables and properties to the mangled ones, with sorted keys so an unchanged cache is
+/// saved as the same text.
+///
+/// # Returns
+///
+/// The JSON in a buffer that must be released with `swc_buffer_free()`; its `ptr` is null if
+/// `cache` is null or the host allocator refused the memory
+///
+/// # Safety
+///
+/// The pointer must have been returned by `swc_mangle_cache_new()` or
+/// `swc_mangle_cache_from_json()`.
+///
+/// # Examples
+///
+/// ```c
+/// swc_buffer json = swc_mangle_cache_to_json(cache);
+/// write_file("build/mangle-cache.json", json.ptr, json.len);
+/// swc_buffer_free(json);
+/// ```
+#[no_mangle]
+pub extern "C" fn swc_mangle_cache_to_json(cache: *const swc_mangle_cache) -> swc_buffer {
+    catch_panic(|| swc_buffer::new(mangle_cache_ref(cache)?.to_json().as_bytes()))
+        .unwrap_or_else(|_| swc_buffer::null())
+}
+
+/// Frees a mangle cache.
+///
+/// Passing a null pointer is a no-op.
+///
+/// # Safety
+///
+/// The pointer must have been returned by `swc_mangle_cache_new()` or
+/// `swc_mangle_cache_from_json()` and must not be used after this call.
+#[no_mangle]
+pub extern "C" fn swc_mangle_cache_free(cache: *mut swc_mangle_cache) {
+    if !cache.is_null() {
+        let _ = catch_panic(|| {
+            let _ = unsafe { Box::from_raw(cache) };
+            Ok(())
+        });
+    }
+}
@@ -0,0 +1,456 @@
+//! Property mangling with names shared through a [`crate::mangle_cache::MangleCache`].
+//!
+//! swc's own property mangler numbers its names from scratch in every call, so the same
+//! property gets a different name in each file, and `swc_ecma_minifier` keeps it in a private
+//! module, so it cannot be handed the cached names either. The library decides which
+//! properties may be mangled by the same rules, `regex`, `reserved` and the built-in list of
+//! DOM and JavaScript properties, and picks the names itself.
+
+use anyhow::{anyhow, Error};
+use std::collections::{BTreeMap, BTreeSet, HashSet};
+use std::sync::LazyLock;
+use swc_atoms::{Atom, Wtf8Atom};
+use swc_common::SyntaxContext;
+use swc_ecma_ast::*;
+use swc_ecma_minifier::option::ManglePropertiesOptions;
+use swc_ecma_visit::{Visit, VisitMut, VisitMutWith, VisitWith};
+
+/// Whether quoted property names are mangled, like terser's `keep_quoted`
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum KeepQuoted {
+    /// `obj["name"]` and `{"name": 1}` are mangled like `obj.name`
+    #[default]
+    No,
+    /// Quoted names are kept, and so is every other use of a name that is quoted anywhere
+    Yes,
+    /// Quoted names are kept, but unquoted uses of the same name are mangled
+    Strict,
+}
+
+/// Removes `keep_quoted` from the `mangle.props` (or `mangle.properties`) object of minifier
+/// options, which swc itself would ignore.
+pub(crate) fn take_keep_quoted(options: &mut serde_json::Value) -> Result<KeepQuoted, Error> {
+    let props = options.get_mut("mangle").and_then(|mangle| {
+        let key = ["props", "properties"]
+            .into_iter()
+            .find(|key| mangle.get(key).is_some())?;
+        mangle.get_mut(key)?.as_object_mut()
+    });
+    let Some(value) = props.and_then(|props| {
+        ["keep_quoted", "keepQuoted"]
+            .into_iter()
+            .find_map(|key| props.remove(key))
+    }) else {
+        return Ok(KeepQuoted::No);
+    };
+
+    match value {
+        serde_json::Value::Bool(false) => Ok(KeepQuoted::No),
+        serde_json::Value::Bool(true) => Ok(KeepQuoted::Yes),
+        serde_json::Value::String(s) if s == "strict" => Ok(KeepQuoted::Strict),
+        other => Err(anyhow!(
+            "`keep_quoted` must be true, false or \"strict\", not {other}"
+        )),
+    }
+}
+
+/// Mangles the property names of `program` as `options` allow, reusing the cached names in
+/// `props` and adding the ones picked for new properties.
+///
+/// `unresolved` is the context of references to undeclared variables, whose properties are
+/// only mangled with `undeclared` or if their name is cached, as in swc and terser. Returns
+/// the number of property names it mangled.
+///
+/// Fails without renaming anything if a cached name is also a property this chunk keeps, as
+/// the property could then neither get its cached name nor another one that other chunks
+/// agree on.
+pub(crate) fn mangle_properties(
+    program: &mut Program,
+    options: &ManglePropertiesOptions,
+    keep_quoted: KeepQuoted,
+    unresolved: SyntaxContext,
+    props: &mut BTreeMap<String, String>,
+) -> Result<usize, Error> {
+    let mut names = PropNames {
+        unresolved,
+        undeclared: options.undeclared == Some(true),
+        uses: BTreeMap::new(),
+    };
+    program.visit_with(&mut names);
+
+    let candidates = names
+        .uses
+        .iter()
+        .filter(|(name, usage)| usage.candidate || props.contains_key(name.as_str()))
+        .filter(|(_, usage)| match keep_quoted {
+            KeepQuoted::No => true,
+            KeepQuoted::Yes => !usage.quoted,
+            KeepQuoted::Strict => usage.unquoted,
+        })
+        .map(|(name, _)| name.clone())
+        .collect();
+    let mangled = mangleable(candidates, options);
+    if mangled.is_empty() {
+        return Ok(0);
+    }
+
+    let kept = names
+        .uses
+        .keys()
+        .filter(|name| !mangled.contains(*name))
+        .map(|name| name.to_string())
+        .collect::<BTreeSet<_>>();
+    for name in &mangled {
+        match props.get(name.as_str()) {
+            Some(cached) if kept.contains(cached) => {
+                return Err(anyhow!(
+                    "property `{name}` cannot be mangled to its cached name `{cached}`, which \
+                     is a property of this file that is not mangled"
+                ))
+            }
+            _ => {}
+        }
+    }
+
+    let renamed = {
+        let mut taken = kept
+            .iter()
+            .cloned()
+            .chain(props.values().cloned())
+            .chain(options.reserved.iter().map(|name| name.to_string()))
+            .collect::<BTreeSet<_>>();
+
+        let mut next = 0;
+        let mut fresh = || loop {
+            let short = short_name(next);
+            next += 1;
+            if Ident::verify_symbol(&short).is_ok() && taken.insert(short.clone()) {
+                break short;
+            }
+        };
+        mangled
+            .iter()
+            .map(|name| {
+                let short = match props.get(name.as_str()) {
+                    Some(cached) => cached.clone(),
+                    None => {
+                        let short = fresh();
+                        props.insert(name.to_string(), short.clone());
+                        short
+                    }
+                };
+                (name.clone(), Atom::from(short.as_str()))
+            })
+            .collect::<BTreeMap<_, _>>()
+    };
+
+    program.visit_mut_with(&mut Renamer {
+        renamed: &renamed,
+        quoted: keep_quoted == KeepQuoted::No,
+    });
+    Ok(renamed.len())
+}
+
+/// Properties of the DOM and JavaScript built-ins, which are never mangled
+///
+/// The lists of swc's property mangler, which it does not export. See `src/lists/NOTICE` for
+/// where they come from and their license.
+static BUILTIN_PROPS: LazyLock<HashSet<String>> = LazyLock::new(|| {
+    [
+        include_str!("lists/domprops.json"),
+        include_str!("lists/jsprops.json"),
+    ]
+    .into_iter()
+    .flat_map(|list| {
+        serde_json::from_str::<Vec<String>>(list).expect("invalid list of built-in properties")
+    })
+    .collect()
+});
+
+/// Which of `names` may be mangled: identifiers that match `regex` and are neither
+/// `reserved` nor built-in properties, as in swc's property mangler.
+fn mangleable(names: BTreeSet<Atom>, options: &ManglePropertiesOptions) -> BTreeSet<Atom> {
+    names
+        .into_iter()
+        .filter(|name| {
+            Ident::verify_symbol(name).is_ok()
+                && options
+                    .regex
+                    .as_ref()
+                    .is_none_or(|regex| regex.is_match(name))
+                && !options.reserved.contains(name)
+                && !BUILTIN_PROPS.contains(name.as_str())
+        })
+        .collect()
+}
+
+/// The `n`th short name: `a` to `_`, then two characters and so on
+fn short_name(mut n: usize) -> String {
+    const FIRST: &[u8] = b"abcdefghijklmnopqrstuvwxyzABCDEFGHIJKLMNOPQRSTUVWXYZ$_";
+    const REST: &[u8] = b"abcdefghijklmnopqrstuvwxyzABCDEFGHIJKLMNOPQRSTUVWXYZ$_0123456789";
+
+    let mut name = String::from(FIRST[n % FIRST.len()] as char);
+    n /= FIRST.len();
+    while n > 0 {
+        n -= 1;
+        name.push(REST[n % REST.len()] as char);
+        n /= REST.len();
+    }
+    name
+}
+
+/// The property name argument of `Object.defineProperty(obj, "name", descriptor)`
+fn defined_property(call: &CallExpr) -> Option<&Expr> {
+    let Callee::Expr(callee) = &call.callee else {
+        return None;
+    };
+    let member = callee.as_member()?;
+    let is_define = member.obj.as_ident().is_some_and(|obj| obj.sym == "Object")
+        && member
+            .prop
+            .as_ident()
+            .is_some_and(|prop| prop.sym == "defineProperty");
+    match &call.args[..] {
+        [_, name, ..] if is_define && name.spread.is_none() => Some(&name.expr),
+        _ => None,
+    }
+}
+
+/// How a program uses a property name
+#[derive(Default)]
+struct Usage {
+    /// Used as in `obj.name`
+    unquoted: bool,
+    /// Used as in `obj["name"]`
+    quoted: bool,
+    /// Used other than as a property of an undeclared variable, or `undeclared` is set
+    candidate: bool,
+}
+
+/// Collects the property names a program uses
+struct PropNames {
+    unresolved: SyntaxContext,
+    undeclared: bool,
+    uses: BTreeMap<Atom, Usage>,
+}
+
+impl PropNames {
+    fn add(&mut self, name: &Atom, quoted: bool, candidate: bool) {
+        let usage = self.uses.entry(name.clone()).or_default();
+        if quoted {
+            usage.quoted = true;
+        } else {
+            usage.unquoted = true;
+        }
+        usage.candidate |= candidate;
+    }
+
+    fn add_quoted(&mut self, expr: &Expr, candidate: bool) {
+        if let Expr::Lit(Lit::Str(s)) = expr {
+            self.add_str(s, candidate);
+        }
+    }
+
+    fn add_str(&mut self, s: &Str, candidate: bool) {
+        if let Some(name) = s.value.as_atom() {
+            self.add(name, true, candidate);
+        }
+    }
+
+    /// Whether the properties of `obj` may be mangled: those of declared variables, as in
+    /// swc, or of any variable with `undeclared`
+    fn is_candidate_object(&self, obj: &Expr) -> bool {
+        match obj {
+            Expr::Member(member) => self.is_candidate_object(&member.obj),
+            Expr::Ident(ident) => self.undeclared || ident.ctxt != self.unresolved,
+            _ => self.undeclared,
+        }
+    }
+}
+
+impl Visit for PropNames {
+    fn visit_member_expr(&mut self, member: &MemberExpr) {
+        member.visit_children_with(self);
+        let candidate = self.is_candidate_object(&member.obj);
+        match &member.prop {
+            MemberProp::Ident(ident) => self.add(&ident.sym, false, candidate),
+            MemberProp::Computed(computed) => self.add_quoted(&computed.expr, candidate),
+            MemberProp::PrivateName(_) => {}
+        }
+    }
+
+    fn visit_super_prop(&mut self, prop: &SuperProp) {
+        prop.visit_children_with(self);
+        match prop {
+            SuperProp::Ident(ident) => self.add(&ident.sym, false, true),
+            SuperProp::Computed(computed) => self.add_quoted(&computed.expr, true),
+        }
+    }
+
+    fn visit_prop_name(&mut self, name: &PropName) {
+        name.visit_children_with(self);
+        match name {
+            PropName::Ident(ident) => self.add(&ident.sym, false, true),
+            PropName::Str(s) => self.add_str(s, true),
+            _ => {}
+        }
+    }
+
+    fn visit_prop(&mut self, prop: &Prop) {
+        prop.visit_children_with(self);
+        if let Prop::Shorthand(ident) = prop {
+            self.add(&ident.sym, false, true);
+        }
+    }
+
+    fn visit_assign_pat_prop(&mut self, prop: &AssignPatProp) {
+        prop.visit_children_with(self);
+        self.add(&prop.key.sym, false, true);
+    }
+
+    fn visit_bin_expr(&mut self, expr: &BinExpr) {
+        expr.visit_children_with(self);
+        if expr.op == BinaryOp::In {
+            let candidate = self.is_candidate_object(&expr.right);
+            self.add_quoted(&expr.left, candidate);
+        }
+    }
+
+    fn visit_call_expr(&mut self, call: &CallExpr) {
+        call.visit_children_with(self);
+        if let Some(name) = defined_property(call) {
+            let candidate = self.is_candidate_object(&call.args[0].expr);
+            self.add_quoted(name, candidate);
+        }
+    }
+}
+
+/// Renames properties to the names picked for them, at quoted uses only if `quoted` is set
+struct Renamer<'a> {
+    renamed: &'a BTreeMap<Atom, Atom>,
+    quoted: bool,
+}
+
+impl Renamer<'_> {
+    fn rename_ident(&self, ident: &mut IdentName) {
+        if let Some(name) = self.renamed.get(&ident.sym) {
+            ident.sym = name.clone();
+        }
+    }
+
+    fn rename_str(&self, s: &mut Str) {
+        if !self.quoted {
+            return;
+        }
+        if let Some(name) = s.value.as_atom().and_then(|value| self.renamed.get(value)) {
+            s.value = Wtf8Atom::from(name.clone());
+            s.raw = None;
+        }
+    }
+
+    fn rename_quoted(&self, expr: &mut Expr) {
+        if let Expr::Lit(Lit::Str(s)) = expr {
+            self.rename_str(s);
+        }
+    }
+}
+
+impl VisitMut for Renamer<'_> {
+    fn visit_mut_member_prop(&mut self, prop: &mut MemberProp) {
+        prop.visit_mut_children_with(self);
+        match prop {
+            MemberProp::Ident(ident) => self.rename_ident(ident),
+            MemberProp::Computed(computed) => self.rename_quoted(&mut computed.expr),
+            MemberProp::PrivateName(_) => {}
+        }
+    }
+
+    fn visit_mut_super_prop(&mut self, prop: &mut SuperProp) {
+        prop.visit_mut_children_with(self);
+        match prop {
+            SuperProp::Ident(ident) => self.rename_ident(ident),
+            SuperProp::Computed(computed) => self.rename_quoted(&mut computed.expr),
+        }
+    }
+
+    fn visit_mut_prop_name(&mut self, name: &mut PropName) {
+        name.visit_mut_children_with(self);
+        match name {
+            PropName::Ident(ident) => self.rename_ident(ident),
+            PropName::Str(s) => self.rename_str(s),
+            _ => {}
+        }
+    }
+
+    fn visit_mut_prop(&mut self, prop: &mut Prop) {
+        prop.visit_mut_children_with(self);
+        if let Prop::Shorthand(ident) = prop {
+            if let Some(name) = self.renamed.get(&ident.sym) {
+                // `{ name }` becomes `{ a: name }`
+                *prop = Prop::KeyValue(KeyValueProp {
+                    key: PropName::Ident(IdentName::new(name.clone(), ident.span)),
+                    value: Box::new(Expr::Ident(ident.clone())),
+                });
+            }
+        }
+    }
+
+    fn visit_mut_object_pat_prop(&mut self, prop: &mut ObjectPatProp) {
+        prop.visit_mut_children_with(self);
+        let ObjectPatProp::Assign(assign) = prop else {
+            return;
+        };
+        let Some(name) = self.renamed.get(&assign.key.sym) else {
+            return;
+        };
+
+        // `{ name = 1 }` becomes `{ a: name = 1 }`
+        let binding = Pat::Ident(assign.key.clone());
+        let value = match assign.value.take() {
+            Some(default) => Pat::Assign(AssignPat {
+                span: assign.span,
+                left: Box::new(binding),
+                right: default,
+            }),
+            None => binding,
+        };
+        *prop = ObjectPatProp::KeyValue(KeyValuePatProp {
+            key: PropName::Ident(IdentName::new(name.clone(), assign.key.id.span)),
+            value: Box::new(value),
+        });
+    }
+
+    fn visit_mut_bin_expr(&mut self, expr: &mut BinExpr) {
+        expr.visit_mut_children_with(self);
+        if expr.op == BinaryOp::In {
+            self.rename_quoted(&mut expr.left);
+        }
+    }
+
+    fn visit_mut_call_expr(&mut self, call: &mut CallExpr) {
+        call.visit_mut_children_with(self);
+        if defined_property(call).is_some() {
+            self.rename_quoted(&mut call.args[1].expr);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The version of `swc_ecma_minifier` the lists in `src/lists` are copied from
+    const LISTS_VERSION: &str = "50.0.0";
+
+    #[test]
+    fn test_lists_match_swc_ecma_minifier() {
+        let manifest = include_str!("../Cargo.toml");
+        let dependency = format!("swc_ecma_minifier = \"{LISTS_VERSION}\"");
+        assert!(
+            manifest.lines().any(|line| line == dependency),
+            "src/lists is copied from swc_ecma_minifier {LISTS_VERSION}, see src/lists/NOTICE"
+        );
+        assert!(BUILTIN_PROPS.contains("addEventListener"));
+        assert!(BUILTIN_PROPS.contains("hasOwnProperty"));
+    }
+}
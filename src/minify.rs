@@ -0,0 +1,571 @@
+//! Minification with swc's minifier, run step by step as `swc::Compiler::minify` does.
+//!
+//! `Compiler::minify` keeps the program and its comments to itself, and its only hook is a
+//! name cache that can veto names. The library runs the same public steps of swc instead, so
+//! it can rename cached variables before the mangler sees them, mangle properties itself, see
+//! [`crate::mangle_props`], collect the license comments it keeps and time the stages. The
+//! only code copied from `Compiler::minify` is the translation of its options, [`settings`].
+//!
+//! [`minify_to`] prints the program itself rather than through `Compiler::print`, which
+//! returns the code as one string, so the code can be written out while it is generated.
+
+use crate::comments::licenses;
+use crate::mangle_cache::MangleCache;
+use crate::mangle_props::{mangle_properties, KeepQuoted};
+use crate::source_map::{finish_stream, LineTracker, SourceMapMode};
+use crate::stats::MinifyStats;
+use crate::status::{Status, StatusExt};
+use anyhow::{Context, Error};
+use rustc_hash::FxHashMap;
+use std::io::Write;
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+use std::time::Instant;
+use swc_atoms::Atom;
+use swc_common::comments::{Comments, SingleThreadedComments};
+use swc_common::errors::Handler;
+use swc_common::source_map::SourceMapGenConfig;
+use swc_common::{BytePos, FileName, Mark, SourceFile, SyntaxContext};
+use swc_compiler::config::{JsMinifyCommentOption, JsMinifyOptions, SourceMapsConfig};
+use swc_compiler::{BoolOr, Compiler, PrintArgs, TransformOutput};
+use swc_compiler_base::IdentCollector;
+use swc_config::file_pattern::FilePattern;
+use swc_ecma_ast::{EsVersion, Program};
+use swc_ecma_codegen::text_writer::{omit_trailing_semi, JsWriter, WriteJs};
+use swc_ecma_codegen::Emitter;
+use swc_ecma_minifier::option::{ExtraOptions, MinifyOptions, TopLevelOptions};
+use swc_ecma_parser::{EsSyntax, Syntax};
+use swc_ecma_transforms_base::fixer::{fixer, paren_remover};
+use swc_ecma_transforms_base::hygiene::hygiene;
+use swc_ecma_transforms_base::rename::RenameMap;
+use swc_ecma_transforms_base::resolver;
+use swc_ecma_visit::{VisitMutWith, VisitWith};
+
+/// Collects the names the mangler picks during one minification
+#[derive(Default)]
+struct Renamed {
+    names: Mutex<RenameMap>,
+    /// When the mangler asked for cached names, which it does once compression is done and
+    /// it prepared for renaming variables
+    mangling_since: Mutex<Option<Instant>>,
+}
+
+impl Renamed {
+    fn names(&self) -> MutexGuard<'_, RenameMap> {
+        self.names.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    fn mangling_since(&self) -> Option<Instant> {
+        *self
+            .mangling_since
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+impl swc_ecma_minifier::option::MangleCache for Renamed {
+    fn vars_cache(&self, op: &mut dyn FnMut(&RenameMap)) {
+        *self
+            .mangling_since
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner) = Some(Instant::now());
+        // The mangler only keeps the names of the variables handed out here, it cannot give
+        // them one. `Names::apply` renamed cached variables beforehand, so none are
+        op(&RenameMap::default())
+    }
+
+    fn props_cache(&self, op: &mut dyn FnMut(&FxHashMap<Atom, Atom>)) {
+        op(&Default::default())
+    }
+
+    fn update_vars_cache(&self, new_data: &RenameMap) {
+        self.names()
+            .extend(new_data.iter().map(|(id, name)| (id.clone(), name.clone())));
+    }
+
+    fn update_props_cache(&self, _: &FxHashMap<Atom, Atom>) {}
+}
+
+/// The result of [`minify`]
+pub(crate) struct Minified {
+    pub output: TransformOutput,
+    /// License comments kept in the code
+    pub licenses: Vec<String>,
+    /// Everything but the output sizes, if asked for
+    pub stats: Option<MinifyStats>,
+}
+
+/// Minifies `fm` like `Compiler::minify`, using and updating `cache` if there is one.
+///
+/// `keep_quoted` applies to property mangling, which swc's options have no setting for.
+/// `measure` asks for the stats of the minification.
+pub(crate) fn minify(
+    compiler: &Compiler,
+    fm: Arc<SourceFile>,
+    handler: &Handler,
+    opts: &JsMinifyOptions,
+    keep_quoted: KeepQuoted,
+    cache: Option<&MangleCache>,
+    measure: bool,
+) -> Result<Minified, Error> {
+    compiler.run(|| {
+        let Optimized {
+            program,
+            comments,
+            settings,
+            source_map_names,
+            mut licenses,
+            mut stats,
+        } = optimize(compiler, &fm, handler, opts, keep_quoted, cache, measure)?;
+
+        let start = Instant::now();
+        let output = compiler.print(
+            &program,
+            PrintArgs {
+                source_root: None,
+                source_file_name: Some(&fm.name.to_string()),
+                output_path: opts.output_path.clone().map(From::from),
+                inline_sources_content: opts.inline_sources_content,
+                source_map: settings.source_map,
+                source_map_ignore_list: opts.source_map_ignore_list.clone(),
+                source_map_names: &source_map_names,
+                orig: settings.orig,
+                comments: Some(&comments),
+                emit_source_map_columns: opts.emit_source_map_columns,
+                emit_source_map_scopes: false,
+                preamble: &opts.format.preamble,
+                codegen_config: settings.codegen,
+                output: None,
+                source_map_url: settings.source_map_url,
+            },
+        )?;
+        if let Some(stats) = &mut stats {
+            stats.codegen = start.elapsed();
+        }
+        licenses.retain(|license| output.code.contains(license.as_str()));
+        Ok(Minified {
+            output,
+            licenses,
+            stats,
+        })
+    })
+}
+
+/// Minifies `fm` like [`minify`] without a cache, but writes the code to `out` while it is
+/// generated instead of returning it.
+///
+/// Returns the source map if one was generated and `source_maps` does not inline it.
+pub(crate) fn minify_to(
+    compiler: &Compiler,
+    fm: Arc<SourceFile>,
+    handler: &Handler,
+    opts: &JsMinifyOptions,
+    source_maps: SourceMapMode,
+    out: &mut dyn Write,
+) -> Result<Option<String>, Error> {
+    compiler.run(|| {
+        let Optimized {
+            program,
+            comments,
+            settings,
+            source_map_names,
+            ..
+        } = optimize(
+            compiler,
+            &fm,
+            handler,
+            opts,
+            KeepQuoted::default(),
+            None,
+            false,
+        )?;
+
+        // What `Compiler::print` does, but writing to `out`
+        let mut out = LineTracker::new(out);
+        let mut mappings = vec![];
+        {
+            let mut writer = JsWriter::new(
+                compiler.cm.clone(),
+                "\n",
+                &mut out,
+                settings.source_map.enabled().then_some(&mut mappings),
+            );
+            writer.preamble(&opts.format.preamble)?;
+            let mut wr: Box<dyn WriteJs> = Box::new(writer);
+            if settings.codegen.minify {
+                wr = Box::new(omit_trailing_semi(wr));
+            }
+            let mut emitter = Emitter {
+                cfg: settings.codegen,
+                comments: Some(&comments),
+                cm: compiler.cm.clone(),
+                wr,
+            };
+            emitter
+                .emit_program(&program)
+                .context("failed to emit module")?;
+        }
+
+        if !settings.source_map.enabled() {
+            return Ok(None);
+        }
+        let source_file_name = fm.name.to_string();
+        let config = MapConfig {
+            source_file_name: &source_file_name,
+            names: &source_map_names,
+            inline_sources_content: opts.inline_sources_content,
+            emit_columns: opts.emit_source_map_columns,
+            ignore_list: opts.source_map_ignore_list.as_ref(),
+        };
+        let mut map = vec![];
+        compiler
+            .cm
+            .build_source_map(&mappings, settings.orig, config)
+            .to_writer(&mut map)
+            .context("failed to write source map")?;
+        let map = String::from_utf8(map).context("source map is not utf-8")?;
+        if let Some(url) = settings.source_map_url {
+            write!(out, "\n//# sourceMappingURL={url}")?;
+        }
+        Ok(finish_stream(&mut out, Some(map), source_maps)?)
+    })
+}
+
+/// The source map settings `Compiler::print` uses for [`minify`]
+///
+/// Mirrors the private `SwcSourceMapConfig` of `swc_compiler_base` 53.0.0 for the arguments
+/// [`minify`] passes, and to be compared with it whenever `swc` is upgraded.
+struct MapConfig<'a> {
+    source_file_name: &'a str,
+    /// Original names of the identifiers
+    names: &'a FxHashMap<BytePos, Atom>,
+    inline_sources_content: bool,
+    emit_columns: bool,
+    ignore_list: Option<&'a FilePattern>,
+}
+
+impl SourceMapGenConfig for MapConfig<'_> {
+    fn file_name_to_source(&self, _f: &FileName) -> String {
+        self.source_file_name.to_owned()
+    }
+
+    fn name_for_bytepos(&self, pos: BytePos) -> Option<&str> {
+        self.names.get(&pos).map(|name| name.as_str())
+    }
+
+    fn inline_sources_content(&self, _f: &FileName) -> bool {
+        self.inline_sources_content
+    }
+
+    fn emit_columns(&self, _f: &FileName) -> bool {
+        self.emit_columns
+    }
+
+    fn skip(&self, f: &FileName) -> bool {
+        match f {
+            FileName::Internal(..) => true,
+            FileName::Custom(s) => s.starts_with('<'),
+            _ => false,
+        }
+    }
+
+    fn ignore_list(&self, f: &FileName) -> bool {
+        let Some(ignore_list) = self.ignore_list else {
+            return false;
+        };
+        match f {
+            FileName::Real(path) => ignore_list.is_match(path.to_string_lossy().as_ref()),
+            FileName::Custom(s) => ignore_list.is_match(s),
+            _ => true,
+        }
+    }
+}
+
+/// A minified program, ready to be printed
+struct Optimized<'a> {
+    program: Program,
+    /// The comments left after minification
+    comments: SingleThreadedComments,
+    settings: Settings<'a>,
+    /// Original names of the identifiers, for the source map
+    source_map_names: FxHashMap<BytePos, Atom>,
+    /// License comments among `comments`
+    licenses: Vec<String>,
+    stats: Option<MinifyStats>,
+}
+
+/// Parses and minifies `fm`, everything [`minify`] does but printing the program.
+fn optimize<'a>(
+    compiler: &Compiler,
+    fm: &Arc<SourceFile>,
+    handler: &Handler,
+    opts: &'a JsMinifyOptions,
+    keep_quoted: KeepQuoted,
+    cache: Option<&MangleCache>,
+    measure: bool,
+) -> Result<Optimized<'a>, Error> {
+    let comments = SingleThreadedComments::default();
+    // The syntax `Compiler::minify` parses with
+    let syntax = Syntax::Es(EsSyntax {
+        jsx: true,
+        decorators: true,
+        decorators_before_export: true,
+        import_attributes: true,
+        ..Default::default()
+    });
+    let mut program = compiler
+        .parse_js(
+            fm.clone(),
+            handler,
+            opts.ecma.clone().into(),
+            syntax,
+            opts.module,
+            Some(&comments),
+        )
+        .context("failed to parse input file")
+        .status(Status::Syntax)?;
+
+    let mut settings = settings(compiler, opts, &program)?;
+    let source_map_names = if settings.source_map.enabled() {
+        let mut collector = IdentCollector {
+            names: Default::default(),
+        };
+        program.visit_with(&mut collector);
+        collector.names
+    } else {
+        Default::default()
+    };
+
+    let mut stats = measure.then(|| MinifyStats {
+        input_size: fm.src.len(),
+        ..Default::default()
+    });
+    program = compiler.run_transform(handler, false, || -> Result<_, Error> {
+        let mut program = optimize_with_hooks(
+            compiler,
+            program,
+            &comments,
+            &mut settings.minify,
+            keep_quoted,
+            cache,
+            stats.as_mut(),
+        )?;
+        if settings.minify.mangle.is_none() {
+            program.visit_mut_with(&mut hygiene())
+        }
+        program.mutate(&mut fixer(Some(&comments as &dyn Comments)));
+        Ok(program)
+    })?;
+
+    swc_compiler_base::minify_file_comments(
+        &comments,
+        settings.preserve_comments.clone(),
+        opts.format.preserve_annotations,
+    );
+    // Printing takes the comments out of `comments`
+    let licenses = licenses(&comments);
+    Ok(Optimized {
+        program,
+        comments,
+        settings,
+        source_map_names,
+        licenses,
+        stats,
+    })
+}
+
+/// Resolves `program` and runs `swc_ecma_minifier::optimize` on it, with the hooks the
+/// library adds around it.
+///
+/// Before, cached top-level variables get their names and the rest of `cache` is reserved;
+/// afterwards properties are mangled and the names the mangler picked are added to `cache`.
+/// The cache stays locked in between, so other minifications with it cannot pick the same
+/// names.
+fn optimize_with_hooks(
+    compiler: &Compiler,
+    mut program: Program,
+    comments: &SingleThreadedComments,
+    options: &mut MinifyOptions,
+    keep_quoted: KeepQuoted,
+    cache: Option<&MangleCache>,
+    mut stats: Option<&mut MinifyStats>,
+) -> Result<Program, Error> {
+    let unresolved_mark = Mark::new();
+    let top_level_mark = Mark::new();
+    program.mutate(&mut paren_remover(Some(comments)));
+    program.mutate(&mut resolver(unresolved_mark, top_level_mark, false));
+
+    let fresh = MangleCache::new();
+    let mut names = cache.unwrap_or(&fresh).lock();
+    let top_level = SyntaxContext::empty().apply_mark(top_level_mark);
+    let unresolved = SyntaxContext::empty().apply_mark(unresolved_mark);
+    let props = options
+        .mangle
+        .as_mut()
+        .and_then(|mangle| mangle.props.take());
+
+    let mut mangled = 0;
+    let mut caches_names = false;
+    if let Some(mangle) = &mut options.mangle {
+        if mangle.top_level == Some(true) {
+            mangled += names.apply(&mut program, mangle, top_level, unresolved);
+            caches_names = true;
+        }
+    }
+
+    // The mangler only tells a name cache which names it picked, so one counts them. When it
+    // asks the cache for names also splits the time of `optimize` between compressing and
+    // mangling, see `MinifyStats`
+    let renamed = Arc::new(Renamed::default());
+    let start = Instant::now();
+    let mut program = swc_ecma_minifier::optimize(
+        program,
+        compiler.cm.clone(),
+        Some(comments),
+        None,
+        options,
+        &ExtraOptions {
+            unresolved_mark,
+            top_level_mark,
+            mangle_name_cache: (caches_names || stats.is_some()).then(|| renamed.clone() as _),
+        },
+    );
+    if let Some(stats) = &mut stats {
+        let end = Instant::now();
+        let mangling_since = match options.compress {
+            Some(_) => renamed.mangling_since().unwrap_or(end),
+            None => start,
+        };
+        stats.compress += mangling_since - start;
+        stats.mangle += end - mangling_since;
+    }
+
+    if let Some(props) = &props {
+        let start = Instant::now();
+        mangled += mangle_properties(
+            &mut program,
+            props,
+            keep_quoted,
+            unresolved,
+            &mut names.props,
+        )?;
+        if let Some(stats) = &mut stats {
+            stats.mangle += start.elapsed();
+        }
+    }
+    let renamed = renamed.names();
+    if let Some(stats) = stats {
+        stats.mangled = mangled
+            + renamed
+                .iter()
+                .filter(|((name, _), new)| name != *new)
+                .count();
+    }
+    if caches_names {
+        names.update(&renamed, top_level);
+    }
+    Ok(program)
+}
+
+/// What `Compiler::minify` makes of its [`JsMinifyOptions`]
+struct Settings<'a> {
+    minify: MinifyOptions,
+    source_map: SourceMapsConfig,
+    /// The input source map
+    orig: Option<swc_compiler::sourcemap::SourceMap>,
+    source_map_url: Option<&'a str>,
+    preserve_comments: BoolOr<JsMinifyCommentOption>,
+    codegen: swc_ecma_codegen::Config,
+}
+
+/// Translates `opts` for the parsed `program` like `Compiler::minify`.
+///
+/// Copied from `Compiler::minify` in `swc` 60.0.0 (`src/lib.rs`), which does not expose it,
+/// and to be compared with it whenever `swc` is upgraded.
+fn settings<'a>(
+    compiler: &Compiler,
+    opts: &'a JsMinifyOptions,
+    program: &Program,
+) -> Result<Settings<'a>, Error> {
+    let (source_map, orig, source_map_url) = opts
+        .source_map
+        .as_ref()
+        .map(|obj| -> Result<_, Error> {
+            let orig = obj.content.as_ref().map(|s| s.to_sourcemap()).transpose()?;
+            Ok((SourceMapsConfig::Bool(true), orig, obj.url.as_deref()))
+        })
+        .unwrap_as_option(|v| {
+            Some(Ok(match v {
+                Some(true) => (SourceMapsConfig::Bool(true), None, None),
+                _ => (SourceMapsConfig::Bool(false), None, None),
+            }))
+        })
+        .unwrap()?;
+
+    let mut minify = MinifyOptions {
+        compress: opts
+            .compress
+            .clone()
+            .unwrap_as_option(|default| match default {
+                Some(true) | None => Some(Default::default()),
+                _ => None,
+            })
+            .map(|v| v.into_config(compiler.cm.clone())),
+        mangle: opts
+            .mangle
+            .clone()
+            .unwrap_as_option(|default| match default {
+                Some(true) | None => Some(Default::default()),
+                _ => None,
+            }),
+        ..Default::default()
+    };
+    if opts.keep_fnames {
+        if let Some(opts) = &mut minify.compress {
+            opts.keep_fnames = true;
+        }
+        if let Some(opts) = &mut minify.mangle {
+            opts.keep_fn_names = true;
+        }
+    }
+    // Top-level names are minified in modules or if asked for
+    if opts.toplevel == Some(true) || program.is_module() {
+        if let Some(opts) = &mut minify.compress {
+            if opts.top_level.is_none() {
+                opts.top_level = Some(TopLevelOptions { functions: true });
+            }
+        }
+        if let Some(opts) = &mut minify.mangle {
+            if opts.top_level.is_none() {
+                opts.top_level = Some(true);
+            }
+        }
+    }
+
+    let preserve_comments = opts
+        .format
+        .comments
+        .clone()
+        .into_inner()
+        .unwrap_or(BoolOr::Data(JsMinifyCommentOption::PreserveSomeComments));
+    let target: EsVersion = opts.ecma.clone().into();
+    let reduce_escaped_newline = minify
+        .compress
+        .as_ref()
+        .is_none_or(|compress| compress.experimental.reduce_escaped_newline);
+    let codegen = swc_ecma_codegen::Config::default()
+        .with_target(target)
+        .with_minify(true)
+        .with_ascii_only(opts.format.ascii_only)
+        .with_emit_assert_for_import_attributes(opts.format.emit_assert_for_import_attributes)
+        .with_inline_script(opts.format.inline_script)
+        .with_reduce_escaped_newline(reduce_escaped_newline);
+
+    Ok(Settings {
+        minify,
+        source_map,
+        orig,
+        source_map_url,
+        preserve_comments,
+        codegen,
+    })
+}
@@ -0,0 +1,671 @@
+use crate::status::{Status, StatusExt};
+use anyhow::{anyhow, Error};
+use std::collections::BTreeMap;
+use std::path::Path;
+use swc_common::comments::SingleThreadedComments;
+use swc_common::sync::Lrc;
+use swc_common::{Mark, SourceMap, DUMMY_SP};
+use swc_ecma_ast::*;
+use swc_ecma_preset_env::Feature;
+use swc_ecma_transforms_base::helpers::inject_helpers;
+use swc_ecma_transforms_module::import_analysis::import_analyzer;
+use swc_ecma_transforms_module::path::Resolver;
+use swc_ecma_transforms_module::util::{Config as ModuleConfig, ImportInterop};
+use swc_ecma_transforms_module::{amd, common_js, system_js, umd};
+
+/// Module system the output is written for
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ModuleFormat {
+    /// Keeps `import` and `export` as they are
+    #[default]
+    Es,
+    /// `require()` and `exports`, as used by Node.js
+    CommonJs,
+    /// `define()`, as used by RequireJS
+    Amd,
+    /// Works as CommonJS, AMD or a browser global, whichever is available
+    Umd,
+    /// `System.register()`, as used by SystemJS
+    SystemJs,
+    /// A self-executing function that reads its imports from, and writes its exports to,
+    /// global variables
+    Iife,
+}
+
+/// How imports of CommonJS modules are interpreted
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ModuleInterop {
+    /// Babel-compatible interop: `__esModule` marks transpiled modules, anything else is
+    /// the default export
+    #[default]
+    Swc,
+    /// Node.js semantics: `module.exports` is always the default export
+    Node,
+    /// No interop helpers; imports are used as they are
+    None,
+}
+
+impl From<ModuleInterop> for ImportInterop {
+    fn from(interop: ModuleInterop) -> Self {
+        match interop {
+            ModuleInterop::Swc => ImportInterop::Swc,
+            ModuleInterop::Node => ImportInterop::Node,
+            ModuleInterop::None => ImportInterop::None,
+        }
+    }
+}
+
+/// Settings for converting ES modules to another module system
+#[derive(Clone, Debug)]
+pub struct ModuleOptions {
+    /// Module system the output is written for
+    pub format: ModuleFormat,
+    /// How imports of CommonJS modules are interpreted
+    pub import_interop: ModuleInterop,
+    /// Omits the `__esModule` marker from the exports
+    pub strict: bool,
+    /// Adds a `"use strict"` directive to the output
+    pub strict_mode: bool,
+    /// Global variable that receives the exports of IIFE and UMD output, e.g. `MyLib` or
+    /// `MyLib.utils`. `None` derives it from the file name. IIFE output creates the
+    /// namespaces of a dotted name, like rollup does.
+    pub global_name: Option<String>,
+    /// Global variables that IIFE and UMD output read imports from, keyed by module
+    /// specifier, e.g. `react` => `React`. Unlisted imports are read from a global derived
+    /// from the specifier.
+    pub globals: BTreeMap<String, String>,
+}
+
+impl Default for ModuleOptions {
+    fn default() -> Self {
+        ModuleOptions {
+            format: ModuleFormat::Es,
+            import_interop: ModuleInterop::Swc,
+            strict: false,
+            strict_mode: true,
+            global_name: None,
+            globals: BTreeMap::new(),
+        }
+    }
+}
+
+impl ModuleOptions {
+    /// Whether swc's UMD output of a compile has to be rewritten to apply these options.
+    fn needs_wrapper_rewrite(&self) -> bool {
+        match self.format {
+            ModuleFormat::Iife => true,
+            ModuleFormat::Umd => self.global_name.is_some() || !self.globals.is_empty(),
+            _ => false,
+        }
+    }
+
+    /// Rejects global names that are not dotted paths of identifiers.
+    fn validate(&self) -> Result<(), Error> {
+        let paths = self.global_name.iter().chain(self.globals.values());
+        for path in paths {
+            if path
+                .split('.')
+                .any(|part| Ident::verify_symbol(part).is_err())
+            {
+                return Err(anyhow!("invalid global variable `{path}`"))
+                    .status(Status::InvalidConfig);
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Module system the output is written for
+///
+/// * `Es` - Keeps `import` and `export` as they are
+/// * `CommonJs` - `require()` and `exports`, as used by Node.js
+/// * `Amd` - `define()`, as used by RequireJS
+/// * `Umd` - Works as CommonJS, AMD or a browser global, whichever is available
+/// * `SystemJs` - `System.register()`, as used by SystemJS
+/// * `Iife` - A self-executing function that reads its imports from, and writes its
+///   exports to, global variables
+#[allow(non_camel_case_types)]
+#[repr(C)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum swc_module_format {
+    Es,
+    CommonJs,
+    Amd,
+    Umd,
+    SystemJs,
+    Iife,
+}
+
+impl From<swc_module_format> for ModuleFormat {
+    fn from(format: swc_module_format) -> Self {
+        match format {
+            swc_module_format::Es => ModuleFormat::Es,
+            swc_module_format::CommonJs => ModuleFormat::CommonJs,
+            swc_module_format::Amd => ModuleFormat::Amd,
+            swc_module_format::Umd => ModuleFormat::Umd,
+            swc_module_format::SystemJs => ModuleFormat::SystemJs,
+            swc_module_format::Iife => ModuleFormat::Iife,
+        }
+    }
+}
+
+/// How imports of CommonJS modules are interpreted
+///
+/// * `Swc` - Babel-compatible interop: `__esModule` marks transpiled modules, anything else
+///   is the default export
+/// * `Node` - Node.js semantics: `module.exports` is always the default export
+/// * `None` - No interop helpers; imports are used as they are
+#[allow(non_camel_case_types)]
+#[repr(C)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum swc_module_interop {
+    Swc,
+    Node,
+    None,
+}
+
+impl From<swc_module_interop> for ModuleInterop {
+    fn from(interop: swc_module_interop) -> Self {
+        match interop {
+            swc_module_interop::Swc => ModuleInterop::Swc,
+            swc_module_interop::Node => ModuleInterop::Node,
+            swc_module_interop::None => ModuleInterop::None,
+        }
+    }
+}
+
+/// Converts a resolved program to the module system selected by `options`.
+///
+/// Runs the same passes as swc's own module pipeline: the import analyzer, the injection
+/// of the helpers used by this and earlier passes and the module transform. Scripts are
+/// treated as modules without imports or exports. `caniuse` tells which syntax the output
+/// may use.
+///
+/// Must run within `HELPERS` and before hygiene and the fixer.
+pub(crate) fn transform(
+    program: &mut Program,
+    file_name: &str,
+    cm: Lrc<SourceMap>,
+    comments: &SingleThreadedComments,
+    unresolved_mark: Mark,
+    caniuse: impl Fn(Feature) -> bool,
+    options: &ModuleOptions,
+) -> Result<(), Error> {
+    if options.format == ModuleFormat::Es {
+        inject_helpers(unresolved_mark).process(program);
+        return Ok(());
+    }
+    options.validate()?;
+
+    if let Program::Script(script) = program {
+        let script = std::mem::take(script);
+        *program = Program::Module(Module {
+            span: script.span,
+            body: script.body.into_iter().map(ModuleItem::Stmt).collect(),
+            shebang: script.shebang,
+        });
+    }
+
+    let import_interop = options.import_interop.into();
+    let config = ModuleConfig {
+        strict: options.strict,
+        strict_mode: options.strict_mode,
+        import_interop: Some(import_interop),
+        ..Default::default()
+    };
+    let block_scoping = caniuse(Feature::BlockScoping);
+    let arrow = caniuse(Feature::ArrowFunctions);
+
+    import_analyzer(import_interop, false).process(program);
+    inject_helpers(unresolved_mark).process(program);
+    match options.format {
+        ModuleFormat::Es => {}
+        ModuleFormat::CommonJs => common_js(
+            Resolver::Default,
+            unresolved_mark,
+            config,
+            common_js::FeatureFlag {
+                support_block_scoping: block_scoping,
+                support_arrow: arrow,
+            },
+        )
+        .process(program),
+        ModuleFormat::Amd => amd(
+            Resolver::Default,
+            unresolved_mark,
+            amd::Config {
+                module_id: None,
+                config,
+            },
+            amd::FeatureFlag {
+                support_block_scoping: block_scoping,
+                support_arrow: arrow,
+            },
+            Some(comments),
+        )
+        .process(program),
+        ModuleFormat::Umd | ModuleFormat::Iife => umd(
+            cm,
+            Resolver::Default,
+            unresolved_mark,
+            umd::Config {
+                globals: Default::default(),
+                config,
+            },
+            umd::FeatureFlag {
+                support_block_scoping: block_scoping,
+            },
+        )
+        .process(program),
+        ModuleFormat::SystemJs => system_js(
+            Resolver::Default,
+            unresolved_mark,
+            system_js::Config {
+                allow_top_level_this: false,
+                config,
+            },
+        )
+        .process(program),
+    }
+
+    rewrite_wrapper(program, file_name, options)
+}
+
+/// Splits the `module` section of a JSON config into what swc understands and the
+/// wrapper options this library adds on top.
+///
+/// `"type": "iife"` is compiled as UMD, and `globalName` is removed because swc rejects
+/// unknown keys. `globals` is part of swc's UMD config but ignored by swc itself.
+///
+/// # Returns
+///
+/// The options [`rewrite_wrapper`] has to be run with, or `None` if swc's output is used
+/// as it is
+pub(crate) fn take_wrapper_options(
+    config: &mut serde_json::Value,
+) -> Result<Option<ModuleOptions>, Error> {
+    let Some(module) = config.get_mut("module").and_then(|m| m.as_object_mut()) else {
+        return Ok(None);
+    };
+    let format = match module.get("type").and_then(|t| t.as_str()) {
+        Some("iife") => ModuleFormat::Iife,
+        Some("umd") => ModuleFormat::Umd,
+        _ => return Ok(None),
+    };
+    module.insert("type".into(), "umd".into());
+
+    let global_name = match module.remove("globalName") {
+        None | Some(serde_json::Value::Null) => None,
+        Some(serde_json::Value::String(name)) => Some(name),
+        Some(_) => {
+            return Err(anyhow!("`module.globalName` must be a string"))
+                .status(Status::InvalidConfig)
+        }
+    };
+    let globals = match module.get("globals") {
+        Some(globals) => serde_json::from_value(globals.clone())?,
+        None => BTreeMap::new(),
+    };
+
+    let options = ModuleOptions {
+        format,
+        global_name,
+        globals,
+        ..Default::default()
+    };
+    options.validate()?;
+    Ok(options.needs_wrapper_rewrite().then_some(options))
+}
+
+/// The parts of swc's UMD wrapper that are rewritten
+///
+/// swc emits
+/// `(function(global, factory) { if (cjs) ...; else if (amd) define(deps, factory); else if
+/// (global = ...) factory(browser args); })(this, function(exports, ...) { ... });`,
+/// where the browser branch is `module.exports = factory(...)` for `export =`.
+struct UmdWrapper<'a> {
+    /// The `global` parameter of the adapter
+    global: Ident,
+    /// The AMD dependency list; `exports` is the exports object, anything else an import
+    deps: Vec<String>,
+    /// The statement of the browser branch
+    browser: &'a mut Expr,
+    /// The module body
+    factory: &'a mut Function,
+}
+
+impl<'a> UmdWrapper<'a> {
+    /// Recognizes the UMD wrapper in a statement.
+    fn find(stmt: &'a mut Stmt) -> Option<Self> {
+        let Stmt::Expr(ExprStmt { expr, .. }) = stmt else {
+            return None;
+        };
+        let Expr::Call(call) = expr.unwrap_parens_mut() else {
+            return None;
+        };
+        let Callee::Expr(callee) = &mut call.callee else {
+            return None;
+        };
+        let Expr::Fn(adapter) = callee.unwrap_parens_mut() else {
+            return None;
+        };
+        let [_, factory] = &mut call.args[..] else {
+            return None;
+        };
+        let Expr::Fn(factory) = factory.expr.unwrap_parens_mut() else {
+            return None;
+        };
+
+        let adapter = &mut adapter.function;
+        let global = adapter.params.first()?.pat.as_ident()?.id.clone();
+        let Stmt::If(cjs) = adapter.body.as_mut()?.stmts.first_mut()? else {
+            return None;
+        };
+        let Stmt::If(amd) = &mut **cjs.alt.as_mut()? else {
+            return None;
+        };
+        let Expr::Call(define) = expr_of(&mut amd.cons)? else {
+            return None;
+        };
+        let Expr::Array(deps) = define.args.first()?.expr.unwrap_parens() else {
+            return None;
+        };
+        let deps = deps
+            .elems
+            .iter()
+            .map(|dep| match dep.as_ref()?.expr.unwrap_parens() {
+                Expr::Lit(Lit::Str(s)) => s.value.as_str().map(String::from),
+                _ => None,
+            })
+            .collect::<Option<Vec<_>>>()?;
+        let Stmt::If(browser) = &mut **amd.alt.as_mut()? else {
+            return None;
+        };
+
+        Some(UmdWrapper {
+            global,
+            deps,
+            browser: expr_of(&mut browser.cons)?,
+            factory: &mut factory.function,
+        })
+    }
+
+    /// Whether the module uses `export =`, i.e. the factory returns its exports.
+    fn is_export_assign(&self) -> bool {
+        matches!(self.browser, Expr::Assign(_))
+    }
+
+    /// The arguments the browser branch calls the factory with, in the order of `deps`.
+    fn browser_args(&mut self) -> Option<&mut Vec<ExprOrSpread>> {
+        let call = match &mut *self.browser {
+            Expr::Assign(assign) => assign.right.unwrap_parens_mut(),
+            call => call,
+        };
+        match call {
+            Expr::Call(call) if call.args.len() == self.deps.len() => Some(&mut call.args),
+            _ => None,
+        }
+    }
+}
+
+/// Returns the expression of an expression statement, looking through a block.
+fn expr_of(stmt: &mut Stmt) -> Option<&mut Expr> {
+    match stmt {
+        Stmt::Expr(ExprStmt { expr, .. }) => Some(expr.unwrap_parens_mut()),
+        Stmt::Block(BlockStmt { stmts, .. }) if stmts.len() == 1 => expr_of(&mut stmts[0]),
+        _ => None,
+    }
+}
+
+/// Builds the expression `base.a.b` for the path `a.b`, or `a.b` without a base.
+fn global_path(base: Option<Expr>, path: &str) -> Expr {
+    let mut parts = path.split('.');
+    let mut expr = match base {
+        Some(base) => base,
+        None => Ident::new_no_ctxt(parts.next().unwrap_or_default().into(), DUMMY_SP).into(),
+    };
+    for part in parts {
+        expr = MemberExpr {
+            span: DUMMY_SP,
+            obj: Box::new(expr),
+            prop: MemberProp::Ident(IdentName::new(part.into(), DUMMY_SP)),
+        }
+        .into();
+    }
+    expr
+}
+
+/// Builds `target = value`, where `target` is an identifier or a member expression.
+fn assign(target: Expr, value: Expr) -> Result<Expr, Error> {
+    let left = match target {
+        Expr::Ident(ident) => SimpleAssignTarget::Ident(ident.into()),
+        Expr::Member(member) => SimpleAssignTarget::Member(member),
+        _ => {
+            return Err(anyhow!(
+                "cannot assign to a global that is not a variable or property"
+            ))
+            .status(Status::Internal)
+        }
+    };
+    Ok(AssignExpr {
+        span: DUMMY_SP,
+        op: AssignOp::Assign,
+        left: left.into(),
+        right: Box::new(value),
+    }
+    .into())
+}
+
+/// Derives the global variable of a module from its file name, e.g. `my-lib.ts` => `myLib`.
+///
+/// Follows swc's naming for real paths, but also strips the extension of names that do
+/// not refer to a file on disk.
+fn default_global_name(file_name: &str) -> String {
+    let stem = Path::new(file_name)
+        .file_stem()
+        .map(|stem| stem.to_string_lossy())
+        .unwrap_or_default();
+    let mut name = String::new();
+    for (i, word) in stem
+        .split(|c: char| !c.is_ascii_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .enumerate()
+    {
+        let mut chars = word.chars();
+        if let Some(first) = chars.next() {
+            match i {
+                0 => name.push(first.to_ascii_lowercase()),
+                _ => name.push(first.to_ascii_uppercase()),
+            }
+            name.extend(chars);
+        }
+    }
+    if Ident::verify_symbol(&name).is_err() {
+        name.insert(0, '_');
+    }
+    name
+}
+
+/// Applies the global name and externals to the UMD wrapper, or replaces it by an IIFE.
+///
+/// Works on the output of swc's UMD transform both before and after hygiene and the
+/// fixer, but not on output the minifier has already compressed. Without a global name,
+/// one is derived from `file_name`.
+///
+/// Fails with [`Status::Internal`] if the program does not have the shape swc's UMD
+/// transform gives it.
+pub(crate) fn rewrite_wrapper(
+    program: &mut Program,
+    file_name: &str,
+    options: &ModuleOptions,
+) -> Result<(), Error> {
+    if !matches!(options.format, ModuleFormat::Umd | ModuleFormat::Iife) {
+        return Ok(());
+    }
+    let Program::Module(module) = program else {
+        return Ok(());
+    };
+
+    let unexpected = || {
+        anyhow!(
+            "failed to apply `globalName`/`globals`: unexpected UMD wrapper \
+             (compressing output is not supported with these options, minify it separately)"
+        )
+    };
+    let (mut index, mut wrapper) = module
+        .body
+        .iter_mut()
+        .enumerate()
+        .find_map(|(index, item)| Some((index, UmdWrapper::find(item.as_mut_stmt()?)?)))
+        .ok_or_else(unexpected)
+        .status(Status::Internal)?;
+    let global_name = match &options.global_name {
+        Some(name) => name.clone(),
+        None => default_global_name(file_name),
+    };
+
+    if options.format == ModuleFormat::Umd {
+        let global = Expr::from(wrapper.global.clone());
+        if let Expr::Assign(assign) = &mut *wrapper.browser {
+            // `module.exports = factory(...)` for `export =`
+            if let Expr::Member(member) = global_path(Some(global.clone()), &global_name) {
+                assign.left = SimpleAssignTarget::Member(member).into();
+            }
+        }
+        let deps = wrapper.deps.clone();
+        let args = wrapper
+            .browser_args()
+            .ok_or_else(unexpected)
+            .status(Status::Internal)?;
+        for (arg, dep) in args.iter_mut().zip(&deps) {
+            let expr = match dep.as_str() {
+                "exports" => assign(
+                    global_path(Some(global.clone()), &global_name),
+                    ObjectLit::default().into(),
+                )?,
+                dep => match options.globals.get(dep) {
+                    Some(path) => global_path(Some(global.clone()), path),
+                    None => continue,
+                },
+            };
+            arg.expr = Box::new(expr);
+        }
+        return Ok(());
+    }
+
+    let export_assign = wrapper.is_export_assign();
+    let deps = wrapper.deps.clone();
+    let browser_args = wrapper
+        .browser_args()
+        .ok_or_else(unexpected)
+        .status(Status::Internal)?
+        .clone();
+
+    let mut args = Vec::with_capacity(deps.len());
+    let mut exports = None;
+    for (i, (dep, arg)) in deps.iter().zip(browser_args).enumerate() {
+        let expr = if dep == "exports" {
+            exports = wrapper
+                .factory
+                .params
+                .get(i)
+                .and_then(|p| p.pat.as_ident())
+                .cloned();
+            ObjectLit::default().into()
+        } else if let Some(path) = options.globals.get(dep) {
+            global_path(None, path)
+        } else {
+            // swc's guess, `global.name` or `global["name"]`
+            match *arg.expr {
+                Expr::Member(MemberExpr {
+                    prop: MemberProp::Ident(prop),
+                    ..
+                }) => Ident::new_no_ctxt(prop.sym, DUMMY_SP).into(),
+                Expr::Member(member) => MemberExpr {
+                    obj: Box::new(Ident::new_no_ctxt("globalThis".into(), DUMMY_SP).into()),
+                    ..member
+                }
+                .into(),
+                expr => expr,
+            }
+        };
+        args.push(expr.into());
+    }
+
+    let mut factory = std::mem::take(wrapper.factory);
+    if let (Some(exports), Some(body)) = (&exports, factory.body.as_mut()) {
+        body.stmts.push(
+            ReturnStmt {
+                span: DUMMY_SP,
+                arg: Some(Box::new(exports.id.clone().into())),
+            }
+            .into(),
+        );
+    }
+    let call: Expr = CallExpr {
+        callee: Callee::Expr(Box::new(
+            ParenExpr {
+                span: DUMMY_SP,
+                expr: Box::new(
+                    FnExpr {
+                        ident: None,
+                        function: Box::new(factory),
+                    }
+                    .into(),
+                ),
+            }
+            .into(),
+        )),
+        args,
+        ..Default::default()
+    }
+    .into();
+
+    let stmt: Stmt = if exports.is_none() && !export_assign {
+        ExprStmt {
+            span: DUMMY_SP,
+            expr: Box::new(call),
+        }
+        .into()
+    } else if global_name.contains('.') {
+        // Like rollup, create the namespace first: `this.a = this.a || {}; this.a.b = ...`
+        let this = || Expr::from(ThisExpr { span: DUMMY_SP });
+        let parts = global_name.split('.').collect::<Vec<_>>();
+        let namespaces = (1..parts.len()).map(|len| {
+            let path = global_path(Some(this()), &parts[..len].join("."));
+            let existing = BinExpr {
+                span: DUMMY_SP,
+                op: BinaryOp::LogicalOr,
+                left: Box::new(path.clone()),
+                right: Box::new(ObjectLit::default().into()),
+            };
+            Ok(ModuleItem::from(Stmt::from(ExprStmt {
+                span: DUMMY_SP,
+                expr: Box::new(assign(path, existing.into())?),
+            })))
+        });
+        let namespaces = namespaces.collect::<Result<Vec<_>, Error>>()?;
+        let count = namespaces.len();
+        module.body.splice(index..index, namespaces);
+        index += count;
+        ExprStmt {
+            span: DUMMY_SP,
+            expr: Box::new(assign(global_path(Some(this()), &global_name), call)?),
+        }
+        .into()
+    } else {
+        VarDecl {
+            kind: VarDeclKind::Var,
+            decls: vec![VarDeclarator {
+                span: DUMMY_SP,
+                name: Pat::Ident(Ident::new_no_ctxt(global_name.into(), DUMMY_SP).into()),
+                init: Some(Box::new(call)),
+                definite: false,
+            }],
+            ..Default::default()
+        }
+        .into()
+    };
+    module.body[index] = stmt.into();
+    Ok(())
+}
@@ -0,0 +1,591 @@
+use crate::env::{swc_polyfill_mode, EnvOptions};
+use crate::ffi::catch_panic;
+use crate::module::{swc_module_format, swc_module_interop, ModuleOptions};
+use crate::source_map::{swc_source_map_mode, SourceMapMode};
+use crate::syntax::{swc_syntax, InputSyntax};
+use std::{ffi::CStr, os::raw::c_char};
+use swc_ecma_ast::EsVersion;
+use swc_ecma_transforms_react::Runtime;
+
+/// Settings for the transpilation pipeline behind `transpile_tsx_to_js`
+///
+/// The defaults reproduce the behaviour of `transpile`: ES2015 output, decorators enabled,
+/// the classic `React.createElement` JSX runtime and `\n` line endings.
+#[derive(Clone, Debug)]
+pub struct TranspileOptions {
+    /// ECMAScript version the code generator targets
+    pub target: EsVersion,
+    /// How JSX elements are compiled
+    pub jsx_runtime: JsxRuntime,
+    /// Function called for JSX elements by the classic runtime
+    pub jsx_pragma: String,
+    /// Component used for JSX fragments by the classic runtime
+    pub jsx_pragma_frag: String,
+    /// Module the automatic runtime imports from, with `/jsx-runtime` appended
+    pub jsx_import_source: String,
+    /// Emits `jsxDEV` calls and `__source`/`__self` props for React's development builds
+    pub jsx_development: bool,
+    /// Rejects namespaced JSX names such as `<a:b />`
+    pub jsx_throw_if_namespace: bool,
+    /// Whether decorators are accepted by the parser
+    pub decorators: bool,
+    /// Language the input is parsed as; `Auto` detects it from the file extension
+    pub syntax: InputSyntax,
+    /// Forces JSX parsing on or off for TypeScript input; `None` enables it for TSX only
+    pub tsx: Option<bool>,
+    /// Line terminator written by the code generator
+    pub newline: String,
+    /// Escapes all non-ASCII characters in the output
+    pub ascii_only: bool,
+    /// Emits compact code without unnecessary whitespace
+    pub minify: bool,
+    /// Keeps comments from the input in the output
+    pub comments: bool,
+    /// Whether and how a source map is generated
+    pub source_maps: SourceMapMode,
+    /// Source map of the input as JSON, composed into the generated map
+    pub input_source_map: Option<String>,
+    /// Module system the output is written for
+    pub module: ModuleOptions,
+    /// Target environments the syntax is lowered and polyfilled for; `None` only changes
+    /// how the code generator writes the output, see `target`
+    pub env: Option<EnvOptions>,
+}
+
+impl Default for TranspileOptions {
+    fn default() -> Self {
+        TranspileOptions {
+            target: EsVersion::Es2015,
+            jsx_runtime: JsxRuntime::Classic,
+            jsx_pragma: String::from("React.createElement"),
+            jsx_pragma_frag: String::from("React.Fragment"),
+            jsx_import_source: String::from("react"),
+            jsx_development: false,
+            jsx_throw_if_namespace: true,
+            decorators: true,
+            syntax: InputSyntax::Auto,
+            tsx: None,
+            newline: String::from("\n"),
+            ascii_only: false,
+            minify: false,
+            comments: true,
+            source_maps: SourceMapMode::None,
+            input_source_map: None,
+            module: ModuleOptions::default(),
+            env: None,
+        }
+    }
+}
+
+/// How JSX elements are compiled
+///
+/// A file can override the runtime, pragmas and import source with `@jsxRuntime`, `@jsx`,
+/// `@jsxFrag` and `@jsxImportSource` comments.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum JsxRuntime {
+    /// Calls the configured pragma, e.g. `React.createElement`
+    #[default]
+    Classic,
+    /// Imports `jsx`/`jsxs` from `<import source>/jsx-runtime`
+    Automatic,
+    /// Leaves JSX untouched
+    Preserve,
+}
+
+impl From<JsxRuntime> for Runtime {
+    fn from(runtime: JsxRuntime) -> Self {
+        match runtime {
+            JsxRuntime::Classic => Runtime::Classic,
+            JsxRuntime::Automatic => Runtime::Automatic,
+            JsxRuntime::Preserve => Runtime::Preserve,
+        }
+    }
+}
+
+/// How JSX elements are compiled
+///
+/// * `Classic` - Calls the JSX pragma, `React.createElement` by default
+/// * `Automatic` - Imports `jsx`/`jsxs` from `<import source>/jsx-runtime`
+/// * `Preserve` - Leaves JSX untouched
+#[allow(non_camel_case_types)]
+#[repr(C)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum swc_jsx_runtime {
+    Classic,
+    Automatic,
+    Preserve,
+}
+
+impl From<swc_jsx_runtime> for JsxRuntime {
+    fn from(runtime: swc_jsx_runtime) -> Self {
+        match runtime {
+            swc_jsx_runtime::Classic => JsxRuntime::Classic,
+            swc_jsx_runtime::Automatic => JsxRuntime::Automatic,
+            swc_jsx_runtime::Preserve => JsxRuntime::Preserve,
+        }
+    }
+}
+
+/// ECMAScript version targeted by the code generator
+#[allow(non_camel_case_types)]
+#[repr(C)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum swc_es_version {
+    Es3,
+    Es5,
+    Es2015,
+    Es2016,
+    Es2017,
+    Es2018,
+    Es2019,
+    Es2020,
+    Es2021,
+    Es2022,
+    Es2023,
+    Es2024,
+    EsNext,
+}
+
+impl From<swc_es_version> for EsVersion {
+    fn from(version: swc_es_version) -> Self {
+        match version {
+            swc_es_version::Es3 => EsVersion::Es3,
+            swc_es_version::Es5 => EsVersion::Es5,
+            swc_es_version::Es2015 => EsVersion::Es2015,
+            swc_es_version::Es2016 => EsVersion::Es2016,
+            swc_es_version::Es2017 => EsVersion::Es2017,
+            swc_es_version::Es2018 => EsVersion::Es2018,
+            swc_es_version::Es2019 => EsVersion::Es2019,
+            swc_es_version::Es2020 => EsVersion::Es2020,
+            swc_es_version::Es2021 => EsVersion::Es2021,
+            swc_es_version::Es2022 => EsVersion::Es2022,
+            swc_es_version::Es2023 => EsVersion::Es2023,
+            swc_es_version::Es2024 => EsVersion::Es2024,
+            swc_es_version::EsNext => EsVersion::EsNext,
+        }
+    }
+}
+
+/// Opaque handle holding the options for `transpile_with_options`
+///
+/// Created with `swc_transpile_options_new`, configured through the
+/// `swc_transpile_options_set_*` functions and released with `swc_transpile_options_free`.
+#[allow(non_camel_case_types)]
+pub struct swc_transpile_options {
+    pub(crate) inner: TranspileOptions,
+}
+
+/// Runs `f` on the options behind `options`, ignoring null handles.
+fn with_options(options: *mut swc_transpile_options, f: impl FnOnce(&mut TranspileOptions)) {
+    let _ = catch_panic(|| {
+        if let Some(options) = unsafe { options.as_mut() } {
+            f(&mut options.inner);
+        }
+        Ok(())
+    });
+}
+
+/// Converts an optional C string, ignoring null pointers and invalid UTF-8.
+pub(crate) fn optional_str(s: *const c_char) -> Option<String> {
+    if s.is_null() {
+        return None;
+    }
+    unsafe { CStr::from_ptr(s) }.to_str().ok().map(String::from)
+}
+
+/// Creates a transpile options handle with the default settings.
+///
+/// The defaults match `transpile`: ES2015 output, decorators enabled, the classic
+/// `React.createElement`/`React.Fragment` JSX pragmas, the syntax detected from the file
+/// extension, `\n` newlines, comments preserved, no minification and no source map.
+///
+/// # Returns
+///
+/// Pointer to a new options handle that must be released with `swc_transpile_options_free()`
+///
+/// # Examples
+///
+/// ```c
+/// swc_transpile_options* options = swc_transpile_options_new();
+/// swc_transpile_options_set_target(options, swc_es_version::Es2020);
+/// swc_transpile_options_set_jsx_pragma(options, "h");
+/// char* js = transpile_with_options("app.tsx", code, options);
+/// swc_transpile_options_free(options);
+/// ```
+#[no_mangle]
+pub extern "C" fn swc_transpile_options_new() -> *mut swc_transpile_options {
+    Box::into_raw(Box::new(swc_transpile_options {
+        inner: TranspileOptions::default(),
+    }))
+}
+
+/// Frees a transpile options handle.
+///
+/// Passing a null pointer is a no-op.
+///
+/// # Safety
+///
+/// The pointer must have been returned by `swc_transpile_options_new()` and must not be
+/// used after this call.
+#[no_mangle]
+pub extern "C" fn swc_transpile_options_free(options: *mut swc_transpile_options) {
+    if !options.is_null() {
+        let _ = catch_panic(|| {
+            let _ = unsafe { Box::from_raw(options) };
+            Ok(())
+        });
+    }
+}
+
+/// Sets the ECMAScript version the code generator targets.
+#[no_mangle]
+pub extern "C" fn swc_transpile_options_set_target(
+    options: *mut swc_transpile_options,
+    target: swc_es_version,
+) {
+    with_options(options, |o| o.target = target.into());
+}
+
+/// Sets the function called for JSX elements, e.g. `h` or `React.createElement`.
+///
+/// A null pointer or invalid UTF-8 leaves the current value unchanged.
+#[no_mangle]
+pub extern "C" fn swc_transpile_options_set_jsx_pragma(
+    options: *mut swc_transpile_options,
+    pragma: *const c_char,
+) {
+    if let Some(pragma) = optional_str(pragma) {
+        with_options(options, |o| o.jsx_pragma = pragma);
+    }
+}
+
+/// Sets the component used for JSX fragments, e.g. `Fragment` or `React.Fragment`.
+///
+/// A null pointer or invalid UTF-8 leaves the current value unchanged.
+#[no_mangle]
+pub extern "C" fn swc_transpile_options_set_jsx_pragma_frag(
+    options: *mut swc_transpile_options,
+    pragma_frag: *const c_char,
+) {
+    if let Some(pragma_frag) = optional_str(pragma_frag) {
+        with_options(options, |o| o.jsx_pragma_frag = pragma_frag);
+    }
+}
+
+/// Sets how JSX elements are compiled.
+#[no_mangle]
+pub extern "C" fn swc_transpile_options_set_jsx_runtime(
+    options: *mut swc_transpile_options,
+    runtime: swc_jsx_runtime,
+) {
+    with_options(options, |o| o.jsx_runtime = runtime.into());
+}
+
+/// Sets the module the automatic runtime imports from, e.g. `preact`.
+///
+/// `/jsx-runtime` (or `/jsx-dev-runtime` in development mode) is appended to it.
+/// A null pointer or invalid UTF-8 leaves the current value unchanged.
+#[no_mangle]
+pub extern "C" fn swc_transpile_options_set_jsx_import_source(
+    options: *mut swc_transpile_options,
+    import_source: *const c_char,
+) {
+    if let Some(import_source) = optional_str(import_source) {
+        with_options(options, |o| o.jsx_import_source = import_source);
+    }
+}
+
+/// Enables or disables JSX development mode.
+///
+/// In development mode the automatic runtime calls `jsxDEV`, and elements get `__source`
+/// and `__self` props pointing at their location in the input.
+#[no_mangle]
+pub extern "C" fn swc_transpile_options_set_jsx_development(
+    options: *mut swc_transpile_options,
+    development: bool,
+) {
+    with_options(options, |o| o.jsx_development = development);
+}
+
+/// Sets whether namespaced JSX names such as `<a:b />` are rejected.
+#[no_mangle]
+pub extern "C" fn swc_transpile_options_set_jsx_throw_if_namespace(
+    options: *mut swc_transpile_options,
+    throw_if_namespace: bool,
+) {
+    with_options(options, |o| o.jsx_throw_if_namespace = throw_if_namespace);
+}
+
+/// Enables or disables decorator syntax in the parser.
+#[no_mangle]
+pub extern "C" fn swc_transpile_options_set_decorators(
+    options: *mut swc_transpile_options,
+    decorators: bool,
+) {
+    with_options(options, |o| o.decorators = decorators);
+}
+
+/// Sets the language the input is parsed as.
+///
+/// `swc_syntax::Auto`, the default, detects it from the file extension of the input's name.
+/// Inputs without a meaningful name, such as code from an editor buffer or a network request,
+/// should set the syntax explicitly.
+#[no_mangle]
+pub extern "C" fn swc_transpile_options_set_syntax(
+    options: *mut swc_transpile_options,
+    syntax: swc_syntax,
+) {
+    with_options(options, |o| o.syntax = syntax.into());
+}
+
+/// Forces JSX parsing on or off for TypeScript input, regardless of the file extension.
+#[no_mangle]
+pub extern "C" fn swc_transpile_options_set_tsx(options: *mut swc_transpile_options, tsx: bool) {
+    with_options(options, |o| o.tsx = Some(tsx));
+}
+
+/// Sets the line terminator written by the code generator, e.g. `"\r\n"`.
+///
+/// A null pointer or invalid UTF-8 leaves the current value unchanged.
+#[no_mangle]
+pub extern "C" fn swc_transpile_options_set_newline(
+    options: *mut swc_transpile_options,
+    newline: *const c_char,
+) {
+    if let Some(newline) = optional_str(newline) {
+        with_options(options, |o| o.newline = newline);
+    }
+}
+
+/// Escapes all non-ASCII characters in the generated code.
+#[no_mangle]
+pub extern "C" fn swc_transpile_options_set_ascii_only(
+    options: *mut swc_transpile_options,
+    ascii_only: bool,
+) {
+    with_options(options, |o| o.ascii_only = ascii_only);
+}
+
+/// Emits compact code without unnecessary whitespace.
+///
+/// This only affects code generation; no compression or mangling is performed.
+#[no_mangle]
+pub extern "C" fn swc_transpile_options_set_minify(
+    options: *mut swc_transpile_options,
+    minify: bool,
+) {
+    with_options(options, |o| o.minify = minify);
+}
+
+/// Keeps or drops comments from the input in the generated code.
+#[no_mangle]
+pub extern "C" fn swc_transpile_options_set_comments(
+    options: *mut swc_transpile_options,
+    comments: bool,
+) {
+    with_options(options, |o| o.comments = comments);
+}
+
+/// Selects whether and how a source map is generated.
+///
+/// Separate maps are returned in the `map` field of `swc_result`; the plain `char*`
+/// entry points can only make use of inline maps.
+#[no_mangle]
+pub extern "C" fn swc_transpile_options_set_source_maps(
+    options: *mut swc_transpile_options,
+    mode: swc_source_map_mode,
+) {
+    with_options(options, |o| o.source_maps = mode.into());
+}
+
+/// Sets the source map of the input, e.g. when the code was produced by an earlier build step.
+///
+/// The generated source map is composed with it, so it points back to the original sources
+/// instead of the intermediate code. A null pointer removes a previously set map.
+#[no_mangle]
+pub extern "C" fn swc_transpile_options_set_input_source_map(
+    options: *mut swc_transpile_options,
+    map_json: *const c_char,
+) {
+    let map_json = optional_str(map_json);
+    with_options(options, |o| o.input_source_map = map_json);
+}
+
+/// Sets the module system the output is written for.
+///
+/// Anything but `swc_module_format::Es` converts `import` and `export`, inlining the
+/// interop helpers the output needs.
+#[no_mangle]
+pub extern "C" fn swc_transpile_options_set_module_format(
+    options: *mut swc_transpile_options,
+    format: swc_module_format,
+) {
+    with_options(options, |o| o.module.format = format.into());
+}
+
+/// Sets how imports of CommonJS modules are interpreted by non-ES module formats.
+#[no_mangle]
+pub extern "C" fn swc_transpile_options_set_module_interop(
+    options: *mut swc_transpile_options,
+    interop: swc_module_interop,
+) {
+    with_options(options, |o| o.module.import_interop = interop.into());
+}
+
+/// Omits the `__esModule` marker from the exports of non-ES module formats.
+#[no_mangle]
+pub extern "C" fn swc_transpile_options_set_module_strict(
+    options: *mut swc_transpile_options,
+    strict: bool,
+) {
+    with_options(options, |o| o.module.strict = strict);
+}
+
+/// Sets whether non-ES module formats get a `"use strict"` directive. Enabled by default.
+#[no_mangle]
+pub extern "C" fn swc_transpile_options_set_module_strict_mode(
+    options: *mut swc_transpile_options,
+    strict_mode: bool,
+) {
+    with_options(options, |o| o.module.strict_mode = strict_mode);
+}
+
+/// Sets the global variable that receives the exports of IIFE and UMD output, e.g.
+/// `MyLib` or `MyLib.utils`.
+///
+/// Without one, the name is derived from the file name. A null pointer removes a
+/// previously set name. IIFE output of a dotted name first creates the namespaces it is
+/// assigned into, e.g. `this.MyLib = this.MyLib || {}`.
+#[no_mangle]
+pub extern "C" fn swc_transpile_options_set_module_global_name(
+    options: *mut swc_transpile_options,
+    global_name: *const c_char,
+) {
+    let global_name = optional_str(global_name);
+    with_options(options, |o| o.module.global_name = global_name);
+}
+
+/// Maps an import to the global variable IIFE and UMD output read it from.
+///
+/// For example `react` => `React`, or `lodash/fp` => `_.fp`. Imports without a mapping
+/// are read from a global derived from the specifier. Adding a module again replaces its
+/// global; null pointers and invalid UTF-8 are ignored.
+#[no_mangle]
+pub extern "C" fn swc_transpile_options_add_module_global(
+    options: *mut swc_transpile_options,
+    module: *const c_char,
+    global: *const c_char,
+) {
+    if let (Some(module), Some(global)) = (optional_str(module), optional_str(global)) {
+        with_options(options, |o| {
+            o.module.globals.insert(module, global);
+        });
+    }
+}
+
+/// Turns env mode on or off. Disabled by default.
+///
+/// Enabling it targets `defaults` unless targets are set and keeps settings made earlier;
+/// disabling it discards the query, engines and every other env setting.
+#[no_mangle]
+pub extern "C" fn swc_transpile_options_set_env(
+    options: *mut swc_transpile_options,
+    enabled: bool,
+) {
+    with_options(options, |o| match enabled {
+        true => {
+            o.env.get_or_insert_default();
+        }
+        false => o.env = None,
+    });
+}
+
+/// Lowers syntax and adds polyfills for the environments matched by a browserslist query.
+///
+/// Enables env mode, in which syntax the targets lack, such as optional chaining, class
+/// fields or async functions, is rewritten. Without it only the code generator follows
+/// `swc_transpile_options_set_target`. The query is resolved with the compatibility data
+/// built into the library, e.g. `"> 0.5%, last 2 versions, not dead"` or `"defaults"`.
+/// A null pointer removes the query but leaves env mode and its other settings as they
+/// are; `swc_transpile_options_set_env` turns env mode off.
+#[no_mangle]
+pub extern "C" fn swc_transpile_options_set_env_targets(
+    options: *mut swc_transpile_options,
+    query: *const c_char,
+) {
+    let query = optional_str(query);
+    with_options(options, |o| match query {
+        Some(query) => o.env.get_or_insert_default().query = Some(query),
+        None => {
+            if let Some(env) = &mut o.env {
+                env.query = None;
+            }
+        }
+    });
+}
+
+/// Sets the minimum version of an engine, e.g. `chrome` `80`, `node` `14.17` or `ios` `12`.
+///
+/// Enables env mode. Engines listed this way take precedence over the version the
+/// browserslist query resolves for them; without a query only the listed engines are
+/// targeted. Null pointers and invalid UTF-8 are ignored; unknown engines and malformed
+/// versions fail the transpilation with `swc_status::InvalidConfig`.
+#[no_mangle]
+pub extern "C" fn swc_transpile_options_set_env_engine(
+    options: *mut swc_transpile_options,
+    engine: *const c_char,
+    version: *const c_char,
+) {
+    if let (Some(engine), Some(version)) = (optional_str(engine), optional_str(version)) {
+        with_options(options, |o| {
+            o.env
+                .get_or_insert_default()
+                .engines
+                .insert(engine, version);
+        });
+    }
+}
+
+/// Selects which core-js polyfill imports env mode adds.
+///
+/// Enables env mode, targeting `defaults` unless targets are set.
+#[no_mangle]
+pub extern "C" fn swc_transpile_options_set_env_polyfills(
+    options: *mut swc_transpile_options,
+    mode: swc_polyfill_mode,
+) {
+    with_options(options, |o| {
+        o.env.get_or_insert_default().polyfills = mode.into()
+    });
+}
+
+/// Sets the core-js version polyfill imports are written for, e.g. `"3.38"`.
+///
+/// Enables env mode, targeting `defaults` unless targets are set. A null pointer resets
+/// it to core-js 3.
+#[no_mangle]
+pub extern "C" fn swc_transpile_options_set_env_core_js(
+    options: *mut swc_transpile_options,
+    version: *const c_char,
+) {
+    let version = optional_str(version);
+    with_options(options, |o| o.env.get_or_insert_default().core_js = version);
+}
+
+/// Makes env mode produce simpler but less spec-compliant code.
+///
+/// Enables env mode, targeting `defaults` unless targets are set.
+#[no_mangle]
+pub extern "C" fn swc_transpile_options_set_env_loose(
+    options: *mut swc_transpile_options,
+    loose: bool,
+) {
+    with_options(options, |o| o.env.get_or_insert_default().loose = loose);
+}
+
+/// Resolves an options handle, falling back to the defaults for a null pointer.
+pub(crate) fn transpile_options(options: *const swc_transpile_options) -> TranspileOptions {
+    unsafe { options.as_ref() }
+        .map(|o| o.inner.clone())
+        .unwrap_or_default()
+}
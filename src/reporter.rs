@@ -0,0 +1,145 @@
+use crate::diagnostics::{Diagnostic, Outcome};
+use crate::result::swc_diagnostic;
+use std::ffi::{c_void, CString};
+use std::io::{self, Write};
+use std::os::raw::c_char;
+use std::ptr;
+use std::sync::{Arc, Mutex, PoisonError, RwLock};
+use swc_common::errors::{DiagnosticBuilder, Emitter, EmitterWriter, SourceMapperDyn};
+use swc_common::sync::Lrc;
+use swc_common::SourceMap;
+
+/// Receives a single error or warning as soon as swc reports it
+///
+/// `diagnostic` and everything it points to, as well as `frame`, are only valid during the
+/// call. `frame` is the diagnostic rendered the way swc prints it to a terminal, including the
+/// offending source lines; it is null if the diagnostic has no location.
+#[allow(non_camel_case_types)]
+pub type swc_diagnostic_fn = Option<
+    unsafe extern "C" fn(
+        user_data: *mut c_void,
+        diagnostic: *const swc_diagnostic,
+        frame: *const c_char,
+    ),
+>;
+
+/// Callback installed by the host
+#[derive(Clone, Copy)]
+struct Reporter {
+    callback: unsafe extern "C" fn(*mut c_void, *const swc_diagnostic, *const c_char),
+    user_data: *mut c_void,
+}
+
+// The host promises that its callback and `user_data` may be used from any thread.
+unsafe impl Send for Reporter {}
+unsafe impl Sync for Reporter {}
+
+static REPORTER: RwLock<Option<Reporter>> = RwLock::new(None);
+
+fn reporter() -> Option<Reporter> {
+    *REPORTER.read().unwrap_or_else(PoisonError::into_inner)
+}
+
+/// Hands `diagnostic` to the host's callback, if one is installed.
+///
+/// `frame` is only called if there is a callback, so the source lines are not rendered for
+/// nothing.
+pub(crate) fn report(diagnostic: &Diagnostic, frame: impl FnOnce() -> Option<String>) {
+    let Some(reporter) = reporter() else {
+        return;
+    };
+
+    let c_string = |s: &str| CString::new(s.replace('\0', "")).unwrap_or_default();
+    let message = c_string(&diagnostic.message);
+    let file = diagnostic.file.as_deref().map(c_string);
+    let code = diagnostic.code.as_deref().map(c_string);
+    let frame = frame().as_deref().map(c_string);
+    let as_ptr = |s: &Option<CString>| s.as_ref().map_or(ptr::null(), |s| s.as_ptr());
+
+    // The strings are borrowed for the duration of the call, never freed through the struct
+    let borrowed = swc_diagnostic {
+        severity: diagnostic.severity.into(),
+        message: message.as_ptr() as *mut c_char,
+        file: as_ptr(&file) as *mut c_char,
+        line: diagnostic.line,
+        column: diagnostic.column,
+        span_start: diagnostic.span_start,
+        span_end: diagnostic.span_end,
+        code: as_ptr(&code) as *mut c_char,
+    };
+    unsafe { (reporter.callback)(reporter.user_data, &borrowed, as_ptr(&frame)) };
+}
+
+/// Hands the diagnostics of `outcome` that did not reach the host's callback yet to it.
+///
+/// Called once by every export before it returns, for the failures that happen outside of
+/// [`crate::diagnostics::with_diagnostics`], such as invalid arguments or panics.
+pub(crate) fn report_rest<T>(outcome: &Outcome<T>) {
+    for diagnostic in &outcome.diagnostics[outcome.reported..] {
+        report(diagnostic, || None);
+    }
+}
+
+/// Shared buffer the [`EmitterWriter`] renders into
+#[derive(Clone, Default)]
+struct Buffer(Arc<Mutex<Vec<u8>>>);
+
+impl Write for Buffer {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Renders `db` with its source lines the way swc prints it to a terminal, without colors.
+pub(crate) fn render_frame(cm: &Lrc<SourceMap>, db: &mut DiagnosticBuilder<'_>) -> String {
+    let buffer = Buffer::default();
+    let cm: Lrc<SourceMapperDyn> = cm.clone();
+    EmitterWriter::new(Box::new(buffer.clone()), Some(cm), false, false).emit(db);
+
+    let bytes = std::mem::take(&mut *buffer.0.lock().unwrap_or_else(PoisonError::into_inner));
+    String::from_utf8_lossy(&bytes).into_owned()
+}
+
+/// Receives every error and warning reported while parsing, transforming or minifying.
+///
+/// The callback runs in addition to collecting the diagnostics into the `swc_result` (or the
+/// error message of the legacy functions), on the thread that runs the operation and before
+/// the operation returns. It also receives the diagnostics of operations whose result does
+/// not carry them, such as warnings of a successful `transpile()`, and the errors of calls
+/// that fail before swc runs, such as a null or invalid UTF-8 argument.
+///
+/// # Parameters
+///
+/// * `callback` - Called with each diagnostic, see `swc_diagnostic_fn`; null removes the callback
+/// * `user_data` - Passed to every call of `callback`; may be null
+///
+/// # Safety
+///
+/// The callback must be callable from any thread with `user_data`, including concurrently, and
+/// must stay valid until it is replaced.
+///
+/// # Examples
+///
+/// ```c
+/// static void log_diagnostic(void* logger, const swc_diagnostic* d, const char* frame) {
+///     log_write((Logger*)logger, d->severity == swc_severity::Error ? LOG_ERROR : LOG_WARN,
+///               frame ? frame : d->message);
+/// }
+///
+/// swc_set_diagnostic_callback(log_diagnostic, &logger);
+/// ```
+#[no_mangle]
+pub extern "C" fn swc_set_diagnostic_callback(callback: swc_diagnostic_fn, user_data: *mut c_void) {
+    *REPORTER.write().unwrap_or_else(PoisonError::into_inner) = callback.map(|callback| Reporter {
+        callback,
+        user_data,
+    });
+}
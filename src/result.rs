@@ -0,0 +1,355 @@
+use crate::alloc;
+use crate::diagnostics::{Diagnostic, Outcome, Severity};
+use crate::ffi::catch_panic;
+use crate::last_error;
+use crate::reporter;
+use crate::stats::swc_minify_stats;
+use crate::status::{swc_status, Status, StatusExt};
+use crate::Output;
+use anyhow::{anyhow, Error};
+use std::{ffi::CStr, os::raw::c_char, ptr};
+
+/// Severity of an `swc_diagnostic`
+#[allow(non_camel_case_types)]
+#[repr(C)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum swc_severity {
+    Error,
+    Warning,
+    Note,
+    Help,
+}
+
+impl From<Severity> for swc_severity {
+    fn from(severity: Severity) -> Self {
+        match severity {
+            Severity::Error => swc_severity::Error,
+            Severity::Warning => swc_severity::Warning,
+            Severity::Note => swc_severity::Note,
+            Severity::Help => swc_severity::Help,
+        }
+    }
+}
+
+/// A single error or warning reported by swc
+///
+/// All strings are owned by the enclosing `swc_result` and are released by `swc_result_free`.
+///
+/// * `message` - Human-readable description, never null
+/// * `file` - Name of the file the diagnostic points into, or null if it has no location
+/// * `line` - 1-based line number, or 0 if the diagnostic has no location
+/// * `column` - 0-based column, counted in characters
+/// * `span_start`, `span_end` - Byte offsets of the reported range, relative to the start of the file
+/// * `code` - Diagnostic code if swc assigned one, otherwise null
+#[allow(non_camel_case_types)]
+#[repr(C)]
+pub struct swc_diagnostic {
+    pub severity: swc_severity,
+    pub message: *mut c_char,
+    pub file: *mut c_char,
+    pub line: u32,
+    pub column: u32,
+    pub span_start: u32,
+    pub span_end: u32,
+    pub code: *mut c_char,
+}
+
+/// Result of an `swc_*` entry point
+///
+/// * `status` - `swc_status::Ok` on success, otherwise the reason the operation failed
+/// * `code` - The generated JavaScript, or null if the operation failed
+/// * `map` - The source map as JSON if one was requested as a separate map, otherwise null
+/// * `diagnostics` - Array of `diagnostics_len` errors and warnings, null if there are none
+/// * `licenses` - Array of `licenses_len` license comments minification kept in `code`, as
+///   written and in source order; null if there are none
+/// * `stats` - What minification achieved, only set by `swc_minify_js_with_stats`, otherwise null
+///
+/// A failed operation always carries at least one diagnostic with `swc_severity::Error`, unless
+/// the host allocator refused to allocate even that (`swc_status::Internal` without
+/// diagnostics). The result and everything it points to must be released with `swc_result_free`.
+#[allow(non_camel_case_types)]
+#[repr(C)]
+pub struct swc_result {
+    pub status: swc_status,
+    pub code: *mut c_char,
+    pub map: *mut c_char,
+    pub diagnostics: *mut swc_diagnostic,
+    pub diagnostics_len: usize,
+    pub licenses: *mut *mut c_char,
+    pub licenses_len: usize,
+    pub stats: *mut swc_minify_stats,
+}
+
+/// Converts a Rust string into an owned C string.
+///
+/// The string is allocated with the host's allocator if one is installed and must be
+/// released with [`free_c_string`]. A string with an interior NUL byte, which would be cut
+/// short in C, fails with [`Status::InvalidArgument`] like [`reject_interior_nul`].
+pub(crate) fn to_c_string(s: impl Into<Vec<u8>>) -> Result<*mut c_char, Error> {
+    let mut bytes = s.into();
+    if bytes.contains(&0) {
+        return Err(anyhow!("{INTERIOR_NUL}")).status(Status::InvalidArgument);
+    }
+    bytes.push(0);
+    Ok(alloc::allocate(&bytes)? as *mut c_char)
+}
+
+/// Like [`to_c_string`], but `None` becomes a null pointer.
+fn optional_c_string(s: Option<String>) -> Result<*mut c_char, Error> {
+    s.map_or(Ok(ptr::null_mut()), to_c_string)
+}
+
+pub(crate) fn free_c_string(s: *mut c_char) {
+    if !s.is_null() {
+        let len = unsafe { CStr::from_ptr(s) }.to_bytes_with_nul().len();
+        alloc::deallocate(s as *mut u8, len);
+    }
+}
+
+impl swc_diagnostic {
+    /// Copies `d` into C strings, leaving nothing allocated if that fails.
+    fn new(d: Diagnostic) -> Result<Self, Error> {
+        let mut diagnostic = swc_diagnostic {
+            severity: d.severity.into(),
+            message: ptr::null_mut(),
+            file: ptr::null_mut(),
+            line: d.line,
+            column: d.column,
+            span_start: d.span_start,
+            span_end: d.span_end,
+            code: ptr::null_mut(),
+        };
+        let copied = to_c_string(d.message)
+            .map(|message| diagnostic.message = message)
+            .and_then(|_| optional_c_string(d.file))
+            .map(|file| diagnostic.file = file)
+            .and_then(|_| optional_c_string(d.code))
+            .map(|code| diagnostic.code = code);
+        match copied {
+            Ok(()) => Ok(diagnostic),
+            Err(error) => {
+                diagnostic.free_strings();
+                Err(error)
+            }
+        }
+    }
+
+    fn free_strings(&self) {
+        free_c_string(self.message);
+        free_c_string(self.file);
+        free_c_string(self.code);
+    }
+}
+
+/// Converts diagnostics into an owned C array, returning null for an empty list.
+///
+/// The array must be released with [`free_diagnostics`]. Nothing is left allocated if a
+/// string cannot be allocated.
+pub(crate) fn into_diagnostics_ptr(
+    diagnostics: impl IntoIterator<Item = Diagnostic>,
+) -> Result<(*mut swc_diagnostic, usize), Error> {
+    let mut converted = Vec::new();
+    for d in diagnostics {
+        match swc_diagnostic::new(d) {
+            Ok(d) => converted.push(d),
+            Err(error) => {
+                converted.iter().for_each(swc_diagnostic::free_strings);
+                return Err(error);
+            }
+        }
+    }
+    let diagnostics = converted.into_boxed_slice();
+    Ok(match diagnostics.len() {
+        0 => (ptr::null_mut(), 0),
+        len => (Box::into_raw(diagnostics) as *mut swc_diagnostic, len),
+    })
+}
+
+/// Converts strings into an owned C array of C strings, returning null for an empty list.
+///
+/// The array must be released with [`free_strings`]. Nothing is left allocated if a string
+/// cannot be allocated.
+fn into_strings_ptr(strings: Vec<String>) -> Result<(*mut *mut c_char, usize), Error> {
+    let mut converted = Vec::new();
+    for s in strings {
+        match to_c_string(s) {
+            Ok(s) => converted.push(s),
+            Err(error) => {
+                converted.into_iter().for_each(free_c_string);
+                return Err(error);
+            }
+        }
+    }
+    let strings = converted.into_boxed_slice();
+    Ok(match strings.len() {
+        0 => (ptr::null_mut(), 0),
+        len => (Box::into_raw(strings) as *mut *mut c_char, len),
+    })
+}
+
+/// Frees an array created by [`into_strings_ptr`] together with its strings.
+fn free_strings(strings: *mut *mut c_char, len: usize) {
+    if strings.is_null() {
+        return;
+    }
+
+    let strings = unsafe { Box::from_raw(ptr::slice_from_raw_parts_mut(strings, len)) };
+    for s in strings.iter() {
+        free_c_string(*s);
+    }
+}
+
+/// Frees an array created by [`into_diagnostics_ptr`] together with its strings.
+pub(crate) fn free_diagnostics(diagnostics: *mut swc_diagnostic, len: usize) {
+    if diagnostics.is_null() {
+        return;
+    }
+
+    let diagnostics = unsafe { Box::from_raw(ptr::slice_from_raw_parts_mut(diagnostics, len)) };
+    diagnostics.iter().for_each(swc_diagnostic::free_strings);
+}
+
+impl swc_result {
+    /// A result with `status` that points to nothing
+    fn empty(status: Status) -> Self {
+        swc_result {
+            status: status.into(),
+            code: ptr::null_mut(),
+            map: ptr::null_mut(),
+            diagnostics: ptr::null_mut(),
+            diagnostics_len: 0,
+            licenses: ptr::null_mut(),
+            licenses_len: 0,
+            stats: ptr::null_mut(),
+        }
+    }
+
+    /// Copies `outcome` into C strings and arrays, leaving nothing allocated if that fails.
+    fn new(outcome: Outcome<Output>) -> Result<Self, Error> {
+        let mut result = swc_result::empty(outcome.status);
+        match result.fill(outcome) {
+            Ok(()) => Ok(result),
+            Err(error) => {
+                result.free_contents();
+                Err(error)
+            }
+        }
+    }
+
+    fn fill(&mut self, outcome: Outcome<Output>) -> Result<(), Error> {
+        (self.diagnostics, self.diagnostics_len) = into_diagnostics_ptr(outcome.diagnostics)?;
+        if let Some(output) = outcome.value {
+            self.code = to_c_string(output.code)?;
+            self.map = optional_c_string(output.map)?;
+            (self.licenses, self.licenses_len) = into_strings_ptr(output.licenses)?;
+            if let Some(stats) = output.stats {
+                self.stats = Box::into_raw(Box::new(swc_minify_stats::from(stats)));
+            }
+        }
+        Ok(())
+    }
+
+    /// Releases everything the result points to, but not the result itself.
+    fn free_contents(&self) {
+        free_c_string(self.code);
+        free_c_string(self.map);
+        free_diagnostics(self.diagnostics, self.diagnostics_len);
+        free_strings(self.licenses, self.licenses_len);
+        if !self.stats.is_null() {
+            drop(unsafe { Box::from_raw(self.stats) });
+        }
+    }
+}
+
+const INTERIOR_NUL: &str = "generated output contains an interior NUL byte";
+
+/// Turns output that cannot be represented as a C string into a failure.
+///
+/// JavaScript output escapes NUL characters, so this only triggers for invalid input that
+/// made its way through unchanged, but it must not crash the host either way. Unlike the
+/// failure of [`to_c_string`], the outcome keeps the diagnostics reported so far.
+pub(crate) fn reject_interior_nul(mut outcome: Outcome<Output>) -> Outcome<Output> {
+    let has_nul = outcome.value.as_ref().is_some_and(|output| {
+        output.code.contains('\0') || output.map.as_ref().is_some_and(|map| map.contains('\0'))
+    });
+
+    if has_nul {
+        outcome.value = None;
+        outcome.status = Status::InvalidArgument;
+        outcome.diagnostics.push(Diagnostic::error(INTERIOR_NUL));
+    }
+
+    outcome
+}
+
+/// Boxes an outcome into a heap-allocated `swc_result` for the caller.
+///
+/// If the strings of the result cannot be allocated or converting it panics, the result
+/// reports that failure instead, without diagnostics if even those cannot be allocated.
+pub(crate) fn into_result_ptr(outcome: Outcome<Output>) -> *mut swc_result {
+    let result = catch_panic(|| {
+        let outcome = reject_interior_nul(outcome);
+        reporter::report_rest(&outcome);
+        last_error::record(&outcome);
+        swc_result::new(outcome)
+    });
+    let result = result.unwrap_or_else(|error| {
+        let failure = Outcome::failure(error);
+        reporter::report_rest(&failure);
+        last_error::record(&failure);
+        let status = failure.status;
+        catch_panic(|| swc_result::new(failure)).unwrap_or_else(|_| swc_result::empty(status))
+    });
+    Box::into_raw(Box::new(result))
+}
+
+/// Boxes the outcome of a streaming call, whose code was already written to the host.
+///
+/// The result carries the separate source map, if any, but no code.
+pub(crate) fn into_streamed_result_ptr(outcome: Outcome<Option<String>>) -> *mut swc_result {
+    let result = into_result_ptr(outcome.map(|map| Output {
+        code: String::new(),
+        map,
+        ..Default::default()
+    }));
+    let result = unsafe { &mut *result };
+    free_c_string(result.code);
+    result.code = ptr::null_mut();
+    result
+}
+
+/// Frees an `swc_result` returned by any `swc_*` entry point.
+///
+/// Releases the generated code, the source map, every diagnostic and license comment, the
+/// stats and the result itself.
+/// Passing a null pointer is a no-op.
+///
+/// # Parameters
+///
+/// * `result` - Pointer previously returned by an `swc_*` entry point
+///
+/// # Safety
+///
+/// This function is unsafe because:
+/// * The pointer must have been returned by this library and not freed already
+/// * Neither the result nor any string it points to may be used after this call
+///
+/// # Examples
+///
+/// ```c
+/// swc_result* result = swc_compile_js("const a: number = 1;");
+/// // Use result->code and result->diagnostics...
+/// swc_result_free(result);
+/// ```
+#[no_mangle]
+pub extern "C" fn swc_result_free(result: *mut swc_result) {
+    if result.is_null() {
+        return;
+    }
+
+    let _ = catch_panic(|| {
+        let result = unsafe { Box::from_raw(result) };
+        result.free_contents();
+        Ok(())
+    });
+}
@@ -0,0 +1,308 @@
+use crate::api::{self, CompileOptions};
+use crate::diagnostics::Outcome;
+use crate::ffi::{catch_panic, guard, input_str};
+use crate::mangle_props::KeepQuoted;
+use crate::options::{optional_str, swc_transpile_options, transpile_options, TranspileOptions};
+use crate::result::{into_result_ptr, swc_result};
+use crate::source_map::SourceMapMode;
+use crate::status::{Status, StatusExt};
+use crate::{compile_in, input_name, minify_in, minify_options, transpile_in, File, Output};
+use anyhow::{anyhow, Error};
+use std::os::raw::c_char;
+use swc_common::{FileName, Globals, GLOBALS};
+
+/// One compiler shared between many calls
+///
+/// A session owns a `SourceMap`, an `swc::Compiler` and the swc `Globals`. Every file
+/// processed through a session is added to its source map, so long-running services should
+/// call [`Session::reset`] from time to time.
+///
+/// A session may be used from any thread, but not from several threads at once.
+pub struct Session {
+    compiler: swc_compiler::Compiler,
+    globals: Globals,
+}
+
+impl Default for Session {
+    fn default() -> Self {
+        Session {
+            compiler: swc_compiler::Compiler::new(Default::default()),
+            globals: Globals::default(),
+        }
+    }
+}
+
+impl Session {
+    pub fn new() -> Self {
+        Session::default()
+    }
+
+    /// Drops every source file accumulated by the session.
+    ///
+    /// The source map, compiler and globals are replaced by fresh ones. Outputs returned
+    /// earlier are not affected.
+    pub fn reset(&mut self) {
+        *self = Session::default();
+    }
+
+    /// Returns the number of source files held by the session.
+    pub fn source_file_count(&self) -> usize {
+        self.compiler.cm.files().len()
+    }
+
+    /// Compiles TypeScript/JavaScript, see [`api::compile`].
+    pub fn compile(&self, file: File, options: &CompileOptions) -> Result<Output, api::Error> {
+        api::into_result(self.run(|compiler| api::compile_in(compiler, file, options)))
+    }
+
+    /// Minifies JavaScript, see [`api::minify`].
+    pub fn minify(&self, file: File, source_maps: SourceMapMode) -> Result<Output, api::Error> {
+        api::into_result(self.run(|compiler| {
+            minify_in(
+                compiler,
+                file,
+                &minify_options(source_maps, None),
+                KeepQuoted::default(),
+                source_maps,
+                None,
+                false,
+            )
+        }))
+    }
+
+    /// Transpiles TypeScript/TSX, see [`api::transpile`].
+    pub fn transpile(&self, file: File, options: &TranspileOptions) -> Result<Output, api::Error> {
+        api::into_result(self.run(|compiler| transpile_in(compiler.cm.clone(), file, options)))
+    }
+
+    /// Runs `f` with the session's compiler within its globals.
+    fn run(&self, f: impl FnOnce(&swc_compiler::Compiler) -> Outcome<Output>) -> Outcome<Output> {
+        GLOBALS.set(&self.globals, || f(&self.compiler))
+    }
+}
+
+/// Opaque handle that shares one compiler between many calls
+///
+/// A session owns a `SourceMap`, an `swc::Compiler` and the swc `Globals`, together with
+/// the configuration used by `swc_session_compile` and `swc_session_transpile`. Every file
+/// processed through a session is added to its source map, so long-running hosts should
+/// call `swc_session_reset` from time to time.
+///
+/// A session may be used from any thread, but not from several threads at once.
+#[allow(non_camel_case_types)]
+pub struct swc_session {
+    session: Session,
+    config: Option<String>,
+    transpile_options: TranspileOptions,
+}
+
+/// Borrows the session behind `session`, rejecting null handles.
+fn session_ref<'a>(session: *const swc_session) -> Result<&'a swc_session, Error> {
+    unsafe { session.as_ref() }
+        .ok_or_else(|| anyhow!("`session` must not be null"))
+        .status(Status::InvalidArgument)
+}
+
+/// Creates a session.
+///
+/// # Parameters
+///
+/// * `config_json` - swc options as JSON (the format of `.swcrc`) used by
+///   `swc_session_compile`, or null for the built-in defaults
+/// * `options` - Options used by `swc_session_transpile`, or null for the defaults. The
+///   options are copied, so the handle may be freed right after this call.
+///
+/// # Returns
+///
+/// Pointer to a new session that must be released with `swc_session_free()`
+///
+/// # Safety
+///
+/// This function is unsafe because:
+/// * It dereferences raw pointers
+/// * It allocates memory that must be freed by the caller using `swc_session_free()`
+///
+/// # Examples
+///
+/// ```c
+/// swc_session* session = swc_session_new("{\"jsc\":{\"target\":\"es2020\"}}", NULL);
+/// for (const auto& [name, code] : files) {
+///     swc_result* result = swc_session_compile(session, name.c_str(), code.c_str());
+///     // Use result->code and result->diagnostics...
+///     swc_result_free(result);
+/// }
+/// swc_session_free(session);
+/// ```
+#[no_mangle]
+pub extern "C" fn swc_session_new(
+    config_json: *const c_char,
+    options: *const swc_transpile_options,
+) -> *mut swc_session {
+    Box::into_raw(Box::new(swc_session {
+        session: Session::new(),
+        config: optional_str(config_json),
+        transpile_options: transpile_options(options),
+    }))
+}
+
+/// Frees a session.
+///
+/// Passing a null pointer is a no-op.
+///
+/// # Safety
+///
+/// The pointer must have been returned by `swc_session_new()` and must not be used after
+/// this call.
+#[no_mangle]
+pub extern "C" fn swc_session_free(session: *mut swc_session) {
+    if !session.is_null() {
+        let _ = catch_panic(|| {
+            let _ = unsafe { Box::from_raw(session) };
+            Ok(())
+        });
+    }
+}
+
+/// Drops every source file accumulated by a session.
+///
+/// The session's source map, compiler and globals are replaced by fresh ones while its
+/// configuration is kept. Results returned earlier are not affected. A null pointer is
+/// ignored.
+///
+/// # Safety
+///
+/// The pointer must have been returned by `swc_session_new()`.
+#[no_mangle]
+pub extern "C" fn swc_session_reset(session: *mut swc_session) {
+    let _ = catch_panic(|| {
+        if let Some(session) = unsafe { session.as_mut() } {
+            session.session.reset();
+        }
+        Ok(())
+    });
+}
+
+/// Returns the number of source files held by a session, or 0 for a null pointer.
+///
+/// Hosts can use this to decide when to call `swc_session_reset()`.
+#[no_mangle]
+pub extern "C" fn swc_session_source_file_count(session: *const swc_session) -> usize {
+    catch_panic(|| Ok(session_ref(session)?.session.source_file_count())).unwrap_or(0)
+}
+
+/// Compiles TypeScript/JavaScript code with the session's compiler and configuration.
+///
+/// Behaves like `swc_compile_js_with_config` with the config passed to `swc_session_new()`,
+/// or like `swc_compile_js` if none was given.
+///
+/// # Parameters
+///
+/// * `session` - Session created with `swc_session_new()`
+/// * `filename` - Path of the input, used for `.swcrc` lookup and diagnostics; may be null
+/// * `code` - C string pointer containing the code to compile
+///
+/// # Returns
+///
+/// Pointer to an `swc_result` that must be released with `swc_result_free()`
+///
+/// # Safety
+///
+/// This function is unsafe because:
+/// * It dereferences raw pointers
+/// * It allocates memory that must be freed by the caller using `swc_result_free()`
+#[no_mangle]
+pub extern "C" fn swc_session_compile(
+    session: *mut swc_session,
+    filename: *const c_char,
+    code: *const c_char,
+) -> *mut swc_result {
+    into_result_ptr(guard(|| {
+        let session = session_ref(session)?;
+        let input = input_str(code, "code")?;
+        let file = File::FileName(input_name(filename), input.into());
+        Ok(session
+            .session
+            .run(|compiler| compile_in(compiler, file, session.config.as_deref(), None)))
+    }))
+}
+
+/// Minifies JavaScript code with the session's compiler.
+///
+/// Behaves like `swc_minify_js`.
+///
+/// # Parameters
+///
+/// * `session` - Session created with `swc_session_new()`
+/// * `filename` - Name of the input, reported in diagnostics; may be null
+/// * `code` - C string pointer containing the JavaScript to minify
+///
+/// # Returns
+///
+/// Pointer to an `swc_result` that must be released with `swc_result_free()`
+///
+/// # Safety
+///
+/// This function is unsafe because:
+/// * It dereferences raw pointers
+/// * It allocates memory that must be freed by the caller using `swc_result_free()`
+#[no_mangle]
+pub extern "C" fn swc_session_minify(
+    session: *mut swc_session,
+    filename: *const c_char,
+    code: *const c_char,
+) -> *mut swc_result {
+    into_result_ptr(guard(|| {
+        let session = session_ref(session)?;
+        let input = input_str(code, "code")?;
+        let file = File::FileName(input_name(filename), input.into());
+        Ok(session.session.run(|compiler| {
+            let options = minify_options(SourceMapMode::None, None);
+            minify_in(
+                compiler,
+                file,
+                &options,
+                KeepQuoted::default(),
+                SourceMapMode::None,
+                None,
+                false,
+            )
+        }))
+    }))
+}
+
+/// Transpiles TypeScript/TSX code with the session's source map and transpile options.
+///
+/// Behaves like `swc_transpile_with_options` with the options passed to
+/// `swc_session_new()`.
+///
+/// # Parameters
+///
+/// * `session` - Session created with `swc_session_new()`
+/// * `filename` - The file name, used to detect TSX and reported in diagnostics
+/// * `code` - C string pointer containing the TypeScript/TSX code
+///
+/// # Returns
+///
+/// Pointer to an `swc_result` that must be released with `swc_result_free()`
+///
+/// # Safety
+///
+/// This function is unsafe because:
+/// * It dereferences raw pointers
+/// * It allocates memory that must be freed by the caller using `swc_result_free()`
+#[no_mangle]
+pub extern "C" fn swc_session_transpile(
+    session: *mut swc_session,
+    filename: *const c_char,
+    code: *const c_char,
+) -> *mut swc_result {
+    into_result_ptr(guard(|| {
+        let session = session_ref(session)?;
+        let filename = input_str(filename, "filename")?;
+        let input = input_str(code, "code")?;
+        let file = File::FileName(FileName::Custom(String::from(filename)), input.into());
+        Ok(session
+            .session
+            .run(|compiler| transpile_in(compiler.cm.clone(), file, &session.transpile_options)))
+    }))
+}
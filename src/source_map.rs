@@ -0,0 +1,164 @@
+use anyhow::{Context, Error};
+use base64::{prelude::BASE64_STANDARD, Engine};
+use std::io::{self, Write};
+use swc_common::source_map::SourceMapGenConfig;
+use swc_common::{BytePos, FileName, LineCol, SourceMap};
+
+/// How a source map is produced alongside the generated code
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum SourceMapMode {
+    /// No source map is generated
+    #[default]
+    None,
+    /// The source map is returned as a separate JSON string
+    Separate,
+    /// The source map is appended to the code as a base64 `data:` URL comment
+    Inline,
+}
+
+impl SourceMapMode {
+    pub fn enabled(self) -> bool {
+        self != SourceMapMode::None
+    }
+}
+
+/// How a source map is produced alongside the generated code
+///
+/// * `None` - No source map is generated
+/// * `Separate` - The source map is returned in the `map` field of `swc_result`
+/// * `Inline` - The source map is appended to the code as a `//# sourceMappingURL=data:...` comment
+#[allow(non_camel_case_types)]
+#[repr(C)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum swc_source_map_mode {
+    None,
+    Separate,
+    Inline,
+}
+
+impl From<swc_source_map_mode> for SourceMapMode {
+    fn from(mode: swc_source_map_mode) -> Self {
+        match mode {
+            swc_source_map_mode::None => SourceMapMode::None,
+            swc_source_map_mode::Separate => SourceMapMode::Separate,
+            swc_source_map_mode::Inline => SourceMapMode::Inline,
+        }
+    }
+}
+
+/// Source map settings for code emitted by the transpile pipeline
+///
+/// Sources are named after their `FileName` and always embed their content, so a map is
+/// usable on its own without access to the original files.
+struct Config;
+
+impl SourceMapGenConfig for Config {
+    fn file_name_to_source(&self, f: &FileName) -> String {
+        f.to_string()
+    }
+
+    fn inline_sources_content(&self, _f: &FileName) -> bool {
+        true
+    }
+}
+
+/// Builds a source map from the mappings collected by a `JsWriter` and serializes it to JSON.
+///
+/// If `input_source_map` is given, the new map is composed with it so that it points back to
+/// the sources the input was generated from.
+pub(crate) fn build_source_map(
+    cm: &SourceMap,
+    mappings: &[(BytePos, LineCol)],
+    input_source_map: Option<&str>,
+) -> Result<String, Error> {
+    let orig = input_source_map.map(parse_source_map).transpose()?;
+    let mut buf = vec![];
+    cm.build_source_map(mappings, orig, Config)
+        .to_writer(&mut buf)
+        .context("failed to write source map")?;
+    String::from_utf8(buf).context("source map is not utf-8")
+}
+
+/// Parses a source map given as JSON.
+fn parse_source_map(json: &str) -> Result<swc_compiler::sourcemap::SourceMap, Error> {
+    swc_compiler::sourcemap::SourceMap::from_slice(json.as_bytes())
+        .context("failed to parse input source map")
+}
+
+/// Applies `mode` to generated code and its source map.
+///
+/// For [`SourceMapMode::Inline`] the map is moved into a `sourceMappingURL` comment at the
+/// end of the code; otherwise both are returned unchanged.
+pub(crate) fn apply_mode(
+    mut code: String,
+    map: Option<String>,
+    mode: SourceMapMode,
+) -> (String, Option<String>) {
+    match (mode, map) {
+        (SourceMapMode::Inline, Some(map)) => {
+            if !code.ends_with('\n') {
+                code.push('\n');
+            }
+            code.push_str(&inline_comment(&map));
+            (code, None)
+        }
+        (_, map) => (code, map),
+    }
+}
+
+/// The comment that embeds `map` into the code it belongs to
+fn inline_comment(map: &str) -> String {
+    let mut comment = String::from("//# sourceMappingURL=data:application/json;base64,");
+    BASE64_STANDARD.encode_string(map.as_bytes(), &mut comment);
+    comment
+}
+
+/// Writer that remembers whether the code written through it ends with a newline
+pub(crate) struct LineTracker<W> {
+    inner: W,
+    at_line_start: bool,
+}
+
+impl<W: Write> LineTracker<W> {
+    pub fn new(inner: W) -> Self {
+        LineTracker {
+            inner,
+            at_line_start: false,
+        }
+    }
+}
+
+impl<W: Write> Write for LineTracker<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let written = self.inner.write(buf)?;
+        if let Some(last) = buf[..written].last() {
+            self.at_line_start = *last == b'\n';
+        }
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+/// The streaming counterpart of [`apply_mode`] for code already written to `out`.
+///
+/// Writes the `sourceMappingURL` comment for [`SourceMapMode::Inline`]; otherwise returns
+/// the map unchanged.
+pub(crate) fn finish_stream<W: Write>(
+    out: &mut LineTracker<W>,
+    map: Option<String>,
+    mode: SourceMapMode,
+) -> io::Result<Option<String>> {
+    match (mode, map) {
+        (SourceMapMode::Inline, Some(map)) => {
+            if !out.at_line_start {
+                out.write_all(b"\n")?;
+            }
+            out.write_all(inline_comment(&map).as_bytes())?;
+            Ok(None)
+        }
+        (_, map) => Ok(map),
+    }
+}
@@ -0,0 +1,155 @@
+//! Numbers describing a minification: sizes before and after, estimates of the compressed
+//! size, mangled names and time spent in each stage.
+
+use crate::ffi::{guard, input_str};
+use crate::options::optional_str;
+use crate::result::{into_result_ptr, swc_result};
+use crate::{input_name, minify_with, parse_minify_options, requested_source_maps, File};
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use std::io::Write;
+use std::os::raw::c_char;
+use std::time::Duration;
+
+/// What a minification achieved and where the time went
+///
+/// Sizes are in bytes. The compressed sizes are those of the output compressed as a whole,
+/// with gzip at level 9 and brotli at quality 11, as a web server serving the file would.
+///
+/// swc's minifier compresses and mangles in one call, so the split between `compress` and
+/// `mangle` is approximate: it is taken when the mangler asks for cached names, after it
+/// already counted the characters of the program and renamed labels and private names.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct MinifyStats {
+    pub input_size: usize,
+    pub output_size: usize,
+    pub gzip_size: usize,
+    pub brotli_size: usize,
+    /// Number of variables and properties given a new name
+    pub mangled: usize,
+    /// Time spent compressing, including dead code elimination and the start of mangling
+    pub compress: Duration,
+    /// Time spent mangling variable and property names, from when the mangler picks names
+    pub mangle: Duration,
+    /// Time spent generating the code and source map
+    pub codegen: Duration,
+}
+
+impl MinifyStats {
+    /// Fills in the output and compressed sizes for `code`.
+    pub(crate) fn measure_output(&mut self, code: &str) {
+        self.output_size = code.len();
+        self.gzip_size = gzip_size(code.as_bytes());
+        self.brotli_size = brotli_size(code.as_bytes());
+    }
+}
+
+fn gzip_size(bytes: &[u8]) -> usize {
+    let mut encoder = GzEncoder::new(Vec::new(), Compression::best());
+    encoder
+        .write_all(bytes)
+        .and_then(|_| encoder.finish())
+        .map_or(0, |compressed| compressed.len())
+}
+
+fn brotli_size(mut bytes: &[u8]) -> usize {
+    let mut compressed = Vec::new();
+    brotli::BrotliCompress(&mut bytes, &mut compressed, &Default::default())
+        .map_or(0, |_| compressed.len())
+}
+
+/// Statistics of a minification
+///
+/// * `input_size`, `output_size` - Size of the input and of the minified code in bytes, not
+///   counting an inline source map
+/// * `gzip_size`, `brotli_size` - Estimated size of the minified code served with gzip
+///   (level 9) or brotli (quality 11) compression
+/// * `mangled` - Number of variables and properties that were given a new name
+/// * `compress_ms`, `mangle_ms`, `codegen_ms` - Milliseconds spent compressing, mangling and
+///   generating code; compressing and mangling run as one step of swc, and the split between
+///   them is approximate
+#[allow(non_camel_case_types)]
+#[repr(C)]
+#[derive(Clone, Copy, Debug)]
+pub struct swc_minify_stats {
+    pub input_size: usize,
+    pub output_size: usize,
+    pub gzip_size: usize,
+    pub brotli_size: usize,
+    pub mangled: usize,
+    pub compress_ms: f64,
+    pub mangle_ms: f64,
+    pub codegen_ms: f64,
+}
+
+impl From<MinifyStats> for swc_minify_stats {
+    fn from(stats: MinifyStats) -> Self {
+        let ms = |duration: Duration| duration.as_secs_f64() * 1000.0;
+        swc_minify_stats {
+            input_size: stats.input_size,
+            output_size: stats.output_size,
+            gzip_size: stats.gzip_size,
+            brotli_size: stats.brotli_size,
+            mangled: stats.mangled,
+            compress_ms: ms(stats.compress),
+            mangle_ms: ms(stats.mangle),
+            codegen_ms: ms(stats.codegen),
+        }
+    }
+}
+
+/// Minifies JavaScript code and reports what the minification achieved.
+///
+/// Behaves like `swc_minify_js_with_options` and additionally fills `stats` of the result:
+/// input and output size, estimated gzip and brotli sizes of the output, the number of mangled
+/// names and the time spent compressing, mangling and generating code. Estimating the
+/// compressed sizes takes about as long as minifying, so the other functions skip it.
+///
+/// # Parameters
+///
+/// * `code` - C string pointer containing the JavaScript code to minify
+/// * `filename` - Name of the input, used in diagnostics and the source map; may be null
+/// * `options_json` - Minifier options as JSON, see `swc_minify_js_with_options`; null
+///   behaves like `{}`
+///
+/// # Returns
+///
+/// Pointer to an `swc_result`. `code` and `stats` are null if minification failed, in which
+/// case `diagnostics` contains at least one error.
+///
+/// # Safety
+///
+/// This function is unsafe because:
+/// * It dereferences raw pointers
+/// * It allocates memory that must be freed by the caller using `swc_result_free()`
+///
+/// # Examples
+///
+/// ```c
+/// swc_result* result = swc_minify_js_with_stats(js, "app.js", NULL);
+/// if (result->stats && result->stats->brotli_size > 50 * 1024) {
+///     fprintf(stderr, "app.js is over budget: %zu bytes brotli\n", result->stats->brotli_size);
+/// }
+/// swc_result_free(result);
+/// ```
+#[no_mangle]
+pub extern "C" fn swc_minify_js_with_stats(
+    code: *const c_char,
+    filename: *const c_char,
+    options_json: *const c_char,
+) -> *mut swc_result {
+    into_result_ptr(guard(|| {
+        let input = input_str(code, "code")?;
+        let options = optional_str(options_json);
+        let (options, keep_quoted) = parse_minify_options(options.as_deref().unwrap_or("{}"))?;
+        let file = File::FileName(input_name(filename), input.into());
+        Ok(minify_with(
+            file,
+            &options,
+            keep_quoted,
+            requested_source_maps(&options),
+            None,
+            true,
+        ))
+    }))
+}
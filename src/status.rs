@@ -0,0 +1,147 @@
+use anyhow::Error;
+use std::fmt;
+use std::io;
+
+/// Why an operation failed
+///
+/// Derived from the error an operation returned, see [`Status::of`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Status {
+    /// The operation succeeded
+    Ok,
+    /// A required argument was null or otherwise unusable
+    InvalidArgument,
+    /// An argument was not valid UTF-8
+    InvalidUtf8,
+    /// The input file does not exist
+    FileNotFound,
+    /// The input file or a config file could not be read
+    Io,
+    /// The JSON config or a `.swcrc` file could not be parsed, or an option has an
+    /// invalid value
+    InvalidConfig,
+    /// The input source map could not be parsed
+    InvalidSourceMap,
+    /// The input has syntax errors
+    Syntax,
+    /// A transform or the code generator reported an error
+    Transform,
+    /// The minifier reported an error
+    Minify,
+    /// A bug in the library, such as a panic
+    Internal,
+}
+
+impl Status {
+    /// Determines the status an error is reported with.
+    ///
+    /// An explicit tag attached with [`StatusExt::status`] takes precedence. Otherwise the
+    /// error chain is inspected for the I/O, JSON and source map errors of the underlying
+    /// libraries.
+    pub(crate) fn of(error: &Error) -> Option<Status> {
+        if let Some(tagged) = error.downcast_ref::<StatusError>() {
+            return Some(tagged.status);
+        }
+
+        error.chain().find_map(|cause| {
+            if let Some(e) = cause.downcast_ref::<io::Error>() {
+                Some(match e.kind() {
+                    io::ErrorKind::NotFound => Status::FileNotFound,
+                    _ => Status::Io,
+                })
+            } else if cause.is::<serde_json::Error>() {
+                Some(Status::InvalidConfig)
+            } else if cause.is::<swc_compiler::sourcemap::Error>() {
+                Some(Status::InvalidSourceMap)
+            } else {
+                None
+            }
+        })
+    }
+}
+
+/// Stable numeric code describing why a call failed
+///
+/// The numeric values are part of the ABI and never change; new codes are only appended.
+///
+/// * `Ok` - The call succeeded
+/// * `InvalidArgument` - A required argument was null
+/// * `InvalidUtf8` - An argument was not valid UTF-8
+/// * `FileNotFound` - The input file does not exist
+/// * `Io` - The input file or a config file could not be read
+/// * `InvalidConfig` - The JSON config or a `.swcrc` file could not be parsed, or an option
+///   has an invalid value
+/// * `InvalidSourceMap` - The input source map could not be parsed
+/// * `Syntax` - The input has syntax errors
+/// * `Transform` - A transform or the code generator reported an error
+/// * `Minify` - The minifier reported an error
+/// * `Internal` - A bug in the library, such as a panic
+#[allow(non_camel_case_types)]
+#[repr(C)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum swc_status {
+    Ok = 0,
+    InvalidArgument = 1,
+    InvalidUtf8 = 2,
+    FileNotFound = 3,
+    Io = 4,
+    InvalidConfig = 5,
+    InvalidSourceMap = 6,
+    Syntax = 7,
+    Transform = 8,
+    Minify = 9,
+    Internal = 10,
+}
+
+impl From<Status> for swc_status {
+    fn from(status: Status) -> Self {
+        match status {
+            Status::Ok => swc_status::Ok,
+            Status::InvalidArgument => swc_status::InvalidArgument,
+            Status::InvalidUtf8 => swc_status::InvalidUtf8,
+            Status::FileNotFound => swc_status::FileNotFound,
+            Status::Io => swc_status::Io,
+            Status::InvalidConfig => swc_status::InvalidConfig,
+            Status::InvalidSourceMap => swc_status::InvalidSourceMap,
+            Status::Syntax => swc_status::Syntax,
+            Status::Transform => swc_status::Transform,
+            Status::Minify => swc_status::Minify,
+            Status::Internal => swc_status::Internal,
+        }
+    }
+}
+
+/// An error tagged with the status it is reported as
+///
+/// Displays exactly like the wrapped error, so tagging never changes a message.
+#[derive(Debug)]
+struct StatusError {
+    status: Status,
+    error: Error,
+}
+
+impl fmt::Display for StatusError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:#}", self.error)
+    }
+}
+
+impl std::error::Error for StatusError {}
+
+/// Attaches a [`Status`] to the error of a `Result`
+pub(crate) trait StatusExt<T> {
+    /// Tags the error with `status`, unless [`Status::of`] already finds a status for it.
+    fn status(self, status: Status) -> Result<T, Error>;
+}
+
+impl<T, E: Into<Error>> StatusExt<T> for Result<T, E> {
+    fn status(self, status: Status) -> Result<T, Error> {
+        self.map_err(|error| {
+            let error = error.into();
+            match Status::of(&error) {
+                Some(_) => error,
+                None => StatusError { status, error }.into(),
+            }
+        })
+    }
+}
@@ -0,0 +1,179 @@
+use crate::diagnostics::with_diagnostics;
+use crate::ffi::{guard, input_str};
+use crate::options::{swc_transpile_options, transpile_options};
+use crate::result::{into_streamed_result_ptr, swc_result};
+use crate::source_map::{swc_source_map_mode, SourceMapMode};
+use crate::status::{Status, StatusExt};
+use crate::{get_js_file, input_name, minify, minify_options, transpile_to, File};
+use anyhow::{anyhow, Context, Error};
+use std::ffi::c_void;
+use std::io::{self, BufWriter, Write};
+use std::os::raw::c_char;
+use swc_common::sync::Lrc;
+use swc_common::{FileName, SourceMap, GLOBALS};
+
+/// Receives the next `len` bytes of generated code
+///
+/// `data` is not NUL-terminated and is only valid during the call. Chunks may split UTF-8
+/// sequences; the concatenation of all chunks is valid UTF-8.
+#[allow(non_camel_case_types)]
+pub type swc_write_fn =
+    Option<unsafe extern "C" fn(ctx: *mut c_void, data: *const c_char, len: usize)>;
+
+/// Size of the chunks generated code is handed to the host in
+const CHUNK_SIZE: usize = 64 * 1024;
+
+/// Forwards everything written to it to the host's callback
+struct CallbackWriter {
+    write: unsafe extern "C" fn(*mut c_void, *const c_char, usize),
+    ctx: *mut c_void,
+}
+
+impl Write for CallbackWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if !buf.is_empty() {
+            unsafe { (self.write)(self.ctx, buf.as_ptr() as *const c_char, buf.len()) };
+        }
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Buffers the output for the host's callback, rejecting a null callback.
+fn writer(write: swc_write_fn, ctx: *mut c_void) -> Result<BufWriter<CallbackWriter>, Error> {
+    let write = write
+        .ok_or_else(|| anyhow!("`write` must not be null"))
+        .status(Status::InvalidArgument)?;
+    Ok(BufWriter::with_capacity(
+        CHUNK_SIZE,
+        CallbackWriter { write, ctx },
+    ))
+}
+
+/// Transpiles TypeScript/TSX code and streams the JavaScript to a callback.
+///
+/// Behaves like `swc_transpile_with_options`, but the code generator writes through `write`
+/// in chunks of up to 64 KiB while it runs, so the output is never held in memory as a
+/// whole. Nothing is written if the input has errors. If generating the code fails after
+/// chunks were handed to `write`, which takes more than 64 KiB of output, those chunks are
+/// not retracted but nothing more is written.
+///
+/// # Parameters
+///
+/// * `filename` - The file name, used to detect the syntax and reported in diagnostics
+/// * `code` - C string pointer containing the TypeScript/TSX code
+/// * `options` - Options created with `swc_transpile_options_new()`, or null for the defaults
+/// * `write` - Called with each chunk of generated code, see `swc_write_fn`
+/// * `ctx` - Passed to every call of `write`; may be null
+///
+/// # Returns
+///
+/// Pointer to an `swc_result` whose `code` is always null. It carries the status, the
+/// diagnostics and, if requested, the separate source map; an inline map is streamed after
+/// the code.
+///
+/// # Safety
+///
+/// This function is unsafe because:
+/// * It dereferences raw pointers
+/// * It calls `write` with `ctx`
+/// * It allocates memory that must be freed by the caller using `swc_result_free()`
+///
+/// # Examples
+///
+/// ```c
+/// static void to_file(void* ctx, const char* data, size_t len) { fwrite(data, 1, len, (FILE*)ctx); }
+///
+/// FILE* out = fopen("bundle.js", "wb");
+/// swc_result* result = swc_transpile_stream("bundle.ts", code, NULL, to_file, out);
+/// fclose(out);
+/// if (result->status != swc_status::Ok) { report(result->diagnostics, result->diagnostics_len); }
+/// swc_result_free(result);
+/// ```
+#[no_mangle]
+pub extern "C" fn swc_transpile_stream(
+    filename: *const c_char,
+    code: *const c_char,
+    options: *const swc_transpile_options,
+    write: swc_write_fn,
+    ctx: *mut c_void,
+) -> *mut swc_result {
+    into_streamed_result_ptr(guard(|| {
+        let filename = input_str(filename, "filename")?;
+        let input = input_str(code, "code")?;
+        let options = transpile_options(options);
+        let mut out = writer(write, ctx)?;
+        let file = File::FileName(FileName::Custom(String::from(filename)), input.into());
+        let cm: Lrc<SourceMap> = Default::default();
+        let outcome = GLOBALS.set(&Default::default(), || {
+            with_diagnostics(cm.clone(), |handler| {
+                transpile_to(cm, handler, file, &options, &mut out).status(Status::Transform)
+            })
+        });
+        // Dropping the writer would flush the code still buffered, also after a failure
+        match outcome.value {
+            Some(_) => out.flush()?,
+            None => drop(out.into_parts()),
+        }
+        Ok(outcome)
+    }))
+}
+
+/// Minifies JavaScript code and streams the result to a callback.
+///
+/// Behaves like `swc_minify_js_with_source_map` without an input source map, but like
+/// `swc_transpile_stream` the code generator writes through `write` in chunks of up to
+/// 64 KiB while it runs. Nothing is written if the input has errors.
+///
+/// # Parameters
+///
+/// * `code` - C string pointer containing the JavaScript to minify
+/// * `filename` - Name of the input, used as source name in the map; may be null
+/// * `mode` - Whether a source map is generated and how it is returned
+/// * `write` - Called with each chunk of generated code, see `swc_write_fn`
+/// * `ctx` - Passed to every call of `write`; may be null
+///
+/// # Returns
+///
+/// Pointer to an `swc_result` whose `code` is always null, see `swc_transpile_stream`
+///
+/// # Safety
+///
+/// This function is unsafe because:
+/// * It dereferences raw pointers
+/// * It calls `write` with `ctx`
+/// * It allocates memory that must be freed by the caller using `swc_result_free()`
+#[no_mangle]
+pub extern "C" fn swc_minify_js_stream(
+    code: *const c_char,
+    filename: *const c_char,
+    mode: swc_source_map_mode,
+    write: swc_write_fn,
+    ctx: *mut c_void,
+) -> *mut swc_result {
+    into_streamed_result_ptr(guard(|| {
+        let input = input_str(code, "code")?;
+        let mode = SourceMapMode::from(mode);
+        let mut out = writer(write, ctx)?;
+        let file = File::FileName(input_name(filename), input.into());
+        let compiler = swc_compiler::Compiler::new(Default::default());
+        let cm = compiler.cm.clone();
+        let outcome = GLOBALS.set(&Default::default(), || {
+            with_diagnostics(cm.clone(), |handler| {
+                let fm = get_js_file(file, cm)?;
+                let options = minify_options(mode, None);
+                minify::minify_to(&compiler, fm, handler, &options, mode, &mut out)
+                    .context("failed to minify")
+                    .status(Status::Minify)
+            })
+        });
+        match outcome.value {
+            Some(_) => out.flush()?,
+            None => drop(out.into_parts()),
+        }
+        Ok(outcome)
+    }))
+}
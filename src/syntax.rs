@@ -0,0 +1,154 @@
+use swc_ecma_ast::Program;
+use swc_ecma_parser::{lexer::Lexer, EsSyntax, PResult, Parser, Syntax, TsSyntax};
+
+/// Language an input is parsed as
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum InputSyntax {
+    /// Detected from the file extension, see [`InputSyntax::detect`]
+    #[default]
+    Auto,
+    /// JavaScript without JSX
+    Js,
+    /// JavaScript with JSX
+    Jsx,
+    /// TypeScript without JSX
+    Ts,
+    /// TypeScript with JSX
+    Tsx,
+    /// TypeScript declaration file
+    Dts,
+}
+
+impl InputSyntax {
+    /// Detects the syntax of a file from its name.
+    ///
+    /// * `.js`, `.mjs`, `.cjs` - [`InputSyntax::Js`]
+    /// * `.jsx` - [`InputSyntax::Jsx`]
+    /// * `.d.ts`, `.d.mts`, `.d.cts` - [`InputSyntax::Dts`]
+    /// * `.tsx` - [`InputSyntax::Tsx`]
+    /// * Anything else, including `.ts`, `.mts`, `.cts` and names without an extension -
+    ///   [`InputSyntax::Ts`]
+    pub fn detect(name: &str) -> InputSyntax {
+        let name = name.to_ascii_lowercase();
+        if [".d.ts", ".d.mts", ".d.cts"]
+            .iter()
+            .any(|ext| name.ends_with(ext))
+        {
+            return InputSyntax::Dts;
+        }
+
+        match name.rsplit_once('.').map(|(_, ext)| ext) {
+            Some("js" | "mjs" | "cjs") => InputSyntax::Js,
+            Some("jsx") => InputSyntax::Jsx,
+            Some("tsx") => InputSyntax::Tsx,
+            _ => InputSyntax::Ts,
+        }
+    }
+
+    /// Resolves [`InputSyntax::Auto`] against the name of the input.
+    pub fn resolve(self, name: &str) -> InputSyntax {
+        match self {
+            InputSyntax::Auto => InputSyntax::detect(name),
+            syntax => syntax,
+        }
+    }
+
+    pub fn is_typescript(self) -> bool {
+        matches!(self, InputSyntax::Ts | InputSyntax::Tsx | InputSyntax::Dts)
+    }
+}
+
+/// Language an input is parsed as
+///
+/// * `Auto` - Detected from the file extension: `.js`/`.mjs`/`.cjs` as JavaScript, `.jsx` as
+///   JavaScript with JSX, `.tsx` as TSX, `.d.ts` as a declaration file and everything else
+///   as TypeScript
+/// * `Js`, `Jsx`, `Ts`, `Tsx`, `Dts` - Forces the given language regardless of the name
+#[allow(non_camel_case_types)]
+#[repr(C)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum swc_syntax {
+    Auto,
+    Js,
+    Jsx,
+    Ts,
+    Tsx,
+    Dts,
+}
+
+impl From<swc_syntax> for InputSyntax {
+    fn from(syntax: swc_syntax) -> Self {
+        match syntax {
+            swc_syntax::Auto => InputSyntax::Auto,
+            swc_syntax::Js => InputSyntax::Js,
+            swc_syntax::Jsx => InputSyntax::Jsx,
+            swc_syntax::Ts => InputSyntax::Ts,
+            swc_syntax::Tsx => InputSyntax::Tsx,
+            swc_syntax::Dts => InputSyntax::Dts,
+        }
+    }
+}
+
+/// Builds the parser configuration for an already resolved syntax.
+///
+/// `tsx` forces JSX on or off for TypeScript input.
+pub(crate) fn parser_syntax(syntax: InputSyntax, tsx: Option<bool>, decorators: bool) -> Syntax {
+    match syntax {
+        InputSyntax::Js | InputSyntax::Jsx => Syntax::Es(EsSyntax {
+            jsx: syntax == InputSyntax::Jsx,
+            decorators,
+            ..Default::default()
+        }),
+        _ => Syntax::Typescript(TsSyntax {
+            tsx: tsx.unwrap_or(syntax == InputSyntax::Tsx),
+            decorators,
+            dts: syntax == InputSyntax::Dts,
+            no_early_errors: false,
+            disallow_ambiguous_jsx_like: false,
+        }),
+    }
+}
+
+/// Parses an input as an ES module, a CommonJS script or either, depending on its name.
+///
+/// `.mjs` and `.mts` files are always modules and `.cjs` files are always CommonJS. Other
+/// files are parsed as a module if they contain `import` or `export`, and as a script
+/// otherwise. `.cts` files are detected like the latter because TypeScript's
+/// `import x = require()` is only valid in a module body.
+pub(crate) fn parse_program(parser: &mut Parser<Lexer>, name: &str) -> PResult<Program> {
+    let name = name.to_ascii_lowercase();
+    if name.ends_with(".mjs") || name.ends_with(".mts") {
+        parser.parse_module().map(Program::Module)
+    } else if name.ends_with(".cjs") {
+        parser.parse_commonjs().map(Program::Script)
+    } else {
+        parser.parse_program()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_detect() {
+        let cases = [
+            ("a.js", InputSyntax::Js),
+            ("a.mjs", InputSyntax::Js),
+            ("a.cjs", InputSyntax::Js),
+            ("a.jsx", InputSyntax::Jsx),
+            ("a.ts", InputSyntax::Ts),
+            ("a.mts", InputSyntax::Ts),
+            ("a.cts", InputSyntax::Ts),
+            ("A.TSX", InputSyntax::Tsx),
+            ("types.d.ts", InputSyntax::Dts),
+            ("types.d.mts", InputSyntax::Dts),
+            ("dir.js/input", InputSyntax::Ts),
+            ("input", InputSyntax::Ts),
+        ];
+
+        for (name, syntax) in cases {
+            assert_eq!(InputSyntax::detect(name), syntax, "{name}");
+        }
+    }
+}
@@ -0,0 +1,524 @@
+#pragma once
+
+/**
+ * RAII wrappers for the C API declared in `swc.h`
+ *
+ * Everything lives in `swc::cxx`, so the C functions of the same name in `swc` stay
+ * unambiguous. Outputs are returned as `std::string`, handles are owned by `std::unique_ptr`
+ * and failures are thrown as `swc::cxx::Error`, which carries the `swc_status` and every
+ * diagnostic of the failed call.
+ *
+ * Requires C++17. Inputs are copied into NUL-terminated strings before they are handed to
+ * the library, so a `std::string_view` does not have to be NUL-terminated.
+ *
+ * ```cpp
+ * swc::cxx::TranspileOptions options;
+ * options.jsx_runtime(swc::swc_jsx_runtime::Automatic);
+ *
+ * try {
+ *     swc::cxx::Output out = swc::cxx::transpile("app.tsx", source, options);
+ *     std::cout << out.code;
+ * } catch (const swc::cxx::Error& e) {
+ *     std::cerr << e.what() << '\n';
+ * }
+ * ```
+ */
+
+#include "swc.h"
+
+#include <cstddef>
+#include <cstdint>
+#include <memory>
+#include <optional>
+#include <stdexcept>
+#include <string>
+#include <string_view>
+#include <utility>
+#include <vector>
+
+namespace swc::cxx {
+
+/// Releases strings returned by the legacy `char*` functions with `free_string`
+struct string_deleter {
+    void operator()(char* s) const noexcept { free_string(s); }
+};
+
+/// Releases results with `swc_result_free`
+struct result_deleter {
+    void operator()(swc_result* result) const noexcept { swc_result_free(result); }
+};
+
+/// Releases options with `swc_transpile_options_free`
+struct options_deleter {
+    void operator()(swc_transpile_options* options) const noexcept {
+        swc_transpile_options_free(options);
+    }
+};
+
+/// Releases sessions with `swc_session_free`
+struct session_deleter {
+    void operator()(swc_session* session) const noexcept { swc_session_free(session); }
+};
+
+/// Releases mangle caches with `swc_mangle_cache_free`
+struct mangle_cache_deleter {
+    void operator()(swc_mangle_cache* cache) const noexcept { swc_mangle_cache_free(cache); }
+};
+
+/// A string returned by one of the legacy `char*` functions, e.g. `swc::transpile`
+using unique_string = std::unique_ptr<char, string_deleter>;
+using unique_result = std::unique_ptr<swc_result, result_deleter>;
+
+/// A single error or warning, see `swc_diagnostic`
+struct Diagnostic {
+    swc_severity severity;
+    std::string message;
+    /// Empty if the diagnostic has no location
+    std::optional<std::string> file;
+    /// 1-based, or 0 if the diagnostic has no location
+    std::uint32_t line;
+    /// 0-based, counted in characters
+    std::uint32_t column;
+    std::uint32_t span_start;
+    std::uint32_t span_end;
+    std::optional<std::string> code;
+};
+
+/// Generated code together with its source map and any warnings
+struct Output {
+    std::string code;
+    /// The source map as JSON if a separate map was requested
+    std::optional<std::string> map;
+    std::vector<Diagnostic> diagnostics;
+    /// The license comments minification kept in `code`, in source order
+    std::vector<std::string> licenses;
+    /// What minification achieved, only set by `minify_with_stats`
+    std::optional<swc_minify_stats> stats;
+};
+
+/// A failed call
+///
+/// `what()` joins the messages of all error diagnostics, one per line, prefixed with
+/// `file:line:column` where known.
+class Error : public std::runtime_error {
+public:
+    Error(swc_status status, std::vector<Diagnostic> diagnostics)
+        : std::runtime_error(join(diagnostics)),
+          status_(status),
+          diagnostics_(std::move(diagnostics)) {}
+
+    swc_status status() const noexcept { return status_; }
+    const std::vector<Diagnostic>& diagnostics() const noexcept { return diagnostics_; }
+
+private:
+    static std::string join(const std::vector<Diagnostic>& diagnostics) {
+        std::string message;
+        for (const Diagnostic& d : diagnostics) {
+            if (d.severity != swc_severity::Error) {
+                continue;
+            }
+            if (!message.empty()) {
+                message += '\n';
+            }
+            if (d.file) {
+                message += *d.file + ':' + std::to_string(d.line) + ':' +
+                           std::to_string(d.column + 1) + ": ";
+            }
+            message += d.message;
+        }
+        return message;
+    }
+
+    swc_status status_;
+    std::vector<Diagnostic> diagnostics_;
+};
+
+namespace detail {
+
+inline std::optional<std::string> optional_string(const char* s) {
+    return s ? std::optional<std::string>(s) : std::nullopt;
+}
+
+inline const char* c_str(const std::optional<std::string>& s) {
+    return s ? s->c_str() : nullptr;
+}
+
+inline std::vector<Diagnostic> diagnostics(const swc_diagnostic* raw, std::size_t len) {
+    std::vector<Diagnostic> diagnostics;
+    diagnostics.reserve(len);
+    for (std::size_t i = 0; i < len; ++i) {
+        const swc_diagnostic& d = raw[i];
+        diagnostics.push_back(Diagnostic{
+            d.severity,
+            d.message,
+            optional_string(d.file),
+            d.line,
+            d.column,
+            d.span_start,
+            d.span_end,
+            optional_string(d.code),
+        });
+    }
+    return diagnostics;
+}
+
+/// Takes ownership of `raw`, returning its output or throwing its diagnostics.
+inline Output take(swc_result* raw) {
+    unique_result result(raw);
+    if (!result) {
+        throw std::bad_alloc();
+    }
+
+    std::vector<Diagnostic> diagnostics =
+        detail::diagnostics(result->diagnostics, result->diagnostics_len);
+    if (result->status != swc_status::Ok) {
+        throw Error(result->status, std::move(diagnostics));
+    }
+
+    std::optional<swc_minify_stats> stats;
+    if (result->stats) {
+        stats = *result->stats;
+    }
+    return Output{result->code, optional_string(result->map), std::move(diagnostics),
+                  std::vector<std::string>(result->licenses,
+                                           result->licenses + result->licenses_len),
+                  stats};
+}
+
+}  // namespace detail
+
+/// Settings for `transpile`, see `swc_transpile_options`
+///
+/// Setters return `*this` so they can be chained. The options are copied by every call
+/// that takes them.
+class TranspileOptions {
+public:
+    TranspileOptions() : options_(swc_transpile_options_new()) {
+        if (!options_) {
+            throw std::bad_alloc();
+        }
+    }
+
+    TranspileOptions& target(swc_es_version target) {
+        swc_transpile_options_set_target(get(), target);
+        return *this;
+    }
+
+    TranspileOptions& jsx_runtime(swc_jsx_runtime runtime) {
+        swc_transpile_options_set_jsx_runtime(get(), runtime);
+        return *this;
+    }
+
+    TranspileOptions& jsx_pragma(const std::string& pragma) {
+        swc_transpile_options_set_jsx_pragma(get(), pragma.c_str());
+        return *this;
+    }
+
+    TranspileOptions& jsx_pragma_frag(const std::string& pragma_frag) {
+        swc_transpile_options_set_jsx_pragma_frag(get(), pragma_frag.c_str());
+        return *this;
+    }
+
+    TranspileOptions& jsx_import_source(const std::string& import_source) {
+        swc_transpile_options_set_jsx_import_source(get(), import_source.c_str());
+        return *this;
+    }
+
+    TranspileOptions& jsx_development(bool development) {
+        swc_transpile_options_set_jsx_development(get(), development);
+        return *this;
+    }
+
+    TranspileOptions& jsx_throw_if_namespace(bool throw_if_namespace) {
+        swc_transpile_options_set_jsx_throw_if_namespace(get(), throw_if_namespace);
+        return *this;
+    }
+
+    TranspileOptions& decorators(bool decorators) {
+        swc_transpile_options_set_decorators(get(), decorators);
+        return *this;
+    }
+
+    TranspileOptions& syntax(swc_syntax syntax) {
+        swc_transpile_options_set_syntax(get(), syntax);
+        return *this;
+    }
+
+    TranspileOptions& tsx(bool tsx) {
+        swc_transpile_options_set_tsx(get(), tsx);
+        return *this;
+    }
+
+    TranspileOptions& newline(const std::string& newline) {
+        swc_transpile_options_set_newline(get(), newline.c_str());
+        return *this;
+    }
+
+    TranspileOptions& ascii_only(bool ascii_only) {
+        swc_transpile_options_set_ascii_only(get(), ascii_only);
+        return *this;
+    }
+
+    TranspileOptions& minify(bool minify) {
+        swc_transpile_options_set_minify(get(), minify);
+        return *this;
+    }
+
+    TranspileOptions& comments(bool comments) {
+        swc_transpile_options_set_comments(get(), comments);
+        return *this;
+    }
+
+    TranspileOptions& source_maps(swc_source_map_mode mode) {
+        swc_transpile_options_set_source_maps(get(), mode);
+        return *this;
+    }
+
+    TranspileOptions& input_source_map(const std::optional<std::string>& map) {
+        swc_transpile_options_set_input_source_map(get(), detail::c_str(map));
+        return *this;
+    }
+
+    TranspileOptions& module_format(swc_module_format format) {
+        swc_transpile_options_set_module_format(get(), format);
+        return *this;
+    }
+
+    TranspileOptions& module_interop(swc_module_interop interop) {
+        swc_transpile_options_set_module_interop(get(), interop);
+        return *this;
+    }
+
+    TranspileOptions& module_strict(bool strict) {
+        swc_transpile_options_set_module_strict(get(), strict);
+        return *this;
+    }
+
+    TranspileOptions& module_strict_mode(bool strict_mode) {
+        swc_transpile_options_set_module_strict_mode(get(), strict_mode);
+        return *this;
+    }
+
+    TranspileOptions& module_global_name(const std::optional<std::string>& name) {
+        swc_transpile_options_set_module_global_name(get(), detail::c_str(name));
+        return *this;
+    }
+
+    TranspileOptions& add_module_global(const std::string& module, const std::string& global) {
+        swc_transpile_options_add_module_global(get(), module.c_str(), global.c_str());
+        return *this;
+    }
+
+    TranspileOptions& env(bool enabled) {
+        swc_transpile_options_set_env(get(), enabled);
+        return *this;
+    }
+
+    /// `std::nullopt` removes the query; `env(false)` turns env mode off
+    TranspileOptions& env_targets(const std::optional<std::string>& query) {
+        swc_transpile_options_set_env_targets(get(), detail::c_str(query));
+        return *this;
+    }
+
+    TranspileOptions& env_engine(const std::string& engine, const std::string& version) {
+        swc_transpile_options_set_env_engine(get(), engine.c_str(), version.c_str());
+        return *this;
+    }
+
+    TranspileOptions& env_polyfills(swc_polyfill_mode mode) {
+        swc_transpile_options_set_env_polyfills(get(), mode);
+        return *this;
+    }
+
+    TranspileOptions& env_core_js(const std::optional<std::string>& version) {
+        swc_transpile_options_set_env_core_js(get(), detail::c_str(version));
+        return *this;
+    }
+
+    TranspileOptions& env_loose(bool loose) {
+        swc_transpile_options_set_env_loose(get(), loose);
+        return *this;
+    }
+
+    swc_transpile_options* get() const noexcept { return options_.get(); }
+
+private:
+    std::unique_ptr<swc_transpile_options, options_deleter> options_;
+};
+
+/// Transpiles TypeScript/TSX with the default options, see `swc_transpile`.
+inline Output transpile(std::string_view filename, std::string_view code) {
+    return detail::take(swc_transpile(std::string(filename).c_str(), std::string(code).c_str()));
+}
+
+/// Transpiles TypeScript/TSX, see `swc_transpile_with_options`.
+inline Output transpile(std::string_view filename, std::string_view code,
+                        const TranspileOptions& options) {
+    return detail::take(swc_transpile_with_options(
+        std::string(filename).c_str(), std::string(code).c_str(), options.get()));
+}
+
+/// Transpiles a TypeScript/TSX file with the default options, see `swc_transpile_file`.
+inline Output transpile_file(std::string_view path) {
+    return detail::take(swc_transpile_file(std::string(path).c_str()));
+}
+
+/// Compiles code with swc's default settings, see `swc_compile_js`.
+inline Output compile(std::string_view code) {
+    return detail::take(swc_compile_js(std::string(code).c_str()));
+}
+
+/// Compiles code with a JSON config, see `swc_compile_js_with_config`.
+inline Output compile(std::string_view code, const std::optional<std::string>& filename,
+                      const std::optional<std::string>& config_json,
+                      const std::optional<std::string>& input_source_map = std::nullopt) {
+    return detail::take(swc_compile_js_with_config(std::string(code).c_str(),
+                                                   detail::c_str(filename),
+                                                   detail::c_str(config_json),
+                                                   detail::c_str(input_source_map)));
+}
+
+/// Compiles a file with swc's default settings, see `swc_compile_file`.
+inline Output compile_file(std::string_view path) {
+    return detail::take(swc_compile_file(std::string(path).c_str()));
+}
+
+/// Compiles a file with a JSON config, see `swc_compile_file_with_config`.
+inline Output compile_file(std::string_view path, const std::optional<std::string>& config_json) {
+    return detail::take(
+        swc_compile_file_with_config(std::string(path).c_str(), detail::c_str(config_json)));
+}
+
+/// Minifies JavaScript, see `swc_minify_js`.
+inline Output minify(std::string_view code) {
+    return detail::take(swc_minify_js(std::string(code).c_str()));
+}
+
+/// Minifies JavaScript and generates a source map, see `swc_minify_js_with_source_map`.
+inline Output minify(std::string_view code, const std::optional<std::string>& filename,
+                     swc_source_map_mode mode,
+                     const std::optional<std::string>& input_source_map = std::nullopt) {
+    return detail::take(swc_minify_js_with_source_map(std::string(code).c_str(),
+                                                      detail::c_str(filename),
+                                                      detail::c_str(input_source_map), mode));
+}
+
+/// Minifies a JavaScript file, see `swc_minify_js_file`.
+inline Output minify_file(std::string_view path) {
+    return detail::take(swc_minify_js_file(std::string(path).c_str()));
+}
+
+/// Minifies JavaScript with minifier options as JSON, see `swc_minify_js_with_options`.
+inline Output minify_with_options(std::string_view code,
+                                  const std::optional<std::string>& options_json,
+                                  const std::optional<std::string>& filename = std::nullopt) {
+    return detail::take(swc_minify_js_with_options(
+        std::string(code).c_str(), detail::c_str(filename), detail::c_str(options_json)));
+}
+
+/// Minifies a JavaScript file with minifier options as JSON, see
+/// `swc_minify_js_file_with_options`.
+inline Output minify_file_with_options(std::string_view path,
+                                       const std::optional<std::string>& options_json) {
+    return detail::take(swc_minify_js_file_with_options(std::string(path).c_str(),
+                                                        detail::c_str(options_json)));
+}
+
+/// Minifies JavaScript, keeping the comments `comments` selects, see
+/// `swc_minify_js_with_comments`. `pattern` is only used with `swc_comments::Regex`.
+inline Output minify_with_comments(std::string_view code, swc_comments comments,
+                                   const std::optional<std::string>& pattern = std::nullopt,
+                                   const std::optional<std::string>& filename = std::nullopt) {
+    return detail::take(swc_minify_js_with_comments(
+        std::string(code).c_str(), detail::c_str(filename), comments, detail::c_str(pattern)));
+}
+
+/// Minifies JavaScript and measures what the minification achieved, see
+/// `swc_minify_js_with_stats`.
+inline Output minify_with_stats(std::string_view code,
+                                const std::optional<std::string>& options_json = std::nullopt,
+                                const std::optional<std::string>& filename = std::nullopt) {
+    return detail::take(swc_minify_js_with_stats(
+        std::string(code).c_str(), detail::c_str(filename), detail::c_str(options_json)));
+}
+
+/// Mangled names shared between minify calls, see `swc_mangle_cache`
+///
+/// Movable but not copyable.
+class MangleCache {
+public:
+    MangleCache() : cache_(swc_mangle_cache_new()) {}
+
+    /// Restores a cache saved with `to_json()`, throwing `Error` if `json` is not one.
+    static MangleCache from_json(std::string_view json) {
+        swc_mangle_cache* cache = swc_mangle_cache_from_json(
+            reinterpret_cast<const std::uint8_t*>(json.data()), json.size());
+        if (!cache) {
+            std::size_t len = 0;
+            const swc_diagnostic* diagnostics = swc_last_diagnostics(&len);
+            throw Error(swc_last_status(), detail::diagnostics(diagnostics, len));
+        }
+        return MangleCache(cache);
+    }
+
+    std::string to_json() const {
+        swc_buffer json = swc_mangle_cache_to_json(get());
+        std::string saved(reinterpret_cast<const char*>(json.ptr), json.len);
+        swc_buffer_free(json);
+        return saved;
+    }
+
+    swc_mangle_cache* get() const noexcept { return cache_.get(); }
+
+private:
+    explicit MangleCache(swc_mangle_cache* cache) : cache_(cache) {}
+
+    std::unique_ptr<swc_mangle_cache, mangle_cache_deleter> cache_;
+};
+
+/// Minifies JavaScript with names shared through `cache`, see `swc_minify_js_with_cache`.
+inline Output minify_with_cache(std::string_view code,
+                                const std::optional<std::string>& options_json,
+                                const MangleCache& cache,
+                                const std::optional<std::string>& filename = std::nullopt) {
+    return detail::take(swc_minify_js_with_cache(std::string(code).c_str(), detail::c_str(filename),
+                                                 detail::c_str(options_json), cache.get()));
+}
+
+/// One compiler shared between many calls, see `swc_session`
+///
+/// Movable but not copyable. Use one session per thread at a time.
+class Session {
+public:
+    explicit Session(const std::optional<std::string>& config_json = std::nullopt)
+        : session_(swc_session_new(detail::c_str(config_json), nullptr)) {}
+
+    Session(const std::optional<std::string>& config_json, const TranspileOptions& options)
+        : session_(swc_session_new(detail::c_str(config_json), options.get())) {}
+
+    Output compile(std::string_view filename, std::string_view code) {
+        return detail::take(swc_session_compile(get(), std::string(filename).c_str(),
+                                                std::string(code).c_str()));
+    }
+
+    Output minify(std::string_view filename, std::string_view code) {
+        return detail::take(swc_session_minify(get(), std::string(filename).c_str(),
+                                               std::string(code).c_str()));
+    }
+
+    Output transpile(std::string_view filename, std::string_view code) {
+        return detail::take(swc_session_transpile(get(), std::string(filename).c_str(),
+                                                  std::string(code).c_str()));
+    }
+
+    std::size_t source_file_count() const { return swc_session_source_file_count(get()); }
+
+    void reset() { swc_session_reset(get()); }
+
+    swc_session* get() const noexcept { return session_.get(); }
+
+private:
+    std::unique_ptr<swc_session, session_deleter> session_;
+};
+
+}  // namespace swc::cxx
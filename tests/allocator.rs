@@ -0,0 +1,109 @@
+//! `swc_set_allocator` only works before the first allocation of a process, so it is tested
+//! in its own test binary instead of next to the unit tests.
+
+use std::alloc::Layout;
+use std::ffi::{c_void, CStr, CString};
+use std::ptr;
+use std::sync::atomic::{AtomicBool, AtomicIsize, Ordering};
+use swc::{
+    free_string, swc_buffer, swc_buffer_free, swc_last_error, swc_last_status, swc_minify_js_buf,
+    swc_result_free, swc_set_allocator, swc_status, swc_transpile, transpile,
+};
+
+/// Number of blocks allocated through the hooks and not freed yet
+static LIVE: AtomicIsize = AtomicIsize::new(0);
+
+/// Makes the hooks refuse every allocation, like an exhausted arena
+static REFUSE: AtomicBool = AtomicBool::new(false);
+
+/// Layout of a block with room for its size in front of the `size` bytes handed out
+fn layout(size: usize) -> Layout {
+    Layout::from_size_align(size + HEADER, HEADER).unwrap()
+}
+
+const HEADER: usize = std::mem::size_of::<usize>();
+
+unsafe extern "C" fn counting_alloc(size: usize, user_data: *mut c_void) -> *mut c_void {
+    assert_eq!(user_data as usize, 42);
+    if REFUSE.load(Ordering::SeqCst) {
+        return ptr::null_mut();
+    }
+    LIVE.fetch_add(1, Ordering::SeqCst);
+    unsafe {
+        let base = std::alloc::alloc(layout(size));
+        (base as *mut usize).write(size);
+        base.add(HEADER) as *mut c_void
+    }
+}
+
+unsafe extern "C" fn counting_free(ptr: *mut c_void, user_data: *mut c_void) {
+    assert_eq!(user_data as usize, 42);
+    LIVE.fetch_sub(1, Ordering::SeqCst);
+    unsafe {
+        let base = (ptr as *mut u8).sub(HEADER);
+        std::alloc::dealloc(base, layout((base as *mut usize).read()));
+    }
+}
+
+#[test]
+fn test_outputs_use_the_host_allocator() {
+    let user_data = 42 as *mut c_void;
+    assert!(!swc_set_allocator(Some(counting_alloc), None, user_data));
+    assert!(swc_set_allocator(
+        Some(counting_alloc),
+        Some(counting_free),
+        user_data
+    ));
+
+    let file = CString::new("a.ts").unwrap();
+    let code = CString::new("const a: number = 1;").unwrap();
+    let js = transpile(file.as_ptr(), code.as_ptr());
+    assert_eq!(
+        unsafe { CStr::from_ptr(js) }.to_str().unwrap(),
+        "const a = 1;\n"
+    );
+    assert_eq!(LIVE.load(Ordering::SeqCst), 1);
+    free_string(js);
+    assert_eq!(LIVE.load(Ordering::SeqCst), 0);
+
+    // The code and the error message of a result come from the hooks as well
+    let broken = CString::new("const = 1;").unwrap();
+    let result = swc_transpile(file.as_ptr(), broken.as_ptr());
+    assert!(LIVE.load(Ordering::SeqCst) >= 2);
+    swc_result_free(result);
+
+    let mut error = swc_buffer {
+        ptr: ptr::null_mut(),
+        len: 0,
+    };
+    let input = b"let unused = 1;";
+    let minified = swc_minify_js_buf(input.as_ptr(), input.len(), &mut error);
+    assert!(!minified.ptr.is_null());
+    swc_buffer_free(minified);
+
+    // The successful call also released the message kept for `swc_last_error`
+    assert_eq!(LIVE.load(Ordering::SeqCst), 0);
+
+    // A refused allocation fails the call instead of aborting the process
+    REFUSE.store(true, Ordering::SeqCst);
+    assert!(transpile(file.as_ptr(), code.as_ptr()).is_null());
+    assert_eq!(swc_last_status(), swc_status::Internal);
+    assert!(swc_last_error().is_null());
+
+    let result = swc_transpile(file.as_ptr(), code.as_ptr());
+    let result_ref = unsafe { &*result };
+    assert_eq!(result_ref.status, swc_status::Internal);
+    assert!(result_ref.code.is_null() && result_ref.diagnostics.is_null());
+    swc_result_free(result);
+
+    let minified = swc_minify_js_buf(input.as_ptr(), input.len(), &mut error);
+    assert!(minified.ptr.is_null() && error.ptr.is_null());
+    REFUSE.store(false, Ordering::SeqCst);
+    assert_eq!(LIVE.load(Ordering::SeqCst), 0);
+
+    let result = swc_transpile(file.as_ptr(), code.as_ptr());
+    assert_eq!(unsafe { &*result }.status, swc_status::Ok);
+    swc_result_free(result);
+
+    assert!(!swc_set_allocator(None, None, ptr::null_mut()));
+}
@@ -0,0 +1,74 @@
+/* Compiled by `test_c_header_compiles` to check that swc_c.h is valid C; never run. */
+
+#include "swc_c.h"
+
+#include <stdio.h>
+
+static void to_file(void *ctx, const char *data, size_t len) {
+    fwrite(data, 1, len, (FILE *)ctx);
+}
+
+static void log_diagnostic(void *user_data, const swc_diagnostic *d, const char *frame) {
+    (void)user_data;
+    fprintf(stderr, "%s\n", frame ? frame : d->message);
+}
+
+int main(void) {
+    swc_set_diagnostic_callback(log_diagnostic, NULL);
+
+    swc_transpile_options *options = swc_transpile_options_new();
+    swc_transpile_options_set_jsx_runtime(options, swc_jsx_runtime_Automatic);
+    swc_transpile_options_set_module_format(options, swc_module_format_CommonJs);
+    swc_transpile_options_set_source_maps(options, swc_source_map_mode_Separate);
+
+    swc_result *result = swc_transpile_with_options("a.ts", "const a: number = 1;", options);
+    swc_transpile_options_free(options);
+
+    if (result->status == swc_status_Ok) {
+        puts(result->code);
+    } else {
+        for (size_t i = 0; i < result->diagnostics_len; i++) {
+            const swc_diagnostic *d = &result->diagnostics[i];
+            if (d->severity == swc_severity_Error) {
+                fprintf(stderr, "%s:%u:%u: %s\n", d->file, d->line, d->column + 1, d->message);
+            }
+        }
+    }
+    swc_result_free(result);
+
+    result = swc_transpile_stream("b.ts", "let b: string;", NULL, to_file, stdout);
+    swc_result_free(result);
+    result = swc_minify_js_stream("var b = 1;", "b.js", swc_source_map_mode_Inline, to_file, stdout);
+    swc_result_free(result);
+
+    swc_mangle_cache *cache = swc_mangle_cache_new();
+    result = swc_minify_js_with_cache("var a = 1;", "a.js", "{\"toplevel\": true}", cache);
+    swc_result_free(result);
+    swc_buffer saved = swc_mangle_cache_to_json(cache);
+    swc_mangle_cache_free(cache);
+    cache = swc_mangle_cache_from_json(saved.ptr, saved.len);
+    swc_buffer_free(saved);
+    swc_mangle_cache_free(cache);
+
+    result = swc_minify_js_with_comments("/*! MIT */ var a = 1;", "a.js", swc_comments_Licenses, NULL);
+    for (size_t i = 0; i < result->licenses_len; i++) {
+        puts(result->licenses[i]);
+    }
+    swc_result_free(result);
+
+    result = swc_minify_js_with_stats("var answer = 42;", "a.js", NULL);
+    if (result->stats) {
+        printf("%zu -> %zu bytes, %zu gzip, %zu brotli, %zu mangled, %f ms\n",
+               result->stats->input_size, result->stats->output_size, result->stats->gzip_size,
+               result->stats->brotli_size, result->stats->mangled,
+               result->stats->compress_ms + result->stats->mangle_ms + result->stats->codegen_ms);
+    }
+    swc_result_free(result);
+
+    swc_buffer error = {0};
+    swc_buffer js = swc_minify_js_buf((const uint8_t *)"let a = 1;", 10, &error);
+    swc_buffer_free(js);
+    swc_buffer_free(error);
+
+    return swc_last_status() == swc_status_Ok ? 0 : 1;
+}
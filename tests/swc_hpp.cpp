@@ -0,0 +1,93 @@
+// Compiled by `test_cxx_header_compiles` to keep swc.hpp in sync with the exports in swc.h.
+// Every wrapper is called once; the program is never run.
+
+#include "swc.hpp"
+
+#include <iostream>
+#include <string>
+#include <string_view>
+#include <utility>
+
+int main() {
+    std::string_view code = "const a: number = 1;";
+
+    swc::cxx::TranspileOptions options;
+    options.target(swc::swc_es_version::Es2020)
+        .jsx_runtime(swc::swc_jsx_runtime::Automatic)
+        .jsx_pragma("h")
+        .jsx_pragma_frag("Fragment")
+        .jsx_import_source("preact")
+        .jsx_development(false)
+        .jsx_throw_if_namespace(true)
+        .decorators(true)
+        .syntax(swc::swc_syntax::Auto)
+        .tsx(false)
+        .newline("\n")
+        .ascii_only(false)
+        .minify(false)
+        .comments(true)
+        .source_maps(swc::swc_source_map_mode::Separate)
+        .input_source_map(std::nullopt)
+        .module_format(swc::swc_module_format::Iife)
+        .module_interop(swc::swc_module_interop::Swc)
+        .module_strict(false)
+        .module_strict_mode(true)
+        .module_global_name("App")
+        .add_module_global("react", "React")
+        .env_targets("defaults")
+        .env_engine("chrome", "80")
+        .env_polyfills(swc::swc_polyfill_mode::Usage)
+        .env_core_js("3.38")
+        .env_loose(false);
+
+    try {
+        swc::cxx::Output out = swc::cxx::transpile("a.ts", code);
+        out = swc::cxx::transpile("a.ts", code, options);
+        out = swc::cxx::transpile_file("a.ts");
+        out = swc::cxx::compile(code);
+        out = swc::cxx::compile(code, "a.ts", R"({"minify":true})", std::nullopt);
+        out = swc::cxx::compile_file("a.ts");
+        out = swc::cxx::compile_file("a.ts", std::nullopt);
+        out = swc::cxx::minify(code);
+        out = swc::cxx::minify(code, "a.js", swc::swc_source_map_mode::Inline);
+        out = swc::cxx::minify_file("a.js");
+        out = swc::cxx::minify_with_options(code, R"({"mangle":false})");
+        out = swc::cxx::minify_file_with_options("a.js", std::nullopt);
+
+        swc::cxx::MangleCache cache = swc::cxx::MangleCache::from_json(R"({"vars": {}})");
+        out = swc::cxx::minify_with_cache(code, R"({"toplevel":true})", cache, "a.js");
+        std::string saved = cache.to_json();
+
+        out = swc::cxx::minify_with_comments(code, swc::swc_comments::Regex, "^!|@license");
+        for (const std::string& license : out.licenses) {
+            std::cout << license << '\n';
+        }
+
+        out = swc::cxx::minify_with_stats(code, R"({"mangle":{"toplevel":true}})", "a.js");
+        if (out.stats) {
+            std::cout << out.stats->input_size << out.stats->output_size << out.stats->gzip_size
+                      << out.stats->brotli_size << out.stats->mangled << out.stats->compress_ms
+                      << out.stats->mangle_ms << out.stats->codegen_ms << '\n';
+        }
+
+        swc::cxx::Session session(std::nullopt, options);
+        swc::cxx::Session moved = std::move(session);
+        out = moved.compile("a.ts", code);
+        out = moved.minify("a.js", code);
+        out = moved.transpile("a.ts", code);
+        if (moved.source_file_count() > 100) {
+            moved.reset();
+        }
+
+        for (const swc::cxx::Diagnostic& d : out.diagnostics) {
+            std::cerr << d.message << d.file.value_or("") << d.line << d.column << d.span_start
+                      << d.span_end << d.code.value_or("") << '\n';
+        }
+        std::cout << out.code << out.map.value_or("") << '\n';
+    } catch (const swc::cxx::Error& e) {
+        std::cerr << static_cast<int>(e.status()) << e.what() << e.diagnostics().size() << '\n';
+    }
+
+    swc::cxx::unique_string legacy(swc::transpile("a.ts", "const a = 1;"));
+    return legacy ? 0 : 1;
+}